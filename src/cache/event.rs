@@ -1,5 +1,7 @@
 use std::collections::{HashSet, VecDeque};
 
+#[cfg(feature = "gateway")]
+use super::Settings;
 use super::{Cache, CacheUpdate};
 use crate::internal::prelude::*;
 use crate::model::channel::{GuildChannel, Message};
@@ -36,6 +38,48 @@ use crate::model::guild::{Guild, GuildMemberFlags, Member, MemberGeneratedFlags,
 use crate::model::user::{CurrentUser, OnlineStatus};
 use crate::model::voice::VoiceState;
 
+// The names of the gateway events that update the cached guilds, along with their channels,
+// members, roles and presences.
+#[cfg(feature = "gateway")]
+const GUILD_UPDATE_EVENTS: &[&str] = &[
+    "CHANNEL_CREATE",
+    "CHANNEL_DELETE",
+    "CHANNEL_UPDATE",
+    "GUILD_CREATE",
+    "GUILD_DELETE",
+    "GUILD_EMOJIS_UPDATE",
+    "GUILD_MEMBER_ADD",
+    "GUILD_MEMBER_REMOVE",
+    "GUILD_MEMBER_UPDATE",
+    "GUILD_MEMBERS_CHUNK",
+    "GUILD_ROLE_CREATE",
+    "GUILD_ROLE_DELETE",
+    "GUILD_ROLE_UPDATE",
+    "GUILD_STICKERS_UPDATE",
+    "MESSAGE_CREATE",
+    "PRESENCE_UPDATE",
+    "THREAD_CREATE",
+    "THREAD_DELETE",
+    "THREAD_UPDATE",
+    "VOICE_CHANNEL_STATUS_UPDATE",
+    "VOICE_STATE_UPDATE",
+];
+
+// The names of the gateway events that update the cached messages.
+#[cfg(feature = "gateway")]
+const MESSAGE_UPDATE_EVENTS: &[&str] =
+    &["CHANNEL_DELETE", "GUILD_DELETE", "MESSAGE_CREATE", "MESSAGE_UPDATE"];
+
+/// Returns the names of the gateway events that update a cache with the given settings, which
+/// must always be deserialized.
+#[cfg(feature = "gateway")]
+pub(crate) fn cache_update_events(settings: &Settings) -> impl Iterator<Item = &'static str> {
+    // The current user and shard data are always cached.
+    let guild_events = if settings.cache_guilds { GUILD_UPDATE_EVENTS } else { &[] };
+    let message_events = if settings.max_messages > 0 { MESSAGE_UPDATE_EVENTS } else { &[] };
+    ["READY", "USER_UPDATE"].iter().chain(guild_events).chain(message_events).copied()
+}
+
 impl CacheUpdate for ChannelCreateEvent {
     type Output = GuildChannel;
 
@@ -39,6 +39,8 @@ use mini_moka::sync::Cache as MokaCache;
 use parking_lot::RwLock;
 
 pub use self::cache_update::CacheUpdate;
+#[cfg(feature = "gateway")]
+pub(crate) use self::event::cache_update_events;
 pub use self::settings::Settings;
 use crate::model::prelude::*;
 
@@ -438,6 +440,9 @@ impl Cache {
     /// Sets the maximum amount of messages per channel to cache.
     ///
     /// By default, no messages will be cached.
+    ///
+    /// **Note**: Shards only receive the message events that update the cache if messages are
+    /// cached when they are started, so enabling this affects shards started afterwards.
     pub fn set_max_messages(&self, max: usize) {
         // Check to see if cache has to be truncated
         if max < self.settings.read().max_messages {
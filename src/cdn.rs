@@ -0,0 +1,412 @@
+//! Building URLs for images and other assets hosted on Discord's CDN.
+//!
+//! Model methods such as [`User::avatar_url`] use [`CdnUrl`] with fixed settings. Build a
+//! [`CdnUrl`] directly to request a specific format or size:
+//!
+//! ```rust
+//! use serenity::cdn::{Asset, CdnUrl, ImageFormat};
+//! use serenity::model::id::UserId;
+//!
+//! let asset = Asset::UserAvatar {
+//!     user_id: UserId::new(210),
+//!     hash: "f1eff024d9c85339c877985229ed8fec".parse().unwrap(),
+//! };
+//! let url = CdnUrl::new(asset).format(ImageFormat::Png).size(256).build().unwrap();
+//!
+//! assert_eq!(
+//!     url,
+//!     "https://cdn.discordapp.com/avatars/210/f1eff024d9c85339c877985229ed8fec.png?size=256"
+//! );
+//! ```
+//!
+//! [Discord docs](https://discord.com/developers/docs/reference#image-formatting).
+//!
+//! [`User::avatar_url`]: crate::model::user::User::avatar_url
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::constants;
+use crate::model::id::{EmojiId, GuildId, RoleId, StickerId, StickerPackBannerId, UserId};
+use crate::model::misc::ImageHash;
+use crate::model::sticker::StickerFormatType;
+
+/// The ID of the application that owns the sticker pack banners.
+const STICKER_PACK_APPLICATION_ID: u64 = 710982414301790216;
+
+/// A file format that an asset can be requested in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+    /// Only available for animated assets.
+    Gif,
+    /// Only available for Lottie stickers.
+    Lottie,
+}
+
+impl ImageFormat {
+    /// Returns the file extension used for the format.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::WebP => "webp",
+            Self::Gif => "gif",
+            Self::Lottie => "json",
+        }
+    }
+}
+
+/// An asset on Discord's CDN, along with the identifiers needed to locate it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Asset {
+    UserAvatar {
+        user_id: UserId,
+        hash: ImageHash,
+    },
+    /// The default avatar of users without one, where `index` is determined by the user's
+    /// discriminator or Id.
+    DefaultUserAvatar {
+        index: u16,
+    },
+    UserBanner {
+        user_id: UserId,
+        hash: ImageHash,
+    },
+    GuildMemberAvatar {
+        guild_id: GuildId,
+        user_id: UserId,
+        hash: ImageHash,
+    },
+    GuildIcon {
+        guild_id: GuildId,
+        hash: ImageHash,
+    },
+    GuildSplash {
+        guild_id: GuildId,
+        hash: ImageHash,
+    },
+    GuildBanner {
+        guild_id: GuildId,
+        hash: ImageHash,
+    },
+    RoleIcon {
+        role_id: RoleId,
+        hash: ImageHash,
+    },
+    CustomEmoji {
+        emoji_id: EmojiId,
+        animated: bool,
+    },
+    Sticker {
+        sticker_id: StickerId,
+        format: StickerFormatType,
+    },
+    StickerPackBanner {
+        banner_asset_id: StickerPackBannerId,
+    },
+}
+
+impl Asset {
+    /// Returns whether the asset is animated, and so can be requested as a GIF.
+    #[must_use]
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Self::UserAvatar {
+                hash, ..
+            }
+            | Self::UserBanner {
+                hash, ..
+            }
+            | Self::GuildMemberAvatar {
+                hash, ..
+            }
+            | Self::GuildIcon {
+                hash, ..
+            }
+            | Self::GuildSplash {
+                hash, ..
+            }
+            | Self::GuildBanner {
+                hash, ..
+            }
+            | Self::RoleIcon {
+                hash, ..
+            } => hash.is_animated(),
+            Self::CustomEmoji {
+                animated, ..
+            } => *animated,
+            Self::Sticker {
+                format, ..
+            } => *format == StickerFormatType::Gif,
+            Self::DefaultUserAvatar {
+                ..
+            }
+            | Self::StickerPackBanner {
+                ..
+            } => false,
+        }
+    }
+
+    /// Returns the format used when none is set on a [`CdnUrl`]: GIF for animated assets,
+    /// otherwise the format the asset is natively stored in.
+    ///
+    /// Returns [`None`] for stickers with an unknown format.
+    #[must_use]
+    pub fn default_format(&self) -> Option<ImageFormat> {
+        let format = match self {
+            Self::Sticker {
+                format, ..
+            } => match *format {
+                StickerFormatType::Png | StickerFormatType::Apng => ImageFormat::Png,
+                StickerFormatType::Lottie => ImageFormat::Lottie,
+                StickerFormatType::Gif => ImageFormat::Gif,
+                StickerFormatType(_) => return None,
+            },
+            _ if self.is_animated() => ImageFormat::Gif,
+            Self::DefaultUserAvatar {
+                ..
+            }
+            | Self::CustomEmoji {
+                ..
+            } => ImageFormat::Png,
+            _ => ImageFormat::WebP,
+        };
+
+        Some(format)
+    }
+
+    /// Returns whether the asset can be requested in the given format.
+    #[must_use]
+    pub fn supports_format(&self, format: ImageFormat) -> bool {
+        match self {
+            Self::DefaultUserAvatar {
+                ..
+            } => format == ImageFormat::Png,
+            Self::Sticker {
+                ..
+            } => self.default_format() == Some(format),
+            Self::StickerPackBanner {
+                ..
+            } => matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP),
+            _ => match format {
+                ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP => true,
+                ImageFormat::Gif => self.is_animated(),
+                ImageFormat::Lottie => false,
+            },
+        }
+    }
+
+    fn path(&self) -> String {
+        match self {
+            Self::UserAvatar {
+                user_id,
+                hash,
+            } => format!("/avatars/{user_id}/{hash}"),
+            Self::DefaultUserAvatar {
+                index,
+            } => format!("/embed/avatars/{index}"),
+            Self::UserBanner {
+                user_id,
+                hash,
+            } => format!("/banners/{user_id}/{hash}"),
+            Self::GuildMemberAvatar {
+                guild_id,
+                user_id,
+                hash,
+            } => format!("/guilds/{guild_id}/users/{user_id}/avatars/{hash}"),
+            Self::GuildIcon {
+                guild_id,
+                hash,
+            } => format!("/icons/{guild_id}/{hash}"),
+            Self::GuildSplash {
+                guild_id,
+                hash,
+            } => format!("/splashes/{guild_id}/{hash}"),
+            Self::GuildBanner {
+                guild_id,
+                hash,
+            } => format!("/banners/{guild_id}/{hash}"),
+            Self::RoleIcon {
+                role_id,
+                hash,
+            } => format!("/role-icons/{role_id}/{hash}"),
+            Self::CustomEmoji {
+                emoji_id, ..
+            } => format!("/emojis/{emoji_id}"),
+            Self::Sticker {
+                sticker_id, ..
+            } => format!("/stickers/{sticker_id}"),
+            Self::StickerPackBanner {
+                banner_asset_id,
+            } => format!("/app-assets/{STICKER_PACK_APPLICATION_ID}/store/{banner_asset_id}"),
+        }
+    }
+}
+
+/// A builder for the URL of an [`Asset`] on Discord's CDN.
+///
+/// [`Self::build`] points the URL to [`constants::CDN_URL`]. Use [`Http::build_cdn_url`] to point
+/// it to the CDN URL configured through [`HttpBuilder::cdn_url`] instead.
+///
+/// [`Http::build_cdn_url`]: crate::http::Http::build_cdn_url
+/// [`HttpBuilder::cdn_url`]: crate::http::HttpBuilder::cdn_url
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct CdnUrl {
+    asset: Asset,
+    format: Option<ImageFormat>,
+    size: Option<u16>,
+}
+
+impl CdnUrl {
+    /// Creates a URL builder for the given asset, using its [default format] and no size.
+    ///
+    /// [default format]: Asset::default_format
+    pub fn new(asset: Asset) -> Self {
+        Self {
+            asset,
+            format: None,
+            size: None,
+        }
+    }
+
+    /// Sets the format to request the asset in.
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the size to request the asset in, which must be a power of two between 16 and 4096.
+    pub fn size(mut self, size: u16) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Returns the asset the URL points to.
+    #[must_use]
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+
+    /// Builds the URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CdnUrlError`] if the asset is not available in the set format, or the set size
+    /// is invalid.
+    pub fn build(&self) -> Result<String, CdnUrlError> {
+        self.build_with_base_url(constants::CDN_URL)
+    }
+
+    /// Builds the URL, pointing to the given CDN URL instead of [`constants::CDN_URL`], e.g.
+    /// `http://127.0.0.1:3000`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CdnUrlError`] if the asset is not available in the set format, or the set size
+    /// is invalid.
+    pub fn build_with_base_url(&self, base_url: &str) -> Result<String, CdnUrlError> {
+        let format = match self.format {
+            Some(format) => format,
+            None => self.asset.default_format().ok_or(CdnUrlError::UnknownFormat)?,
+        };
+
+        if !self.asset.supports_format(format) {
+            return Err(CdnUrlError::UnsupportedFormat(format));
+        }
+
+        let base_url = base_url.trim_end_matches('/');
+        let mut url = format!("{base_url}{}.{}", self.asset.path(), format.extension());
+        if let Some(size) = self.size {
+            if !(16..=4096).contains(&size) || !size.is_power_of_two() {
+                return Err(CdnUrlError::InvalidSize(size));
+            }
+
+            url.push_str("?size=");
+            url.push_str(&size.to_string());
+        }
+
+        Ok(url)
+    }
+}
+
+/// An error returned when building a [`CdnUrl`] fails.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CdnUrlError {
+    /// The asset is not available in the given format.
+    UnsupportedFormat(ImageFormat),
+    /// The given size is not a power of two between 16 and 4096.
+    InvalidSize(u16),
+    /// No format was set, and the asset's format is unknown.
+    UnknownFormat,
+}
+
+impl fmt::Display for CdnUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => {
+                write!(f, "Asset is not available as {}", format.extension())
+            },
+            Self::InvalidSize(size) => write!(f, "Invalid asset size {size}"),
+            Self::UnknownFormat => f.write_str("Asset format is unknown"),
+        }
+    }
+}
+
+impl StdError for CdnUrlError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cdn_url() {
+        let hash: ImageHash = "a_e3c0db7f38777778fb43081f8746ebc9".parse().unwrap();
+        let asset = Asset::GuildIcon {
+            guild_id: GuildId::new(1),
+            hash,
+        };
+
+        assert_eq!(
+            CdnUrl::new(asset).build().unwrap(),
+            "https://cdn.discordapp.com/icons/1/a_e3c0db7f38777778fb43081f8746ebc9.gif"
+        );
+        assert_eq!(CdnUrl::new(asset).size(100).build(), Err(CdnUrlError::InvalidSize(100)));
+
+        let asset = Asset::DefaultUserAvatar {
+            index: 3,
+        };
+        assert_eq!(
+            CdnUrl::new(asset).format(ImageFormat::WebP).build(),
+            Err(CdnUrlError::UnsupportedFormat(ImageFormat::WebP))
+        );
+
+        let asset = Asset::Sticker {
+            sticker_id: StickerId::new(2),
+            format: StickerFormatType::Lottie,
+        };
+        assert_eq!(
+            CdnUrl::new(asset).build().unwrap(),
+            "https://cdn.discordapp.com/stickers/2.json"
+        );
+    }
+
+    #[test]
+    fn test_cdn_base_url() {
+        let asset = Asset::Sticker {
+            sticker_id: StickerId::new(2),
+            format: StickerFormatType::Png,
+        };
+        assert_eq!(
+            CdnUrl::new(asset).size(64).build_with_base_url("http://127.0.0.1:3000/").unwrap(),
+            "http://127.0.0.1:3000/stickers/2.png?size=64"
+        );
+    }
+}
@@ -11,6 +11,15 @@ pub const EMBED_MAX_COUNT: usize = 10;
 /// The maximum number of stickers in a message.
 pub const STICKER_MAX_COUNT: usize = 3;
 
+/// The version of Discord's HTTP API used by the library by default.
+pub const API_VERSION: u8 = api_version!();
+
+/// The URL of Discord's HTTP API which requests are sent to by default, including the API version.
+pub const API_URL: &str = api_url!();
+
+/// The URL of Discord's CDN which asset URLs point to by default.
+pub const CDN_URL: &str = "https://cdn.discordapp.com";
+
 /// The gateway version used by the library. The gateway URL is retrieved via the REST API.
 pub const GATEWAY_VERSION: u8 = 10;
 
@@ -53,6 +53,30 @@ pub enum Error {
     Token(TokenError),
 }
 
+impl Error {
+    /// Returns true when Discord rejected a request because the current user lacks the required
+    /// permissions or access.
+    #[must_use]
+    pub fn is_missing_permissions(&self) -> bool {
+        match self {
+            #[cfg(feature = "http")]
+            Self::Http(e) => e.is_missing_permissions(),
+            _ => false,
+        }
+    }
+
+    /// Returns true when Discord rejected a request because a resource it refers to, such as a
+    /// channel or message, does not exist.
+    #[must_use]
+    pub fn is_unknown_resource(&self) -> bool {
+        match self {
+            #[cfg(feature = "http")]
+            Self::Http(e) => e.is_unknown_resource(),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(feature = "gateway")]
 impl From<GatewayError> for Error {
     fn from(e: GatewayError) -> Error {
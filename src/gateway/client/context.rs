@@ -1,9 +1,10 @@
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(feature = "cache")]
 pub use crate::cache::Cache;
-use crate::gateway::{ActivityData, ShardMessenger, ShardRunner};
+use crate::gateway::{ActivityData, ChunkGuildFilter, GuildMembers, ShardMessenger};
 use crate::http::{CacheHttp, Http};
 use crate::model::prelude::*;
 
@@ -62,15 +63,15 @@ impl Context {
     /// Create a new Context to be passed to an event handler.
     pub(crate) fn new(
         data: Arc<dyn std::any::Any + Send + Sync>,
-        runner: &ShardRunner,
+        shard: ShardMessenger,
         shard_id: ShardId,
         http: Arc<Http>,
         #[cfg(feature = "cache")] cache: Arc<Cache>,
     ) -> Context {
         Context {
-            shard: ShardMessenger::new(runner),
-            shard_id,
             data,
+            shard,
+            shard_id,
             http,
             #[cfg(feature = "cache")]
             cache,
@@ -322,6 +323,27 @@ impl Context {
         self.shard.set_presence(activity, status);
     }
 
+    /// Requests members of a [`Guild`] from the gateway, and waits for all of them to be received.
+    ///
+    /// Refer to [`ShardMessenger::request_members`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::MemberChunksTimedOut`] if not all members were received before the
+    /// timeout.
+    ///
+    /// [`GatewayError::MemberChunksTimedOut`]: crate::gateway::GatewayError::MemberChunksTimedOut
+    pub async fn request_members(
+        &self,
+        guild_id: GuildId,
+        limit: Option<u16>,
+        presences: bool,
+        filter: ChunkGuildFilter,
+        timeout: Duration,
+    ) -> Result<GuildMembers> {
+        self.shard.request_members(guild_id, limit, presences, filter, timeout).await
+    }
+
     /// Gets all emojis for the current application.
     ///
     /// # Errors
@@ -1,17 +1,29 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use futures::future::BoxFuture;
+use futures::{FutureExt as _, SinkExt as _};
+use parking_lot::Mutex;
+use tokio::sync::{Notify, Semaphore};
+use tracing::debug;
+
 use super::event_handler::{EventHandler, RawEventHandler};
+use super::event_stream::EventStreamSender;
 use super::{Context, FullEvent};
 #[cfg(feature = "cache")]
 use crate::cache::{Cache, CacheUpdate};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
 use crate::internal::prelude::*;
+use crate::internal::tokio::spawn_named;
 use crate::model::channel::ChannelType;
 use crate::model::event::Event;
 use crate::model::guild::Member;
-#[cfg(feature = "cache")]
-use crate::model::id::GuildId;
+use crate::model::id::{ChannelId, GuildId};
 
 #[cfg(feature = "cache")]
 macro_rules! if_cache {
@@ -39,6 +51,212 @@ macro_rules! update_cache {
     ($cache:ident, $event:ident) => {};
 }
 
+/// How events received by the shards are dispatched to the event handlers and the framework.
+///
+/// This does not apply to the [`EventStream`], which always receives events in order.
+///
+/// [`EventStream`]: super::EventStream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DispatchMode {
+    /// Each event is handled in its own task as soon as it is received, so any number of events
+    /// may be handled at once, in any order.
+    #[default]
+    Unbounded,
+    /// Each event is handled in its own task, but at most the given number of events are handled
+    /// at once across all shards.
+    ///
+    /// Once the limit is reached, shards wait for an event to finish being handled before reading
+    /// further events. Shards don't heartbeat while they wait, so handlers that take longer than
+    /// the heartbeat interval of roughly 40 seconds make Discord close the connections.
+    Bounded(NonZeroUsize),
+    /// Events of the same guild are handled one after another, in the order they were received,
+    /// while events of different guilds are handled at the same time.
+    ///
+    /// Events outside of a guild, such as direct messages, are handled in order with each other.
+    /// A handler that never returns holds up all later events of its guild.
+    OrderedPerGuild,
+    /// Like [`Self::OrderedPerGuild`], but events of the same channel or thread are handled in
+    /// order, while events of different channels are handled at the same time.
+    ///
+    /// Events that don't belong to a channel, such as [`Event::GuildMemberAdd`], are handled in
+    /// order with the other events of their guild that don't belong to a channel either.
+    OrderedPerChannel,
+}
+
+/// The events that have to be handled in order with each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Lane {
+    Channel(ChannelId),
+    Guild(GuildId),
+    Global,
+}
+
+type LaneQueues = HashMap<Lane, VecDeque<BoxFuture<'static, ()>>>;
+
+/// Spawns the tasks handling events according to a [`DispatchMode`], shared by all shards of a
+/// [`ShardManager`].
+///
+/// [`ShardManager`]: crate::gateway::ShardManager
+pub(crate) struct Dispatcher {
+    mode: DispatchMode,
+    permits: Option<Arc<Semaphore>>,
+    lanes: Arc<Mutex<LaneQueues>>,
+    in_flight: Arc<InFlight>,
+    closed: AtomicBool,
+}
+
+/// Counts the events that are waiting to be handled or being handled.
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+/// Counts an event as in flight until dropped, which happens even if its handler panics.
+struct InFlightGuard(Arc<InFlight>);
+
+impl InFlightGuard {
+    fn new(in_flight: &Arc<InFlight>) -> Self {
+        in_flight.count.fetch_add(1, Ordering::AcqRel);
+        Self(Arc::clone(in_flight))
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl Dispatcher {
+    pub(crate) fn new(mode: DispatchMode) -> Self {
+        let permits = match mode {
+            DispatchMode::Bounded(limit) => Some(Arc::new(Semaphore::new(limit.get()))),
+            _ => None,
+        };
+
+        Self {
+            mode,
+            permits,
+            lanes: Arc::default(),
+            in_flight: Arc::default(),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Stops dispatching events; events dispatched afterwards are dropped.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+
+    /// Returns the number of events that are waiting to be handled or being handled.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.count.load(Ordering::Acquire)
+    }
+
+    /// Waits until all events that were dispatched have been handled.
+    pub(crate) async fn wait_idle(&self) {
+        loop {
+            let idle = self.in_flight.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Returns the lane the event has to be handled in.
+    pub(crate) fn lane(&self, event: &Event) -> Lane {
+        let channel_id = match self.mode {
+            DispatchMode::OrderedPerChannel => event.channel_id(),
+            _ => None,
+        };
+
+        match (channel_id, event.guild_id()) {
+            (Some(channel_id), _) => Lane::Channel(channel_id),
+            (None, Some(guild_id)) => Lane::Guild(guild_id),
+            (None, None) => Lane::Global,
+        }
+    }
+
+    /// Spawns a task handling an event of the given lane.
+    ///
+    /// In [`DispatchMode::Bounded`], this waits until fewer than the limit of events are being
+    /// handled. Once the dispatcher is closed, the event is dropped instead.
+    pub(crate) async fn dispatch(&self, lane: Lane, future: BoxFuture<'static, ()>) {
+        if self.closed.load(Ordering::Acquire) {
+            debug!("Dropping event dispatched during shutdown");
+            return;
+        }
+
+        let guard = InFlightGuard::new(&self.in_flight);
+        let future = Box::pin(async move {
+            future.await;
+            drop(guard);
+        });
+
+        match self.mode {
+            DispatchMode::Unbounded => {
+                spawn_named("shard_runner::dispatch", future);
+            },
+            DispatchMode::Bounded(_) => {
+                let permits = self.permits.as_ref().expect("bounded dispatchers have permits");
+                let permit = Arc::clone(permits)
+                    .acquire_owned()
+                    .await
+                    .expect("the semaphore is never closed");
+
+                spawn_named("shard_runner::dispatch", async move {
+                    future.await;
+                    drop(permit);
+                });
+            },
+            DispatchMode::OrderedPerGuild | DispatchMode::OrderedPerChannel => {
+                self.dispatch_ordered(lane, future);
+            },
+        }
+    }
+
+    fn dispatch_ordered(&self, lane: Lane, future: BoxFuture<'static, ()>) {
+        {
+            let mut lanes = self.lanes.lock();
+            if let Some(queue) = lanes.get_mut(&lane) {
+                // The lane already has a task, which handles the event once it is done with the
+                // ones before it.
+                queue.push_back(future);
+                return;
+            }
+
+            lanes.insert(lane, VecDeque::new());
+        }
+
+        let lanes = Arc::clone(&self.lanes);
+        spawn_named("shard_runner::dispatch_ordered", async move {
+            let mut next = Some(future);
+            while let Some(future) = next {
+                // A panicking handler must not hold up the rest of the lane.
+                drop(AssertUnwindSafe(future).catch_unwind().await);
+
+                let mut lanes = lanes.lock();
+                let queue = lanes.get_mut(&lane).expect("lanes are removed by their task");
+                next = queue.pop_front();
+                if next.is_none() {
+                    lanes.remove(&lane);
+                }
+            }
+        });
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher").field("mode", &self.mode).finish_non_exhaustive()
+    }
+}
+
 /// Calls the user's event handlers and the framework handler.
 ///
 /// This MUST be called from a different task to the recv_event loop, to allow for
@@ -60,6 +278,78 @@ pub(crate) async fn dispatch_model(
         event,
     );
 
+    dispatch_full_event(
+        context,
+        full_event,
+        extra_event,
+        #[cfg(feature = "framework")]
+        framework,
+        event_handler,
+    )
+    .await;
+}
+
+/// Sends the event to the event stream, and has the user's event handlers and the framework
+/// handler called from a separate task, like [`dispatch_model`] does.
+///
+/// This MUST be called from the recv_event loop, so that events are sent to the stream in order
+/// and a full stream holds up the shard.
+pub(crate) async fn dispatch_to_stream(
+    event: Event,
+    context: Context,
+    #[cfg(feature = "framework")] framework: Option<Arc<dyn Framework>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    event_stream: &mut EventStreamSender,
+) {
+    if let Some(raw_handler) = raw_event_handler {
+        let context = context.clone();
+        let event = event.clone();
+        spawn_named("dispatch::raw_event_handler", async move {
+            raw_handler.raw_event(context, &event).await;
+        });
+    }
+
+    let (full_event, extra_event) = update_cache_with_event(
+        #[cfg(feature = "cache")]
+        &context.cache,
+        event,
+    );
+
+    #[cfg(feature = "framework")]
+    let has_framework = framework.is_some();
+    #[cfg(not(feature = "framework"))]
+    let has_framework = false;
+
+    if has_framework || event_handler.is_some() {
+        spawn_named(
+            "dispatch::event_handler",
+            dispatch_full_event(
+                context.clone(),
+                full_event.clone(),
+                extra_event.clone(),
+                #[cfg(feature = "framework")]
+                framework,
+                event_handler,
+            ),
+        );
+    }
+
+    for event in extra_event.into_iter().chain([full_event]) {
+        // Only fails if the stream has been dropped, in which case the handlers still get events.
+        if event_stream.send((context.clone(), event)).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn dispatch_full_event(
+    context: Context,
+    full_event: FullEvent,
+    extra_event: Option<FullEvent>,
+    #[cfg(feature = "framework")] framework: Option<Arc<dyn Framework>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+) {
     #[cfg(feature = "framework")]
     if let Some(framework) = framework {
         if let Some(extra_event) = &extra_event {
@@ -477,7 +767,166 @@ fn update_cache_with_event(
         Event::MessagePollVoteRemove(event) => FullEvent::MessagePollVoteRemove {
             event,
         },
+        Event::Unknown {
+            kind,
+            data,
+        } => FullEvent::Unknown {
+            kind,
+            data,
+        },
     };
 
     (event, extra_event)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use parking_lot::Mutex;
+    use tokio::sync::{mpsc, Semaphore};
+    use tokio::time::sleep;
+
+    use super::{DispatchMode, Dispatcher, Lane};
+    use crate::model::id::GuildId;
+
+    #[tokio::test]
+    async fn test_ordered_dispatch() {
+        let dispatcher = Dispatcher::new(DispatchMode::OrderedPerGuild);
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+
+        // Earlier events take longer to handle, so they would finish last if run concurrently.
+        for (guild, event, delay) in [(1, 0, 60), (2, 0, 40), (1, 1, 20), (1, 2, 0), (2, 1, 0)] {
+            let handled = Arc::clone(&handled);
+            let done_tx = done_tx.clone();
+            let lane = Lane::Guild(GuildId::new(guild));
+            dispatcher
+                .dispatch(
+                    lane,
+                    Box::pin(async move {
+                        sleep(Duration::from_millis(delay)).await;
+                        handled.lock().push((guild, event));
+                        done_tx.send(()).unwrap();
+                    }),
+                )
+                .await;
+        }
+
+        for _ in 0..5 {
+            done_rx.recv().await.unwrap();
+        }
+
+        // Events of a guild are handled in order, while guild 2 doesn't wait for guild 1.
+        assert_eq!(*handled.lock(), [(2, 0), (2, 1), (1, 0), (1, 1), (1, 2)]);
+        assert!(dispatcher.lanes.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bounded_dispatch() {
+        let limit = NonZeroUsize::new(2).unwrap();
+        let dispatcher = Dispatcher::new(DispatchMode::Bounded(limit));
+        let release = Arc::new(Semaphore::new(0));
+
+        for _ in 0..2 {
+            let release = Arc::clone(&release);
+            dispatcher
+                .dispatch(
+                    Lane::Global,
+                    Box::pin(async move {
+                        release.acquire().await.unwrap().forget();
+                    }),
+                )
+                .await;
+        }
+
+        // The limit is reached, so the next event waits until one of the others is done.
+        let third = dispatcher.dispatch(Lane::Global, Box::pin(async {}));
+        tokio::pin!(third);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut third).await.is_err());
+
+        release.add_permits(1);
+        tokio::time::timeout(Duration::from_secs(1), third).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle() {
+        let dispatcher = Dispatcher::new(DispatchMode::OrderedPerGuild);
+        let release = Arc::new(Semaphore::new(0));
+
+        for _ in 0..2 {
+            let release = Arc::clone(&release);
+            let future = Box::pin(async move {
+                release.acquire().await.unwrap().forget();
+            });
+            dispatcher.dispatch(Lane::Global, future).await;
+        }
+        assert_eq!(dispatcher.in_flight(), 2);
+
+        // Events dispatched after closing are dropped.
+        dispatcher.close();
+        dispatcher.dispatch(Lane::Global, Box::pin(async {})).await;
+        assert_eq!(dispatcher.in_flight(), 2);
+
+        let idle = dispatcher.wait_idle();
+        tokio::pin!(idle);
+        release.add_permits(1);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut idle).await.is_err());
+
+        release.add_permits(1);
+        tokio::time::timeout(Duration::from_secs(1), idle).await.unwrap();
+        assert_eq!(dispatcher.in_flight(), 0);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_cache_update_events() {
+        use std::collections::HashSet;
+
+        use crate::cache::{cache_update_events, Settings};
+
+        // The events that `update_cache_with_event` updates the cache with.
+        let updating = HashSet::from([
+            "CHANNEL_CREATE",
+            "CHANNEL_DELETE",
+            "CHANNEL_UPDATE",
+            "GUILD_CREATE",
+            "GUILD_DELETE",
+            "GUILD_EMOJIS_UPDATE",
+            "GUILD_MEMBER_ADD",
+            "GUILD_MEMBER_REMOVE",
+            "GUILD_MEMBER_UPDATE",
+            "GUILD_MEMBERS_CHUNK",
+            "GUILD_ROLE_CREATE",
+            "GUILD_ROLE_DELETE",
+            "GUILD_ROLE_UPDATE",
+            "GUILD_STICKERS_UPDATE",
+            "MESSAGE_CREATE",
+            "MESSAGE_UPDATE",
+            "PRESENCE_UPDATE",
+            "READY",
+            "THREAD_CREATE",
+            "THREAD_DELETE",
+            "THREAD_UPDATE",
+            "USER_UPDATE",
+            "VOICE_CHANNEL_STATUS_UPDATE",
+            "VOICE_STATE_UPDATE",
+        ]);
+
+        let settings = Settings {
+            max_messages: 1,
+            ..Default::default()
+        };
+        assert_eq!(cache_update_events(&settings).collect::<HashSet<_>>(), updating);
+
+        // Events are only wanted if the data they update is cached.
+        let settings = Settings {
+            cache_guilds: false,
+            ..Default::default()
+        };
+        let wanted: HashSet<_> = cache_update_events(&settings).collect();
+        assert_eq!(wanted, HashSet::from(["READY", "USER_UPDATE"]));
+    }
+}
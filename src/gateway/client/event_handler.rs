@@ -6,7 +6,7 @@ use async_trait::async_trait;
 use strum::{EnumCount, IntoStaticStr, VariantNames};
 
 use super::context::Context;
-use crate::gateway::ShardStageUpdateEvent;
+use crate::gateway::{ReshardProgressEvent, ShardStageUpdateEvent, ShardUnhealthyEvent};
 use crate::http::RatelimitInfo;
 use crate::model::prelude::*;
 
@@ -26,7 +26,7 @@ macro_rules! event_handler {
                 $( #[deprecated = $deprecated] )?
                 async fn $method_name(&self, $($context: Context,)? $( $arg_name: $arg_type ),*) {
                     // Suppress unused argument warnings
-                    drop(( $($context,)? $($arg_name),* ))
+                    let _ = ( $($context,)? $($arg_name),* );
                 }
             )*
 
@@ -46,6 +46,34 @@ macro_rules! event_handler {
             fn filter_event(&self, _context: &Context, _event: &Event) -> bool {
                 true
             }
+
+            /// Returns the names of the gateway events this handler needs, such as
+            /// `"MESSAGE_CREATE"`, or `None` if it needs all of them, which is the default.
+            ///
+            /// Events that neither the handlers, the cache, the framework nor any collector need
+            /// are not deserialized at all, which saves a lot of work on large bots that receive
+            /// floods of events such as `PRESENCE_UPDATE` or `TYPING_START` without using them.
+            ///
+            /// Both the names returned by [`Event::name`] and the names sent by Discord are
+            /// accepted, e.g. `"REACTION_ADD"` or `"MESSAGE_REACTION_ADD"` for
+            /// [`Event::ReactionAdd`].
+            ///
+            /// **Note**: [`Self::shards_ready`] is derived from `READY`, which is always received,
+            /// while [`Self::cache_ready`] is derived from `GUILD_CREATE`.
+            ///
+            /// [`Event::name`]: crate::model::event::Event::name
+            /// [`Event::ReactionAdd`]: crate::model::event::Event::ReactionAdd
+            fn wanted_events(&self) -> Option<&[&str]> {
+                None
+            }
+
+            /// Called once during [`ShardManager::shutdown_gracefully`], after the handlers of all
+            /// earlier events have finished and before the shards are shut down.
+            ///
+            /// This is the place to flush buffers and close connections to databases.
+            ///
+            /// [`ShardManager::shutdown_gracefully`]: crate::gateway::ShardManager::shutdown_gracefully
+            async fn shutdown(&self) {}
         }
 
         /// This enum stores every possible event that an [`EventHandler`] can receive.
@@ -345,6 +373,22 @@ event_handler! {
     /// Provides the context of the shard and the event information about the update.
     ShardStageUpdate { event: ShardStageUpdateEvent } => async fn shard_stage_update(&self, ctx: Context);
 
+    /// Dispatched when a connected shard has gone without heartbeat acknowledgements or dispatches
+    /// for longer than the [`ShardHealthThresholds`] allow, before it is restarted if enabled.
+    ///
+    /// Provides the context of the shard and its health at that point.
+    ///
+    /// [`ShardHealthThresholds`]: crate::gateway::ShardHealthThresholds
+    ShardUnhealthy { event: ShardUnhealthyEvent } => async fn shard_unhealthy(&self, ctx: Context);
+
+    /// Dispatched when a shard started by [`ShardManager::reshard`] has loaded its guilds, and once
+    /// event dispatch has been switched over to the new shards.
+    ///
+    /// Provides the context of the shard and the progress of the reshard.
+    ///
+    /// [`ShardManager::reshard`]: crate::gateway::ShardManager::reshard
+    ReshardProgress { event: ReshardProgressEvent } => async fn reshard_progress(&self, ctx: Context);
+
     /// Dispatched when a user starts typing.
     TypingStart { event: TypingStartEvent } => async fn typing_start(&self, ctx: Context);
 
@@ -499,8 +543,21 @@ event_handler! {
     /// Dispatched when a user removes a previous vote on a poll.
     MessagePollVoteRemove { event: MessagePollVoteRemoveEvent } => async fn poll_vote_remove(&self, ctx: Context);
 
+    /// Dispatched when a dispatch event is received that the library does not know about, such as
+    /// one recently added by Discord.
+    ///
+    /// Provides the event's name and raw data.
+    Unknown { kind: FixedString, data: Value } => async fn unknown_event(&self, ctx: Context);
+
     /// Dispatched when an HTTP rate limit is hit
     Ratelimit { data: RatelimitInfo } => async fn ratelimit(&self);
+
+    /// Dispatched when the number of sessions that can still be started before the session start
+    /// limit resets drops to a tenth of the total or below.
+    ///
+    /// Once no sessions can be started anymore, shards that need to identify wait until the limit
+    /// resets, which can take up to a day.
+    SessionStartLimitLow { data: SessionStartLimit } => async fn session_start_limit_low(&self);
 }
 
 /// This core trait for handling raw events
@@ -526,4 +583,13 @@ pub trait RawEventHandler: Send + Sync {
         // Suppress unused argument warnings
         true
     }
+
+    /// Returns the names of the gateway events this handler needs, such as `"MESSAGE_CREATE"`, or
+    /// `None` if it needs all of them, which is the default.
+    ///
+    /// Names are accepted both as returned by [`Event::name`] and as sent by Discord. See
+    /// [`EventHandler::wanted_events`] for details.
+    fn wanted_events(&self) -> Option<&[&str]> {
+        None
+    }
 }
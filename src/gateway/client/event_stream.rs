@@ -0,0 +1,85 @@
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use futures::channel::mpsc::{self, Receiver, Sender};
+use futures::Stream;
+
+use super::{Context, FullEvent};
+
+/// The sending half of an [`EventStream`], which is given to each shard.
+pub type EventStreamSender = Sender<(Context, FullEvent)>;
+
+/// A stream of the events received by all shards of a [`Client`], as an alternative to
+/// implementing [`EventHandler`].
+///
+/// Each shard's events arrive in the order the shard received them in, after the cache has been
+/// updated with them. Events that are dispatched to an [`EventHandler`] outside of a shard's event
+/// loop, such as [`FullEvent::Ratelimit`] and [`FullEvent::ShardStageUpdate`], are not included.
+///
+/// The stream has a bounded buffer, set with [`ClientBuilder::event_stream`]. Once it is full,
+/// shards wait for room before reading further events, so that a slow consumer holds up the
+/// gateway connections instead of piling up events in memory. Shards don't heartbeat while they
+/// wait, so if the consumer stalls for longer than the heartbeat interval of roughly 40 seconds,
+/// Discord closes the connections, after which the shards resume their sessions.
+///
+/// The stream ends once all shards have been shut down.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use futures::StreamExt;
+/// use serenity::gateway::client::FullEvent;
+/// use serenity::prelude::*;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let token = Token::from_env("DISCORD_TOKEN")?;
+/// let mut client = Client::builder(token, GatewayIntents::default()).event_stream(256).await?;
+///
+/// let mut events = client.event_stream().expect("the event stream is only taken once");
+/// tokio::spawn(async move { client.start().await });
+///
+/// while let Some((ctx, event)) = events.next().await {
+///     if let FullEvent::Message {
+///         new_message,
+///     } = event
+///     {
+///         if new_message.content == "!ping" {
+///             new_message.channel_id.say(&ctx.http, "Pong!").await?;
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client`]: super::Client
+/// [`ClientBuilder::event_stream`]: super::ClientBuilder::event_stream
+/// [`EventHandler`]: super::EventHandler
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventStream {
+    rx: Receiver<(Context, FullEvent)>,
+}
+
+impl EventStream {
+    /// Creates an event stream buffering up to `capacity` events, along with its sending half.
+    ///
+    /// This is only needed when creating a [`ShardManager`] manually, as [`Client`] does this
+    /// when enabled with [`ClientBuilder::event_stream`].
+    ///
+    /// [`ShardManager`]: crate::gateway::ShardManager
+    pub fn channel(capacity: usize) -> (EventStreamSender, Self) {
+        let (tx, rx) = mpsc::channel(capacity);
+        (tx, Self {
+            rx,
+        })
+    }
+}
+
+impl Stream for EventStream {
+    type Item = (Context, FullEvent);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+}
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(feature = "cache")]
+use crate::cache::Settings as CacheSettings;
+use crate::model::gateway::GatewayIntents;
+
+/// The gateway intents that parts of a client need in order to receive their events, as
+/// returned by [`ClientBuilder::get_required_intents`].
+///
+/// Intents can be added for the events used by collectors and anything else the client doesn't
+/// know about, and then be compared against the enabled intents with [`Self::missing`].
+///
+/// # Examples
+///
+/// ```rust
+/// use serenity::gateway::client::RequiredIntents;
+/// use serenity::model::gateway::GatewayIntents;
+///
+/// let mut required = RequiredIntents::new();
+/// required.add_events(["MESSAGE_CREATE", "GUILD_MEMBER_ADD", "INTERACTION_CREATE"]);
+///
+/// let missing = required.missing(GatewayIntents::GUILD_MESSAGES);
+/// assert_eq!(missing.len(), 1);
+/// assert_eq!(missing[0].intents, GatewayIntents::GUILD_MEMBERS);
+/// ```
+///
+/// [`ClientBuilder::get_required_intents`]: super::ClientBuilder::get_required_intents
+#[derive(Clone, Debug, Default)]
+pub struct RequiredIntents {
+    // What needs the intents, and the intents any one of which is enough for it.
+    requirements: Vec<(Cow<'static, str>, GatewayIntents)>,
+}
+
+/// A part of a client that won't receive its events, as returned by [`RequiredIntents::missing`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct MissingIntents {
+    /// What needs the intents, such as the name of a gateway event.
+    pub needed_by: Cow<'static, str>,
+    /// The intents any one of which would be enough.
+    pub intents: GatewayIntents,
+}
+
+impl fmt::Display for MissingIntents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.intents.iter_names().map(|(name, _)| name).collect();
+        write!(f, "{} needs the {} intent", self.needed_by, names.join(" or "))
+    }
+}
+
+impl RequiredIntents {
+    /// Creates an empty set of requirements.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a requirement for one of the given intents.
+    pub fn add(&mut self, needed_by: impl Into<Cow<'static, str>>, intents: GatewayIntents) {
+        self.requirements.push((needed_by.into(), intents));
+    }
+
+    /// Adds the intents that enable the gateway event with the given name, such as
+    /// `"MESSAGE_CREATE"`, as returned by [`GatewayIntents::for_event`].
+    pub fn add_event(&mut self, name: &str) {
+        if let Some(intents) = GatewayIntents::for_event(name) {
+            self.add(format!("the {name} event"), intents);
+        }
+    }
+
+    /// Adds the intents that enable each of the given gateway events.
+    pub fn add_events<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        for name in names {
+            self.add_event(name);
+        }
+    }
+
+    /// Adds the intents that the cache needs to be filled with the given settings.
+    #[cfg(feature = "cache")]
+    pub fn add_cache(&mut self, settings: &CacheSettings) {
+        if settings.cache_guilds || settings.cache_channels {
+            self.add("the cache", GatewayIntents::GUILDS);
+        }
+        if settings.max_messages > 0 {
+            self.add(
+                "the message cache",
+                GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES,
+            );
+        }
+    }
+
+    /// Returns all intents that fulfil the requirements.
+    #[must_use]
+    pub fn intents(&self) -> GatewayIntents {
+        self.requirements.iter().fold(GatewayIntents::empty(), |all, (_, intents)| all | *intents)
+    }
+
+    /// Returns the requirements that aren't fulfilled by any of the given intents.
+    #[must_use]
+    pub fn missing(&self, enabled: GatewayIntents) -> Vec<MissingIntents> {
+        let mut missing: Vec<MissingIntents> = Vec::new();
+        for (needed_by, intents) in &self.requirements {
+            let duplicate = missing.iter().any(|m| m.needed_by == *needed_by);
+            if !enabled.intersects(*intents) && !duplicate {
+                missing.push(MissingIntents {
+                    needed_by: needed_by.clone(),
+                    intents: *intents,
+                });
+            }
+        }
+
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequiredIntents;
+    use crate::gateway::client::{Client, EventHandler};
+    use crate::gateway::GatewayError;
+    use crate::model::gateway::GatewayIntents;
+
+    #[test]
+    fn test_missing_intents() {
+        let mut required = RequiredIntents::new();
+        required.add_events(["MESSAGE_CREATE", "MESSAGE_CREATE", "READY", "PRESENCE_UPDATE"]);
+        required.add("prefix commands", GatewayIntents::MESSAGE_CONTENT);
+
+        let intents = GatewayIntents::GUILD_MESSAGES
+            | GatewayIntents::DIRECT_MESSAGES
+            | GatewayIntents::GUILD_PRESENCES
+            | GatewayIntents::MESSAGE_CONTENT;
+        assert_eq!(required.intents(), intents);
+        assert!(required.missing(intents).is_empty());
+
+        // Direct messages alone are enough for message events.
+        let missing = required.missing(GatewayIntents::DIRECT_MESSAGES);
+        let needed_by: Vec<_> = missing.iter().map(|m| &*m.needed_by).collect();
+        assert_eq!(needed_by, ["the PRESENCE_UPDATE event", "prefix commands"]);
+
+        let missing = required.missing(GatewayIntents::empty());
+        assert_eq!(missing.len(), 3);
+        assert_eq!(
+            missing[0].to_string(),
+            "the MESSAGE_CREATE event needs the GUILD_MESSAGES or DIRECT_MESSAGES intent"
+        );
+
+        // Renamed events are known by both of their names.
+        let reactions = GatewayIntents::for_event("MESSAGE_REACTION_ADD");
+        assert!(reactions.is_some());
+        assert_eq!(GatewayIntents::for_event("REACTION_ADD"), reactions);
+    }
+
+    const TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GHIJKL.MNOPQRSTUVWXYZabcdefghijklmnopqrstuv";
+
+    #[tokio::test]
+    async fn test_strict_intents() {
+        struct Handler;
+
+        impl EventHandler for Handler {
+            fn wanted_events(&self) -> Option<&[&str]> {
+                Some(&["GUILD_MEMBER_ADD", "MESSAGE_CREATE"])
+            }
+        }
+
+        let builder = Client::builder(TOKEN.parse().unwrap(), GatewayIntents::non_privileged())
+            .event_handler(Handler)
+            .strict_intents(true);
+        let missing = builder.get_required_intents().missing(builder.get_intents());
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].intents, GatewayIntents::GUILD_MEMBERS);
+
+        let result = builder.await;
+        assert!(matches!(
+            result,
+            Err(crate::Error::Gateway(GatewayError::MissingIntents(GatewayIntents::GUILD_MEMBERS)))
+        ));
+    }
+}
@@ -18,14 +18,20 @@
 //!   completely shared State.
 //! - [`Client::start_shard_range`]: start a range of shards within this instance. This should be
 //!   used when you, for example, want to split 10 shards across 3 instances.
+//! - [`Client::start_cluster`]: starts the shards of one cluster, as described by a
+//!   [`ClusterConfig`]. Clusters in separate processes should share an [`IdentifyQueue`].
 //!
 //! Click [here][Client#examples] for an example on how to use a [`Client`].
 //!
 //! [`sharding`]: crate::gateway::sharding
+//! [`ClusterConfig`]: crate::gateway::ClusterConfig
+//! [`IdentifyQueue`]: crate::gateway::IdentifyQueue
 
 mod context;
 pub(crate) mod dispatch;
 mod event_handler;
+mod event_stream;
+mod intents;
 
 use std::future::IntoFuture;
 use std::num::NonZeroU16;
@@ -41,8 +47,18 @@ use futures::StreamExt as _;
 use tracing::{debug, warn};
 
 pub use self::context::Context;
+pub use self::dispatch::DispatchMode;
 pub use self::event_handler::{EventHandler, FullEvent, RawEventHandler};
-use super::TransportCompression;
+pub use self::event_stream::{EventStream, EventStreamSender};
+pub use self::intents::{MissingIntents, RequiredIntents};
+use super::{
+    EventRecorder,
+    EventSink,
+    EventSinkBatching,
+    EventSinkSender,
+    GatewayEncoding,
+    TransportCompression,
+};
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
 #[cfg(feature = "cache")]
@@ -53,10 +69,15 @@ use crate::framework::Framework;
 use crate::gateway::VoiceGatewayManager;
 use crate::gateway::{
     ActivityData,
+    ClusterConfig,
     GatewayError,
+    IdentifyQueue,
     PresenceData,
+    PresenceRotation,
+    ShardHealthThresholds,
     ShardManager,
     ShardManagerOptions,
+    ShardSession,
     DEFAULT_WAIT_BETWEEN_SHARD_START,
 };
 use crate::http::Http;
@@ -85,6 +106,17 @@ pub struct ClientBuilder {
     presence: PresenceData,
     wait_time_between_shard_start: Duration,
     compression: TransportCompression,
+    encoding: GatewayEncoding,
+    event_recorder: Option<Arc<EventRecorder>>,
+    event_sink: Option<(Arc<dyn EventSink>, EventSinkBatching)>,
+    gateway_url: Option<Arc<str>>,
+    resume_sessions: Vec<ShardSession>,
+    identify_queue: Option<Arc<dyn IdentifyQueue>>,
+    event_stream_capacity: Option<usize>,
+    dispatch_mode: DispatchMode,
+    presence_rotation: Option<PresenceRotation>,
+    health_thresholds: Option<ShardHealthThresholds>,
+    strict_intents: bool,
 }
 
 impl ClientBuilder {
@@ -121,6 +153,17 @@ impl ClientBuilder {
             presence: PresenceData::default(),
             wait_time_between_shard_start: DEFAULT_WAIT_BETWEEN_SHARD_START,
             compression: TransportCompression::None,
+            encoding: GatewayEncoding::Json,
+            event_recorder: None,
+            event_sink: None,
+            gateway_url: None,
+            resume_sessions: Vec::new(),
+            identify_queue: None,
+            event_stream_capacity: None,
+            dispatch_mode: DispatchMode::default(),
+            presence_rotation: None,
+            health_thresholds: None,
+            strict_intents: false,
         }
     }
 
@@ -187,6 +230,94 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the encoding to be used for payloads sent to and received from the gateway.
+    ///
+    /// [`GatewayEncoding::Etf`] payloads are smaller than JSON ones, and cost no more to decode.
+    /// Defaults to [`GatewayEncoding::Json`].
+    pub fn encoding(mut self, encoding: GatewayEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets a recorder to write every event received by the shards to.
+    ///
+    /// Recordings can be replayed into event handlers with an [`EventReplayer`], to reproduce bugs
+    /// that depend on a specific sequence of events. Keep a clone of the recorder to
+    /// [flush] it before exiting.
+    ///
+    /// [`EventReplayer`]: crate::gateway::EventReplayer
+    /// [flush]: EventRecorder::flush
+    pub fn event_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.event_recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Sets a sink to forward the raw payload of every event received by the shards to, such as
+    /// for publishing them to a message broker.
+    ///
+    /// See [`EventSink`] for more information.
+    pub fn event_sink<S>(mut self, sink: impl Into<Arc<S>>, batching: EventSinkBatching) -> Self
+    where
+        S: EventSink + 'static,
+    {
+        self.event_sink = Some((sink.into(), batching));
+        self
+    }
+
+    /// Sets the URL of the gateway to connect to, instead of fetching it from Discord.
+    ///
+    /// This is mostly useful for connecting to a gateway proxy, or a [`FakeGateway`] in tests.
+    /// Since the recommended shard count is not fetched either, [`Client::start_autosharded`] will
+    /// still make a request to Discord.
+    ///
+    /// [`FakeGateway`]: crate::gateway::fake::FakeGateway
+    pub fn gateway_url(mut self, url: impl Into<Arc<str>>) -> Self {
+        self.gateway_url = Some(url.into());
+        self
+    }
+
+    /// Sets sessions saved by [`ShardManager::shutdown_all_resumable`] for the shards to resume,
+    /// instead of identifying from scratch.
+    ///
+    /// Shards whose session can't be resumed anymore will identify as usual. See [`ShardSession`]
+    /// for the caveats of resuming.
+    pub fn resume_sessions(mut self, sessions: impl IntoIterator<Item = ShardSession>) -> Self {
+        self.resume_sessions = sessions.into_iter().collect();
+        self
+    }
+
+    /// Sets the queue to coordinate identifies with, when running shards in several processes.
+    ///
+    /// Every process should be given a queue that hands out identify slots from the same place,
+    /// such as a [`UnixIdentifyQueue`] for processes on the same host. Shards resuming a session
+    /// don't identify, so they skip the queue.
+    ///
+    /// [`UnixIdentifyQueue`]: crate::gateway::UnixIdentifyQueue
+    pub fn identify_queue(mut self, identify_queue: Arc<dyn IdentifyQueue>) -> Self {
+        self.identify_queue = Some(identify_queue);
+        self
+    }
+
+    /// Enables [`Client::event_stream`], buffering up to `capacity` events that haven't been
+    /// consumed yet.
+    ///
+    /// Event handlers and the framework keep receiving events as well. See [`EventStream`] for
+    /// what happens when the buffer is full.
+    pub fn event_stream(mut self, capacity: usize) -> Self {
+        self.event_stream_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets how events are dispatched to the event handlers and the framework, which defaults to
+    /// [`DispatchMode::Unbounded`].
+    ///
+    /// For example, [`DispatchMode::OrderedPerChannel`] makes sure that a message is handled by
+    /// [`EventHandler::message`] before its edits are handled by [`EventHandler::message_update`].
+    pub fn dispatch_mode(mut self, dispatch_mode: DispatchMode) -> Self {
+        self.dispatch_mode = dispatch_mode;
+        self
+    }
+
     /// Sets the voice gateway handler to be used. It will receive voice events sent over the
     /// gateway and then consider - based on its settings - whether to dispatch a command.
     #[cfg(feature = "voice")]
@@ -237,6 +368,42 @@ impl ClientBuilder {
         self.intents
     }
 
+    /// Sets whether building the client fails with [`GatewayError::MissingIntents`] if parts of it
+    /// need intents that are not enabled, instead of logging a warning. Defaults to `false`.
+    ///
+    /// See [`Self::get_required_intents`] for which intents are checked.
+    pub fn strict_intents(mut self, strict: bool) -> Self {
+        self.strict_intents = strict;
+        self
+    }
+
+    /// Gets the intents needed by the event handlers, the cache and the voice manager, which are
+    /// checked against the enabled intents when the client is built.
+    ///
+    /// Event handlers only take part if they list their events with
+    /// [`EventHandler::wanted_events`]. Intents needed by collectors, or by reading message
+    /// content, can be added to the result and checked with [`RequiredIntents::missing`].
+    #[must_use]
+    pub fn get_required_intents(&self) -> RequiredIntents {
+        let mut required = RequiredIntents::new();
+        if let Some(events) = self.event_handler.as_ref().and_then(|h| h.wanted_events()) {
+            required.add_events(events.iter().copied());
+        }
+        if let Some(events) = self.raw_event_handler.as_ref().and_then(|h| h.wanted_events()) {
+            required.add_events(events.iter().copied());
+        }
+
+        #[cfg(feature = "cache")]
+        required.add_cache(&self.cache_settings);
+
+        #[cfg(feature = "voice")]
+        if self.voice_manager.is_some() {
+            required.add("the voice manager", GatewayIntents::GUILD_VOICE_STATES);
+        }
+
+        required
+    }
+
     /// Adds an event handler with multiple methods for each possible event.
     pub fn event_handler<H>(mut self, event_handler: impl Into<Arc<H>>) -> Self
     where
@@ -282,6 +449,25 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets activities that all shards cycle through at a fixed interval, which take over from the
+    /// initial activity once a shard has connected. Refer to [`PresenceRotation`] for more
+    /// information.
+    pub fn presence_rotation(mut self, rotation: PresenceRotation) -> Self {
+        self.presence_rotation = Some(rotation);
+
+        self
+    }
+
+    /// Sets when connected shards are considered unhealthy, which makes them get restarted and
+    /// reported to [`EventHandler::shard_unhealthy`]. Shards are not checked by default.
+    ///
+    /// The health of the shards can be inspected at any time with [`ShardManager::health`].
+    pub fn shard_health_thresholds(mut self, thresholds: ShardHealthThresholds) -> Self {
+        self.health_thresholds = Some(thresholds);
+
+        self
+    }
+
     /// Gets the initial presence. See [`Self::activity`] and [`Self::status`] for more info.
     #[must_use]
     pub fn get_presence(&self) -> &PresenceData {
@@ -296,6 +482,18 @@ impl IntoFuture for ClientBuilder {
 
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     fn into_future(self) -> Self::IntoFuture {
+        let missing_intents = self.get_required_intents().missing(self.intents);
+        for missing in &missing_intents {
+            warn!("{missing}, which is not enabled");
+        }
+        if self.strict_intents && !missing_intents.is_empty() {
+            let missing =
+                missing_intents.iter().fold(GatewayIntents::empty(), |all, m| all | m.intents);
+            return Box::pin(
+                async move { Err(Error::Gateway(GatewayError::MissingIntents(missing))) },
+            );
+        }
+
         let data = self.data.unwrap_or(Arc::new(()));
         #[cfg(feature = "framework")]
         let framework = self.framework;
@@ -321,16 +519,27 @@ impl IntoFuture for ClientBuilder {
         #[cfg(feature = "cache")]
         let cache = Arc::new(Cache::new_with_settings(self.cache_settings));
 
+        let (event_stream_tx, event_stream) =
+            self.event_stream_capacity.map(EventStream::channel).unzip();
+
         Box::pin(async move {
-            let (ws_url, shard_total, max_concurrency) = match http.get_bot_gateway().await {
-                Ok(response) => (
-                    Arc::from(response.url),
-                    response.shards,
-                    response.session_start_limit.max_concurrency,
-                ),
+            let gateway = match self.gateway_url {
+                Some(url) => Ok((url, NonZeroU16::MIN, NonZeroU16::MIN, None)),
+                None => http.get_bot_gateway().await.map(|response| {
+                    (
+                        Arc::from(response.url),
+                        response.shards,
+                        response.session_start_limit.max_concurrency,
+                        Some(response.session_start_limit),
+                    )
+                }),
+            };
+            let (ws_url, shard_total, max_concurrency, session_start_limit) = match gateway {
+                Ok(gateway) => gateway,
                 Err(err) => {
                     tracing::warn!("HTTP request to get gateway URL failed: {err}");
-                    (Arc::from("wss://gateway.discord.gg"), NonZeroU16::MIN, NonZeroU16::MIN)
+                    let ws_url = Arc::from("wss://gateway.discord.gg");
+                    (ws_url, NonZeroU16::MIN, NonZeroU16::MIN, None)
                 },
             };
 
@@ -355,6 +564,18 @@ impl IntoFuture for ClientBuilder {
                 max_concurrency,
                 wait_time_between_shard_start: self.wait_time_between_shard_start,
                 compression: self.compression,
+                encoding: self.encoding,
+                event_recorder: self.event_recorder,
+                event_sink: self
+                    .event_sink
+                    .map(|(sink, batching)| EventSinkSender::spawn(sink, batching)),
+                resume_sessions: self.resume_sessions,
+                identify_queue: self.identify_queue,
+                event_stream: event_stream_tx,
+                dispatch_mode: self.dispatch_mode,
+                session_start_limit,
+                presence_rotation: self.presence_rotation,
+                health_thresholds: self.health_thresholds,
             });
 
             let client = Client {
@@ -367,6 +588,7 @@ impl IntoFuture for ClientBuilder {
                 #[cfg(feature = "cache")]
                 cache,
                 http,
+                event_stream,
             };
             #[cfg(feature = "framework")]
             if let Some(mut framework) = framework {
@@ -498,6 +720,7 @@ pub struct Client {
     pub cache: Arc<Cache>,
     /// An HTTP client.
     pub http: Arc<Http>,
+    event_stream: Option<EventStream>,
 }
 
 impl Client {
@@ -522,6 +745,15 @@ impl Client {
         Arc::clone(&self.data).downcast().ok()
     }
 
+    /// Takes the stream of events received by all shards, if it was enabled with
+    /// [`ClientBuilder::event_stream`].
+    ///
+    /// The stream can only be taken once, and only receives events once the client has been
+    /// started. See [`EventStream`] for an example.
+    pub fn event_stream(&mut self) -> Option<EventStream> {
+        self.event_stream.take()
+    }
+
     /// Establish the connection and start listening for events.
     ///
     /// This will start receiving events in a loop and start dispatching the events to your
@@ -761,6 +993,55 @@ impl Client {
         self.start_connection(range.start, range.end, check_shard_total(total_shards)).await
     }
 
+    /// Establish the sharded connections of one cluster and start listening for events.
+    ///
+    /// This will start receiving events and dispatch them to your registered handlers.
+    ///
+    /// This starts the range of shards given by [`ClusterConfig::shards`]. When the clusters run
+    /// in separate processes, set a shared [`IdentifyQueue`] with
+    /// [`ClientBuilder::identify_queue`] so that they don't exceed the identify ratelimit
+    /// together.
+    ///
+    /// # Examples
+    ///
+    /// Start the cluster given by the `CLUSTER_ID` environment variable, out of 4 clusters with 32
+    /// shards in total:
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// use std::num::NonZeroU16;
+    ///
+    /// use serenity::gateway::ClusterConfig;
+    /// # use serenity::prelude::*;
+    /// use serenity::Client;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn Error>> {
+    /// let token = Token::from_env("DISCORD_TOKEN")?;
+    /// let mut client = Client::builder(token, GatewayIntents::default()).await?;
+    ///
+    /// let cluster_id = std::env::var("CLUSTER_ID")?.parse()?;
+    /// let cluster_count = NonZeroU16::new(4).unwrap();
+    /// let shard_total = NonZeroU16::new(32).unwrap();
+    /// let cluster = ClusterConfig::new(cluster_id, cluster_count, shard_total);
+    ///
+    /// if let Err(why) = client.start_cluster(cluster).await {
+    ///     println!("Err with client: {:?}", why);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Gateway`] when all shards have shutdown due to an error.
+    /// Returns [`Error::Http`] if fetching the current User fails when initialising a voice
+    /// manager.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn start_cluster(&mut self, cluster: ClusterConfig) -> Result<()> {
+        let shards = cluster.shards();
+        self.start_connection(shards.start, shards.end - 1, cluster.shard_total).await
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn start_connection(
         &mut self,
@@ -0,0 +1,397 @@
+//! Dispatching of events received by shards running somewhere else, such as in another process.
+//!
+//! Together with an [`EventSink`], this splits a bot into gateway processes, which run the shards
+//! and publish their events to a message broker, and worker processes, which take the events off
+//! the broker and handle them. An [`EventConsumer`] feeds such a stream of events through the same
+//! cache update, framework, event handlers and collectors as a [`Client`]:
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//!
+//! use futures::StreamExt;
+//! use serenity::gateway::client::EventHandler;
+//! use serenity::gateway::{EventConsumer, SinkEvent};
+//! use serenity::http::Http;
+//! use serenity::model::id::ShardId;
+//! use serenity::secrets::Token;
+//!
+//! struct Handler;
+//!
+//! #[serenity::async_trait]
+//! impl EventHandler for Handler {}
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let http = Arc::new(Http::new(Token::from_env("DISCORD_TOKEN")?));
+//! # let payloads: Vec<String> = Vec::new();
+//! // Payloads taken from a message broker.
+//! let events = futures::stream::iter(payloads)
+//!     .filter_map(|payload| async move { SinkEvent::new(ShardId(0), payload).ok() });
+//!
+//! EventConsumer::new(http).event_handler(Handler).run(events).await;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Messages that handlers send to the shard through [`Context::shard`], such as presence updates
+//! and member chunk requests, are passed to a [`CommandForwarder`], which sends them back to the
+//! process running the shards.
+//!
+//! [`EventSink`]: super::EventSink
+//! [`Client`]: super::client::Client
+//! [`Context::shard`]: super::client::Context::shard
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::{Stream, StreamExt};
+use tracing::{debug, warn};
+
+use super::client::dispatch::{dispatch_model, DispatchMode, Dispatcher};
+use super::client::{Context, EventHandler, RawEventHandler};
+use super::sharding::deserialize_and_log_event;
+#[cfg(feature = "collector")]
+use super::sharding::run_collectors;
+use super::{ShardManager, ShardMessenger, ShardRunnerMessage, SinkEvent};
+#[cfg(feature = "cache")]
+use crate::cache::{Cache, Settings as CacheSettings};
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+use crate::http::Http;
+use crate::internal::tokio::spawn_named;
+use crate::model::event::Event;
+use crate::model::id::{ShardId, UserId};
+
+/// Passes the messages that event handlers send to a shard on to wherever the shard runs.
+///
+/// Implementations typically serialize the message and publish it to a message broker, from
+/// which the process running the shard takes it and sends it to the shard with
+/// [`ShardMessenger::send_to_shard`]. [`ShardManager`] implements this trait by sending the
+/// messages to its shards directly.
+#[async_trait]
+pub trait CommandForwarder: Send + Sync {
+    /// Forwards a message for the shard with the given Id.
+    ///
+    /// Messages of the same shard are forwarded one at a time, in the order they were sent in.
+    async fn forward(&self, shard_id: ShardId, message: ShardRunnerMessage);
+}
+
+#[async_trait]
+impl CommandForwarder for ShardManager {
+    async fn forward(&self, shard_id: ShardId, message: ShardRunnerMessage) {
+        if let Some(runner) = self.runners.lock().await.get(&shard_id) {
+            runner.runner_tx.send_to_shard(message);
+        } else {
+            warn!("[EventConsumer] Dropping message for unknown shard {shard_id}");
+        }
+    }
+}
+
+/// Dispatches events from a stream to event handlers, as if they were received by shards.
+///
+/// Events go through the same deserialization, cache update, collector and dispatch steps as when
+/// they are received by a shard, and are dispatched according to the [`DispatchMode`]. Member
+/// chunk and voice channel requests made through [`ShardMessenger`] are answered by the events in
+/// the stream, as long as their responses are forwarded to it.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: self
+#[must_use]
+pub struct EventConsumer {
+    data: Arc<dyn std::any::Any + Send + Sync>,
+    http: Arc<Http>,
+    #[cfg(feature = "cache")]
+    cache: Arc<Cache>,
+    #[cfg(feature = "framework")]
+    framework: Option<Arc<dyn Framework>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    dispatch_mode: DispatchMode,
+    command_forwarder: Option<Arc<dyn CommandForwarder>>,
+}
+
+impl EventConsumer {
+    /// Creates a consumer with an empty cache and no handlers.
+    ///
+    /// The [`Http`] instance is given to handlers through the [`Context`].
+    pub fn new(http: Arc<Http>) -> Self {
+        Self {
+            data: Arc::new(()),
+            http,
+            #[cfg(feature = "cache")]
+            cache: Arc::new(Cache::new_with_settings(CacheSettings::default())),
+            #[cfg(feature = "framework")]
+            framework: None,
+            event_handler: None,
+            raw_event_handler: None,
+            dispatch_mode: DispatchMode::default(),
+            command_forwarder: None,
+        }
+    }
+
+    /// Sets the global data type that can be accessed from [`Context::data`].
+    pub fn data<D: std::any::Any + Send + Sync>(mut self, data: Arc<D>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the cache to update with the consumed events.
+    #[cfg(feature = "cache")]
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Sets the framework to dispatch the consumed events to.
+    ///
+    /// **Note**: Unlike with the [`ClientBuilder`], [`Framework::init`] is not called.
+    ///
+    /// [`ClientBuilder`]: super::client::ClientBuilder
+    #[cfg(feature = "framework")]
+    pub fn framework(mut self, framework: Arc<dyn Framework>) -> Self {
+        self.framework = Some(framework);
+        self
+    }
+
+    /// Sets the event handler to dispatch the consumed events to.
+    pub fn event_handler<H>(mut self, event_handler: impl Into<Arc<H>>) -> Self
+    where
+        H: EventHandler + 'static,
+    {
+        self.event_handler = Some(event_handler.into());
+        self
+    }
+
+    /// Sets the raw event handler to dispatch the consumed events to.
+    pub fn raw_event_handler<H>(mut self, raw_event_handler: impl Into<Arc<H>>) -> Self
+    where
+        H: RawEventHandler + 'static,
+    {
+        self.raw_event_handler = Some(raw_event_handler.into());
+        self
+    }
+
+    /// Sets how events are dispatched to the handlers. Defaults to [`DispatchMode::Unbounded`].
+    pub fn dispatch_mode(mut self, dispatch_mode: DispatchMode) -> Self {
+        self.dispatch_mode = dispatch_mode;
+        self
+    }
+
+    /// Sets where messages sent to the shards by handlers go. If not set, they are discarded.
+    pub fn command_forwarder<F>(mut self, command_forwarder: impl Into<Arc<F>>) -> Self
+    where
+        F: CommandForwarder + 'static,
+    {
+        self.command_forwarder = Some(command_forwarder.into());
+        self
+    }
+
+    /// Dispatches the events of the stream until it ends, returning the number of events that
+    /// were dispatched.
+    ///
+    /// Events that cannot be deserialized are skipped, as they would be by a shard. Handlers that
+    /// are still running when the stream ends are not waited for.
+    pub async fn run(&self, events: impl Stream<Item = SinkEvent>) -> usize {
+        let dispatcher = Dispatcher::new(self.dispatch_mode);
+        let mut messengers = HashMap::new();
+        let mut user_id = None;
+        let mut count = 0;
+
+        let mut events = std::pin::pin!(events);
+        while let Some(sink_event) = events.next().await {
+            let map = match serde_json::from_str(&sink_event.payload) {
+                Ok(map) => map,
+                Err(why) => {
+                    warn!(
+                        "[EventConsumer] Skipping invalid payload of {}: {why:?}",
+                        sink_event.name
+                    );
+                    continue;
+                },
+            };
+            let Ok(event) = deserialize_and_log_event(map, &sink_event.payload) else {
+                continue;
+            };
+
+            let shard_id = sink_event.shard_id;
+            let messenger =
+                messengers.entry(shard_id).or_insert_with(|| self.messenger(shard_id)).clone();
+
+            if let Event::Ready(ready) = &event {
+                user_id = Some(ready.ready.user.id);
+            }
+
+            if self.dispatch(event, shard_id, messenger, user_id, &dispatcher).await {
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Creates the messenger given to handlers of events of the shard, along with a task that
+    /// forwards its messages.
+    fn messenger(&self, shard_id: ShardId) -> ShardMessenger {
+        let (tx, mut rx) = mpsc::unbounded();
+        if let Some(forwarder) = self.command_forwarder.clone() {
+            // The task ends once all clones of the messenger are dropped.
+            spawn_named("event_consumer::forward", async move {
+                while let Some(message) = rx.next().await {
+                    forwarder.forward(shard_id, message).await;
+                }
+            });
+        }
+
+        ShardMessenger {
+            tx,
+            #[cfg(feature = "collector")]
+            collectors: Arc::default(),
+            chunk_requests: Arc::default(),
+            queued_commands: Arc::default(),
+            voice_requests: Arc::default(),
+        }
+    }
+
+    async fn dispatch(
+        &self,
+        event: Event,
+        shard_id: ShardId,
+        messenger: ShardMessenger,
+        user_id: Option<UserId>,
+        dispatcher: &Dispatcher,
+    ) -> bool {
+        debug!("[EventConsumer] Dispatching {} of shard {shard_id}", event.name());
+
+        match &event {
+            Event::Ready(ready) => self.http.set_application_id(ready.ready.application.id),
+            Event::GuildMembersChunk(chunk) => messenger.chunk_requests.deliver(chunk),
+            Event::VoiceStateUpdate(_) | Event::VoiceServerUpdate(_) => {
+                messenger.voice_requests.deliver(&event, user_id);
+            },
+            _ => {},
+        }
+
+        #[cfg(feature = "collector")]
+        let collectors = Arc::clone(&messenger.collectors);
+        let context = Context::new(
+            Arc::clone(&self.data),
+            messenger,
+            shard_id,
+            Arc::clone(&self.http),
+            #[cfg(feature = "cache")]
+            Arc::clone(&self.cache),
+        );
+
+        let can_dispatch = self
+            .event_handler
+            .as_ref()
+            .is_none_or(|handler| handler.filter_event(&context, &event))
+            && self
+                .raw_event_handler
+                .as_ref()
+                .is_none_or(|handler| handler.filter_event(&context, &event));
+
+        if !can_dispatch {
+            return false;
+        }
+
+        #[cfg(feature = "collector")]
+        run_collectors(&collectors, &event);
+
+        let lane = dispatcher.lane(&event);
+        dispatcher
+            .dispatch(
+                lane,
+                Box::pin(dispatch_model(
+                    event,
+                    context,
+                    #[cfg(feature = "framework")]
+                    self.framework.clone(),
+                    self.event_handler.clone(),
+                    self.raw_event_handler.clone(),
+                )),
+            )
+            .await;
+
+        true
+    }
+}
+
+impl std::fmt::Debug for EventConsumer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventConsumer")
+            .field("dispatch_mode", &self.dispatch_mode)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::internal::prelude::*;
+    use crate::model::user::OnlineStatus;
+
+    const TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GHIJKL.MNOPQRSTUVWXYZabcdefghijklmnopqrstuv";
+
+    struct ChannelForwarder(mpsc::UnboundedSender<(ShardId, ShardRunnerMessage)>);
+
+    #[async_trait]
+    impl CommandForwarder for ChannelForwarder {
+        async fn forward(&self, shard_id: ShardId, message: ShardRunnerMessage) {
+            drop(self.0.send((shard_id, message)));
+        }
+    }
+
+    struct Handler;
+
+    #[async_trait]
+    impl RawEventHandler for Handler {
+        async fn raw_event(&self, ctx: Context, _: &Event) {
+            ctx.shard.set_status(OnlineStatus::Idle);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consume_events() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let consumer = EventConsumer::new(Arc::new(Http::new(TOKEN.parse().unwrap())))
+            .raw_event_handler(Handler)
+            .command_forwarder(ChannelForwarder(tx));
+
+        let line = concat!(
+            r#"{"shard_id":3,"seq":2,"name":"RESUMED","#,
+            r#""payload":{"op":0,"s":2,"t":"RESUMED","d":{}}}"#,
+        );
+        let events = vec![
+            SinkEvent::new(ShardId(1), r#"{"op":0,"s":1,"t":"BRAND_NEW_EVENT","d":{}}"#).unwrap(),
+            SinkEvent {
+                shard_id: ShardId(1),
+                seq: 2,
+                name: FixedString::from_static_trunc("MESSAGE_CREATE"),
+                payload: FixedString::from_static_trunc("not json"),
+            },
+            SinkEvent::from_ndjson(line).unwrap(),
+        ];
+        assert_eq!(events[2].shard_id, ShardId(3));
+        assert_eq!(events[2].name, "RESUMED");
+
+        // The payload that is not JSON is skipped.
+        assert_eq!(consumer.run(futures::stream::iter(events)).await, 2);
+
+        let mut shards = Vec::new();
+        for _ in 0..2 {
+            let (shard_id, message) = rx.recv().await.unwrap();
+            assert!(matches!(message, ShardRunnerMessage::SetStatus(OnlineStatus::Idle)));
+            shards.push(shard_id);
+        }
+        shards.sort();
+        assert_eq!(shards, [ShardId(1), ShardId(3)]);
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt;
 
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 
+use crate::model::gateway::GatewayIntents;
+
 /// An error that occurred while attempting to deal with the gateway.
 ///
 /// Note that - from a user standpoint - there should be no situation in which you manually handle
@@ -61,6 +63,32 @@ pub enum Error {
     DecompressZstdCorrupted,
     /// When decompressed gateway data is not valid UTF-8.
     DecompressUtf8(std::string::FromUtf8Error),
+    /// When a payload could not be decoded from the Erlang Term Format.
+    Etf(String),
+    /// When a reshard was requested while another one is still in progress.
+    ReshardInProgress,
+    /// When not all member chunks requested with [`ShardMessenger::request_members`] were
+    /// received in time.
+    ///
+    /// [`ShardMessenger::request_members`]: super::ShardMessenger::request_members
+    MemberChunksTimedOut,
+    /// When a shard could not identify because no more sessions can be started until the session
+    /// start limit resets.
+    ///
+    /// The shard is started once the limit has reset.
+    SessionStartLimitExhausted,
+    /// When joining a voice channel with [`ShardMessenger::join_voice_channel`] did not result in
+    /// a voice state and voice server update in time.
+    ///
+    /// [`ShardMessenger::join_voice_channel`]: super::ShardMessenger::join_voice_channel
+    VoiceConnectionTimedOut,
+    /// When the client is built with strict intent checking, and parts of it need intents that
+    /// are not enabled, which are given.
+    ///
+    /// See [`ClientBuilder::strict_intents`] for more information.
+    ///
+    /// [`ClientBuilder::strict_intents`]: super::client::ClientBuilder::strict_intents
+    MissingIntents(GatewayIntents),
 }
 
 impl fmt::Display for Error {
@@ -90,6 +118,17 @@ impl fmt::Display for Error {
                 f.write_str("Zstd decompression error: corrupted data")
             },
             Self::DecompressUtf8(inner) => fmt::Display::fmt(&inner, f),
+            Self::Etf(reason) => write!(f, "ETF decoding error: {reason}"),
+            Self::ReshardInProgress => f.write_str("A reshard is already in progress"),
+            Self::MemberChunksTimedOut => f.write_str("Timed out waiting for member chunks"),
+            Self::SessionStartLimitExhausted => f.write_str("Session start limit exhausted"),
+            Self::MissingIntents(intents) => {
+                let names: Vec<_> = intents.iter_names().map(|(name, _)| name).collect();
+                write!(f, "Missing gateway intents: {}", names.join(", "))
+            },
+            Self::VoiceConnectionTimedOut => {
+                f.write_str("Timed out waiting for the voice server of a voice channel")
+            },
         }
     }
 }
@@ -0,0 +1,689 @@
+//! A codec for the [Erlang External Term Format][ETF], used when connecting to the gateway with
+//! [`GatewayEncoding::Etf`].
+//!
+//! Payloads are decoded through serde, straight into the types they are deserialized into, like
+//! with JSON. Atoms are decoded as strings, except for `nil`, `true` and `false`, and integers of
+//! up to 64 bits (such as snowflakes) are decoded as numbers. Payloads are encoded from [`Value`]s.
+//!
+//! [ETF]: https://www.erlang.org/doc/apps/erts/erl_ext_dist.html
+//! [`GatewayEncoding::Etf`]: super::GatewayEncoding::Etf
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use serde::de::value::{BorrowedStrDeserializer, SeqDeserializer};
+#[cfg(any(test, feature = "fake_gateway"))]
+use serde::de::DeserializeOwned;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize};
+
+use super::GatewayError;
+use crate::internal::prelude::*;
+
+pub(crate) const VERSION: u8 = 131;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const COMPRESSED: u8 = 80;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// An error that occurred while decoding a term, turned into [`GatewayError::Etf`].
+#[derive(Debug)]
+struct DecodeError(String);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl StdError for DecodeError {}
+
+impl de::Error for DecodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(error: DecodeError) -> Self {
+        Error::Gateway(GatewayError::Etf(error.0))
+    }
+}
+
+fn error(reason: &str) -> DecodeError {
+    DecodeError(reason.to_owned())
+}
+
+/// Returns the bytes of the term contained in an ETF payload, decompressing them if needed.
+pub(crate) fn term_bytes(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    let Some((&VERSION, term)) = bytes.split_first() else {
+        return Err(error("unsupported format version").into());
+    };
+
+    let Some((&COMPRESSED, compressed)) = term.split_first() else {
+        return Ok(Cow::Borrowed(term));
+    };
+
+    let mut deserializer = Deserializer {
+        bytes: compressed,
+    };
+    let size = deserializer.read_u32()?;
+
+    // The size is untrusted, so it only bounds the decompression, reading one byte past it to
+    // detect terms that are larger than announced.
+    let mut decompressed = Vec::with_capacity((size as usize).min(64 * 1024));
+    ZlibDecoder::new(deserializer.bytes)
+        .take(u64::from(size) + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() != size as usize {
+        return Err(error("decompressed size mismatch").into());
+    }
+
+    Ok(Cow::Owned(decompressed))
+}
+
+/// Deserializes a value from the bytes of a term, as returned by [`term_bytes`].
+///
+/// Strings are borrowed from the bytes where possible, and terms that aren't needed, such as
+/// unknown struct fields, are skipped without being decoded.
+pub(crate) fn from_term_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T> {
+    let mut deserializer = Deserializer {
+        bytes,
+    };
+
+    let value = T::deserialize(&mut deserializer)?;
+    if !deserializer.bytes.is_empty() {
+        return Err(error("trailing bytes after term").into());
+    }
+
+    Ok(value)
+}
+
+/// Deserializes a value from an ETF payload.
+#[cfg(any(test, feature = "fake_gateway"))]
+pub(crate) fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    from_term_bytes(&term_bytes(bytes)?)
+}
+
+struct Deserializer<'de> {
+    bytes: &'de [u8],
+}
+
+impl<'de> Deserializer<'de> {
+    fn read_slice(&mut self, len: usize) -> StdResult<&'de [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(error("unexpected end of payload"));
+        }
+
+        let (slice, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(slice)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> StdResult<[u8; N], DecodeError> {
+        let slice = self.read_slice(N)?;
+        Ok(slice.try_into().expect("slice has the requested length"))
+    }
+
+    fn read_u8(&mut self) -> StdResult<u8, DecodeError> {
+        self.read_array::<1>().map(|[byte]| byte)
+    }
+
+    fn read_u16(&mut self) -> StdResult<u16, DecodeError> {
+        self.read_array().map(u16::from_be_bytes)
+    }
+
+    fn read_u32(&mut self) -> StdResult<u32, DecodeError> {
+        self.read_array().map(u32::from_be_bytes)
+    }
+
+    fn read_str(&mut self, len: usize) -> StdResult<&'de str, DecodeError> {
+        std::str::from_utf8(self.read_slice(len)?).map_err(|_| error("invalid UTF-8 string"))
+    }
+
+    /// Reads the name of an atom, after its tag.
+    fn read_atom(&mut self, tag: u8) -> StdResult<&'de str, DecodeError> {
+        let len =
+            if tag == SMALL_ATOM_UTF8_EXT { self.read_u8()?.into() } else { self.read_u16()? };
+        self.read_str(len.into())
+    }
+
+    /// Reads a string after its tag, which may be an atom or a binary.
+    fn read_string(&mut self, tag: u8) -> StdResult<Option<&'de str>, DecodeError> {
+        match tag {
+            ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT => self.read_atom(tag).map(Some),
+            BINARY_EXT => {
+                let len = self.read_u32()?;
+                self.read_str(len as usize).map(Some)
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn read_float(&mut self) -> StdResult<f64, DecodeError> {
+        let text = self.read_str(31)?.trim_end_matches('\0');
+        text.parse().map_err(|_| error("invalid float"))
+    }
+
+    /// Reads a big integer after its tag, which must fit into a [`u64`] or an [`i64`].
+    fn read_big(&mut self, tag: u8) -> StdResult<i128, DecodeError> {
+        let len = if tag == SMALL_BIG_EXT { self.read_u8()?.into() } else { self.read_u32()? };
+        let len = len as usize;
+
+        let sign = self.read_u8()?;
+        let digits = self.read_slice(len)?;
+        if len > 8 {
+            return Err(error("integer does not fit into 64 bits"));
+        }
+
+        let mut bytes = [0; 8];
+        bytes[..len].copy_from_slice(digits);
+        let magnitude = i128::from(u64::from_le_bytes(bytes));
+
+        if sign == 0 {
+            Ok(magnitude)
+        } else if magnitude <= -i128::from(i64::MIN) {
+            Ok(-magnitude)
+        } else {
+            Err(error("integer does not fit into 64 bits"))
+        }
+    }
+
+    /// Consumes the next term if it is the `nil` atom.
+    fn next_is_nil(&mut self) -> bool {
+        let mut peek = Deserializer {
+            bytes: self.bytes,
+        };
+
+        let is_nil = match peek.read_u8() {
+            Ok(tag @ (ATOM_EXT | ATOM_UTF8_EXT | SMALL_ATOM_UTF8_EXT)) => {
+                peek.read_atom(tag).is_ok_and(|name| name == "nil")
+            },
+            _ => false,
+        };
+        if is_nil {
+            self.bytes = peek.bytes;
+        }
+
+        is_nil
+    }
+
+    /// Skips the next term without decoding it.
+    fn skip_term(&mut self) -> StdResult<(), DecodeError> {
+        let tag = self.read_u8()?;
+        let len = match tag {
+            SMALL_INTEGER_EXT => 1,
+            INTEGER_EXT => 4,
+            NEW_FLOAT_EXT => 8,
+            FLOAT_EXT => 31,
+            SMALL_BIG_EXT => usize::from(self.read_u8()?) + 1,
+            LARGE_BIG_EXT => self.read_u32()? as usize + 1,
+            ATOM_EXT | ATOM_UTF8_EXT | STRING_EXT => self.read_u16()?.into(),
+            SMALL_ATOM_UTF8_EXT => self.read_u8()?.into(),
+            BINARY_EXT => self.read_u32()? as usize,
+            NIL_EXT => 0,
+            SMALL_TUPLE_EXT | LARGE_TUPLE_EXT | LIST_EXT | MAP_EXT => {
+                let len = if tag == SMALL_TUPLE_EXT {
+                    self.read_u8()?.into()
+                } else {
+                    self.read_u32()? as usize
+                };
+
+                let terms = match tag {
+                    // Lists end with a tail, and maps consist of pairs.
+                    LIST_EXT => len + 1,
+                    MAP_EXT => len * 2,
+                    _ => len,
+                };
+                for _ in 0..terms {
+                    self.skip_term()?;
+                }
+
+                0
+            },
+            _ => return Err(error("unsupported term")),
+        };
+
+        self.read_slice(len).map(drop)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> StdResult<V::Value, DecodeError> {
+        let tag = self.read_u8()?;
+        if let Some(string) = self.read_string(tag)? {
+            return match (tag, string) {
+                (BINARY_EXT, _) => visitor.visit_borrowed_str(string),
+                (_, "nil") => visitor.visit_unit(),
+                (_, "true") => visitor.visit_bool(true),
+                (_, "false") => visitor.visit_bool(false),
+                _ => visitor.visit_borrowed_str(string),
+            };
+        }
+
+        match tag {
+            SMALL_INTEGER_EXT => visitor.visit_u8(self.read_u8()?),
+            INTEGER_EXT => visitor.visit_i32(i32::from_be_bytes(self.read_array()?)),
+            NEW_FLOAT_EXT => visitor.visit_f64(f64::from_be_bytes(self.read_array()?)),
+            FLOAT_EXT => visitor.visit_f64(self.read_float()?),
+            SMALL_BIG_EXT | LARGE_BIG_EXT => {
+                let int = self.read_big(tag)?;
+                if let Ok(int) = u64::try_from(int) {
+                    visitor.visit_u64(int)
+                } else {
+                    let int = i64::try_from(int).map_err(|_| error("integer out of range"))?;
+                    visitor.visit_i64(int)
+                }
+            },
+            STRING_EXT => {
+                // A list of small integers, which Erlang encodes compactly.
+                let len = self.read_u16()?;
+                let mut seq = SeqDeserializer::<_, DecodeError>::new(
+                    self.read_slice(len.into())?.iter().copied(),
+                );
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            },
+            NIL_EXT => visitor.visit_seq(Terms::new(self, 0)),
+            LIST_EXT => {
+                let len = self.read_u32()?;
+                let value = Terms::new(&mut *self, len as usize).visit_seq(visitor)?;
+
+                // Proper lists end with an empty list as their tail.
+                if self.read_u8()? != NIL_EXT {
+                    return Err(error("improper lists are not supported"));
+                }
+
+                Ok(value)
+            },
+            SMALL_TUPLE_EXT => {
+                let len = self.read_u8()?;
+                Terms::new(self, len.into()).visit_seq(visitor)
+            },
+            LARGE_TUPLE_EXT => {
+                let len = self.read_u32()?;
+                Terms::new(self, len as usize).visit_seq(visitor)
+            },
+            MAP_EXT => {
+                let len = self.read_u32()?;
+                let mut map = Terms::new(self, len as usize);
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            },
+            _ => Err(error("unsupported term")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> StdResult<V::Value, DecodeError> {
+        if self.next_is_nil() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> StdResult<V::Value, DecodeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> StdResult<V::Value, DecodeError> {
+        // Only unit variants are supported, which are encoded as their names.
+        let tag = self.read_u8()?;
+        let variant = self.read_string(tag)?.ok_or_else(|| error("unsupported enum"))?;
+        visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> StdResult<V::Value, DecodeError> {
+        self.skip_term()?;
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// The elements of a list or tuple, or the entries of a map.
+struct Terms<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'a, 'de> Terms<'a, 'de> {
+    fn new(deserializer: &'a mut Deserializer<'de>, len: usize) -> Self {
+        Self {
+            deserializer,
+            remaining: len,
+        }
+    }
+
+    fn visit_seq<V: Visitor<'de>>(mut self, visitor: V) -> StdResult<V::Value, DecodeError> {
+        let value = visitor.visit_seq(&mut self)?;
+        self.end()?;
+        Ok(value)
+    }
+
+    fn end(&self) -> StdResult<(), DecodeError> {
+        if self.remaining == 0 {
+            Ok(())
+        } else {
+            Err(error("not all elements were deserialized"))
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Terms<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> StdResult<Option<T::Value>, DecodeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // Don't trust the length for preallocation, as every term takes up at least one byte.
+        Some(self.remaining.min(self.deserializer.bytes.len()))
+    }
+}
+
+impl<'de> MapAccess<'de> for Terms<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> StdResult<Option<K::Value>, DecodeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.remaining -= 1;
+        seed.deserialize(MapKey(&mut *self.deserializer)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> StdResult<V::Value, DecodeError> {
+        seed.deserialize(&mut *self.deserializer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining.min(self.deserializer.bytes.len()))
+    }
+}
+
+/// Deserializes map keys, which are always strings, as integer keys are converted to strings.
+struct MapKey<'a, 'de>(&'a mut Deserializer<'de>);
+
+impl<'de> de::Deserializer<'de> for MapKey<'_, 'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> StdResult<V::Value, DecodeError> {
+        let tag = self.0.read_u8()?;
+        if let Some(key) = self.0.read_string(tag)? {
+            return visitor.visit_borrowed_str(key);
+        }
+
+        let key = match tag {
+            SMALL_INTEGER_EXT => i128::from(self.0.read_u8()?),
+            INTEGER_EXT => i128::from(i32::from_be_bytes(self.0.read_array()?)),
+            SMALL_BIG_EXT | LARGE_BIG_EXT => self.0.read_big(tag)?,
+            _ => return Err(error("unsupported map key")),
+        };
+
+        visitor.visit_string(key.to_string())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> StdResult<V::Value, DecodeError> {
+        self.0.deserialize_ignored_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+    }
+}
+
+/// Encodes a [`Value`] as an ETF payload.
+pub(crate) fn to_vec(value: &Value) -> Vec<u8> {
+    let mut bytes = vec![VERSION];
+    write_term(&mut bytes, value);
+    bytes
+}
+
+fn write_term(bytes: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => write_atom(bytes, "nil"),
+        Value::Bool(true) => write_atom(bytes, "true"),
+        Value::Bool(false) => write_atom(bytes, "false"),
+        Value::Number(number) => {
+            if let Some(int) = number.as_u64() {
+                write_int(bytes, false, int);
+            } else if let Some(int) = number.as_i64() {
+                write_int(bytes, true, int.unsigned_abs());
+            } else {
+                bytes.push(NEW_FLOAT_EXT);
+                bytes.extend(number.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        },
+        Value::String(string) => write_binary(bytes, string),
+        Value::Array(array) => {
+            if !array.is_empty() {
+                bytes.push(LIST_EXT);
+                bytes.extend(len_u32(array.len()).to_be_bytes());
+                for element in array {
+                    write_term(bytes, element);
+                }
+            }
+
+            bytes.push(NIL_EXT);
+        },
+        Value::Object(map) => {
+            bytes.push(MAP_EXT);
+            bytes.extend(len_u32(map.len()).to_be_bytes());
+            for (key, value) in map {
+                write_binary(bytes, key);
+                write_term(bytes, value);
+            }
+        },
+    }
+}
+
+fn write_atom(bytes: &mut Vec<u8>, name: &'static str) {
+    bytes.push(SMALL_ATOM_UTF8_EXT);
+    bytes.push(name.len() as u8);
+    bytes.extend(name.as_bytes());
+}
+
+fn write_binary(bytes: &mut Vec<u8>, string: &str) {
+    bytes.push(BINARY_EXT);
+    bytes.extend(len_u32(string.len()).to_be_bytes());
+    bytes.extend(string.as_bytes());
+}
+
+fn write_int(bytes: &mut Vec<u8>, negative: bool, magnitude: u64) {
+    if !negative && magnitude <= u8::MAX.into() {
+        bytes.push(SMALL_INTEGER_EXT);
+        bytes.push(magnitude as u8);
+    } else if let Ok(int) = i32::try_from(magnitude) {
+        let int = if negative { -int } else { int };
+        bytes.push(INTEGER_EXT);
+        bytes.extend(int.to_be_bytes());
+    } else {
+        let digits = magnitude.to_le_bytes();
+        let len = 8 - (magnitude.leading_zeros() / 8) as usize;
+
+        bytes.push(SMALL_BIG_EXT);
+        bytes.push(len as u8);
+        bytes.push(negative.into());
+        bytes.extend(&digits[..len]);
+    }
+}
+
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).expect("gateway payloads are smaller than 4 GiB")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::GatewayEvent;
+
+    #[test]
+    fn test_roundtrip() {
+        let value = serde_json::json!({
+            "op": 2,
+            "d": {
+                "token": "abc",
+                "shard": [0, 1],
+                "large_threshold": 250,
+                "since": null,
+                "afk": false,
+                "activities": [],
+                "intents": 3_276_799,
+                "seq": -5,
+                "id": 175_928_847_299_117_063_u64,
+                "ratio": 0.5,
+            },
+        });
+
+        assert_eq!(from_slice::<Value>(&to_vec(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn test_decode_dispatch() {
+        let mut payload = vec![VERSION, MAP_EXT, 0, 0, 0, 4];
+        for (key, term) in [
+            ("op", &[SMALL_INTEGER_EXT, 0][..]),
+            ("s", &[SMALL_INTEGER_EXT, 7]),
+            ("t", &[ATOM_EXT, 0, 12]),
+        ] {
+            write_binary(&mut payload, key);
+            payload.extend(term);
+        }
+        payload.extend(b"GUILD_DELETE");
+
+        // A snowflake, encoded as a 64-bit big integer.
+        write_binary(&mut payload, "d");
+        payload.extend([MAP_EXT, 0, 0, 0, 2]);
+        write_atom(&mut payload, "id");
+        payload.extend([SMALL_BIG_EXT, 8, 0]);
+        payload.extend(175_928_847_299_117_063_u64.to_le_bytes());
+        write_atom(&mut payload, "unavailable");
+        write_atom(&mut payload, "true");
+
+        let event: GatewayEvent = from_slice(&payload).unwrap();
+        let GatewayEvent::Dispatch {
+            seq,
+            data,
+            ..
+        } = event
+        else {
+            panic!("expected a dispatch, got {event:?}");
+        };
+
+        assert_eq!(seq, 7);
+        assert_eq!(data["t"], "GUILD_DELETE");
+        assert_eq!(data["d"]["id"], 175_928_847_299_117_063_u64);
+        assert_eq!(data["d"]["unavailable"], true);
+    }
+
+    #[test]
+    fn test_decode_struct() {
+        #[derive(Deserialize)]
+        struct Payload<'a> {
+            t: &'a str,
+            s: Option<u64>,
+            id: Option<u64>,
+        }
+
+        let value = serde_json::json!({
+            "d": {"nested": [{"a": 1.5}, "b", null]},
+            "t": "READY",
+            "s": null,
+            "id": 175_928_847_299_117_063_u64,
+        });
+        let bytes = to_vec(&value);
+        let term = term_bytes(&bytes).unwrap();
+
+        // Unknown fields are skipped, and strings are borrowed from the payload.
+        let payload: Payload<'_> = from_term_bytes(&term).unwrap();
+        assert_eq!(payload.t, "READY");
+        assert_eq!(payload.s, None);
+        assert_eq!(payload.id, Some(175_928_847_299_117_063));
+
+        assert!(from_term_bytes::<Value>(&term[..term.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_decompress() {
+        use std::io::Write;
+
+        use flate2::write::ZlibEncoder;
+        use flate2::Compression;
+
+        let value = serde_json::json!({"t": "READY", "s": 1});
+        let term = &to_vec(&value)[1..];
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(term).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let payload = |size: usize| {
+            let mut payload = vec![VERSION, COMPRESSED];
+            payload.extend(len_u32(size).to_be_bytes());
+            payload.extend(&compressed);
+            payload
+        };
+
+        assert_eq!(from_slice::<Value>(&payload(term.len())).unwrap(), value);
+        // The announced size must match the decompressed term exactly.
+        assert!(term_bytes(&payload(term.len() - 1)).is_err());
+        assert!(term_bytes(&payload(term.len() + 1)).is_err());
+        assert!(term_bytes(&payload(u32::MAX as usize)).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Forwarding of raw gateway dispatches to external consumers, such as a message broker.
+//!
+//! An [`EventSink`] passed to [`ClientBuilder::event_sink`] receives the payload of every dispatch
+//! received by the shards, exactly as Discord sent it, so that services that are not written in
+//! Rust can consume the events too. Payloads are collected into batches in the background, so a
+//! slow sink doesn't hold up the shards.
+//!
+//! [`NdjsonSink`] is a sink that writes the payloads as newline-delimited JSON to a file or Unix
+//! socket:
+//!
+//! ```rust,no_run
+//! use serenity::gateway::{EventSinkBatching, NdjsonSink};
+//! use serenity::prelude::*;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let sink = NdjsonSink::create("events.ndjson").await?;
+//!
+//! let token = Token::from_env("DISCORD_TOKEN")?;
+//! let mut client = Client::builder(token, GatewayIntents::default())
+//!     .event_sink(sink, EventSinkBatching::default())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ClientBuilder::event_sink`]: super::client::ClientBuilder::event_sink
+
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{timeout_at, Instant};
+use tracing::warn;
+
+use crate::constants::Opcode;
+use crate::internal::prelude::*;
+use crate::internal::tokio::spawn_named;
+use crate::model::id::ShardId;
+
+/// A dispatch payload received by a shard, as passed to an [`EventSink`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SinkEvent {
+    /// The shard that received the event.
+    pub shard_id: ShardId,
+    /// The sequence number of the event.
+    pub seq: u64,
+    /// The name of the event, such as `MESSAGE_CREATE`.
+    pub name: FixedString,
+    /// The whole payload as JSON, including the `op`, `s`, `t` and `d` fields.
+    ///
+    /// This is the text sent by Discord, unless the shards use [`GatewayEncoding::Etf`], in which
+    /// case it is converted to JSON, only for the events the sink wants.
+    ///
+    /// [`GatewayEncoding::Etf`]: super::GatewayEncoding::Etf
+    pub payload: FixedString,
+}
+
+impl SinkEvent {
+    /// Creates an event from the whole payload of a dispatch, as received from Discord, taking
+    /// the sequence number and name from its `s` and `t` fields.
+    ///
+    /// This is useful for feeding events taken from a message broker into an [`EventConsumer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`] if the payload is not a JSON object.
+    ///
+    /// [`EventConsumer`]: super::EventConsumer
+    pub fn new(shard_id: ShardId, payload: impl Into<String>) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Header {
+            #[serde(default)]
+            s: Option<u64>,
+            #[serde(default)]
+            t: Option<String>,
+        }
+
+        let payload = payload.into();
+        let header: Header = serde_json::from_str(&payload)?;
+        Ok(Self {
+            shard_id,
+            seq: header.s.unwrap_or_default(),
+            name: FixedString::from_string_trunc(header.t.unwrap_or_default()),
+            payload: FixedString::from_string_trunc(payload),
+        })
+    }
+
+    /// Parses a line written by a [`NdjsonSink`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Json`] if the line is not an object in the format of [`NdjsonSink`].
+    pub fn from_ndjson(line: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Line {
+            shard_id: u16,
+            seq: u64,
+            name: String,
+            payload: Value,
+        }
+
+        let line: Line = serde_json::from_str(line)?;
+        Ok(Self {
+            shard_id: ShardId(line.shard_id),
+            seq: line.seq,
+            name: FixedString::from_string_trunc(line.name),
+            payload: FixedString::from_string_trunc(line.payload.to_string()),
+        })
+    }
+}
+
+/// A consumer of the raw dispatch payloads received by the shards of a [`Client`].
+///
+/// See the [module documentation] for how to set one up.
+///
+/// [`Client`]: super::client::Client
+/// [module documentation]: self
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Checks whether the event with the given name, such as `"MESSAGE_CREATE"`, should be
+    /// forwarded. Returns `true` by default.
+    ///
+    /// This runs in the event loop of the shard that received the event, so it should be cheap.
+    fn wants_event(&self, _name: &str) -> bool {
+        true
+    }
+
+    /// Receives a batch of events, in the order they were received in.
+    ///
+    /// Only one batch is sent at a time. Events keep being collected while this runs, until the
+    /// buffer set with [`EventSinkBatching::buffer`] is full, after which events are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Errors are logged, and the batch is not retried.
+    async fn send(&self, events: &[SinkEvent]) -> Result<()>;
+}
+
+/// How events are collected into batches for an [`EventSink`].
+#[derive(Clone, Copy, Debug)]
+pub struct EventSinkBatching {
+    /// The maximum number of events in a batch. Defaults to 100.
+    pub max_events: usize,
+    /// The longest time to wait for more events before sending a batch that isn't full. Defaults
+    /// to 50 milliseconds.
+    pub max_delay: Duration,
+    /// The number of events to buffer while the sink is busy. Defaults to 10000.
+    pub buffer: usize,
+}
+
+impl Default for EventSinkBatching {
+    fn default() -> Self {
+        Self {
+            max_events: 100,
+            max_delay: Duration::from_millis(50),
+            buffer: 10_000,
+        }
+    }
+}
+
+/// The sending half of an [`EventSink`], which is given to each shard.
+#[derive(Clone)]
+pub struct EventSinkSender {
+    sink: Arc<dyn EventSink>,
+    tx: mpsc::Sender<SinkEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl EventSinkSender {
+    /// Spawns a task that passes events on to the sink in batches, and returns the sender that
+    /// shards forward their events to.
+    ///
+    /// This is only needed when creating a [`ShardManager`] manually, as [`Client`] does this
+    /// when enabled with [`ClientBuilder::event_sink`]. The task ends once all senders are
+    /// dropped.
+    ///
+    /// [`ShardManager`]: super::ShardManager
+    /// [`Client`]: super::client::Client
+    /// [`ClientBuilder::event_sink`]: super::client::ClientBuilder::event_sink
+    #[must_use]
+    pub fn spawn(sink: Arc<dyn EventSink>, batching: EventSinkBatching) -> Self {
+        let (tx, rx) = mpsc::channel(batching.buffer.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let task_sink = Arc::clone(&sink);
+        let task_dropped = Arc::clone(&dropped);
+        spawn_named("event_sink::run", async move {
+            run_batches(&*task_sink, rx, batching, &task_dropped).await;
+        });
+
+        Self {
+            sink,
+            tx,
+            dropped,
+        }
+    }
+
+    /// Forwards a dispatch, unless the sink doesn't want it.
+    pub(crate) fn forward(&self, shard_id: ShardId, seq: u64, data: &JsonMap, original_str: &str) {
+        let name = data.get("t").and_then(Value::as_str).unwrap_or_default();
+        if !self.sink.wants_event(name) {
+            return;
+        }
+
+        // Dispatches decoded from ETF have no original text, so it's only built for sinks.
+        let payload = if original_str.is_empty() {
+            #[derive(Serialize)]
+            struct Payload<'a> {
+                op: Opcode,
+                s: u64,
+                #[serde(flatten)]
+                data: &'a JsonMap,
+            }
+
+            let payload = Payload {
+                op: Opcode::Dispatch,
+                s: seq,
+                data,
+            };
+            serde_json::to_string(&payload).expect("JSON values always serialize")
+        } else {
+            original_str.to_owned()
+        };
+
+        let event = SinkEvent {
+            shard_id,
+            seq,
+            name: FixedString::from_str_trunc(name),
+            payload: FixedString::from_string_trunc(payload),
+        };
+        if self.tx.try_send(event).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl std::fmt::Debug for EventSinkSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSinkSender").finish_non_exhaustive()
+    }
+}
+
+async fn run_batches(
+    sink: &dyn EventSink,
+    mut rx: mpsc::Receiver<SinkEvent>,
+    batching: EventSinkBatching,
+    dropped: &AtomicU64,
+) {
+    let max_events = batching.max_events.max(1);
+    while let Some(first) = rx.recv().await {
+        let deadline = Instant::now() + batching.max_delay;
+        let mut batch = vec![first];
+        while batch.len() < max_events {
+            match timeout_at(deadline, rx.recv()).await {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if let Err(why) = sink.send(&batch).await {
+            warn!("[EventSink] Failed to send {} events: {why:?}", batch.len());
+        }
+
+        let dropped = dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("[EventSink] Dropped {dropped} events, as the sink could not keep up");
+        }
+    }
+}
+
+enum Target {
+    Writer(Box<dyn AsyncWrite + Send + Unpin>),
+    #[cfg(unix)]
+    Unix {
+        path: PathBuf,
+        stream: Option<UnixStream>,
+    },
+}
+
+/// An [`EventSink`] that writes every event as a line of JSON, to a file, a Unix socket, or any
+/// other writer.
+///
+/// Each line is an object with the `shard_id`, `seq` and `name` of the event, as well as the
+/// original `payload`:
+///
+/// ```json
+/// {"shard_id":0,"seq":42,"name":"RESUMED","payload":{"op":0,"s":42,"t":"RESUMED","d":{}}}
+/// ```
+pub struct NdjsonSink {
+    target: Mutex<Target>,
+}
+
+impl NdjsonSink {
+    /// Creates a sink writing to the given writer.
+    pub fn new(writer: impl AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self {
+            target: Mutex::new(Target::Writer(Box::new(writer))),
+        }
+    }
+
+    /// Creates a sink appending to the file at the given path, which is created if it doesn't
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be opened.
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let file =
+            tokio::fs::OpenOptions::new().create(true).append(true).open(path.as_ref()).await?;
+        Ok(Self::new(file))
+    }
+
+    /// Creates a sink writing to the Unix socket at the given path.
+    ///
+    /// If the connection breaks, the batch being written is lost, and the next batch is written to
+    /// a new connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if connecting to the socket fails.
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let stream = UnixStream::connect(&path).await?;
+        Ok(Self {
+            target: Mutex::new(Target::Unix {
+                path,
+                stream: Some(stream),
+            }),
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for NdjsonSink {
+    async fn send(&self, events: &[SinkEvent]) -> Result<()> {
+        let mut lines = Vec::new();
+        for event in events {
+            lines.extend_from_slice(b"{\"shard_id\":");
+            lines.extend_from_slice(event.shard_id.0.to_string().as_bytes());
+            lines.extend_from_slice(b",\"seq\":");
+            lines.extend_from_slice(event.seq.to_string().as_bytes());
+            lines.extend_from_slice(b",\"name\":");
+            serde_json::to_writer(&mut lines, event.name.as_str())?;
+            lines.extend_from_slice(b",\"payload\":");
+            lines.extend_from_slice(event.payload.as_bytes());
+            lines.extend_from_slice(b"}\n");
+        }
+
+        match &mut *self.target.lock().await {
+            Target::Writer(writer) => {
+                writer.write_all(&lines).await?;
+                writer.flush().await?;
+            },
+            #[cfg(unix)]
+            Target::Unix {
+                path,
+                stream,
+            } => {
+                let connected = match stream {
+                    Some(connected) => connected,
+                    None => stream.insert(UnixStream::connect(&*path).await?),
+                };
+                if let Err(why) = connected.write_all(&lines).await {
+                    *stream = None;
+                    return Err(why.into());
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for NdjsonSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NdjsonSink").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    struct ChannelSink(mpsc::UnboundedSender<Vec<SinkEvent>>);
+
+    #[async_trait]
+    impl EventSink for ChannelSink {
+        fn wants_event(&self, name: &str) -> bool {
+            name != "TYPING_START"
+        }
+
+        async fn send(&self, events: &[SinkEvent]) -> Result<()> {
+            drop(self.0.send(events.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn dispatch(name: &str) -> JsonMap {
+        match json!({"t": name, "d": {}}) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_batches() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sender = EventSinkSender::spawn(Arc::new(ChannelSink(tx)), EventSinkBatching {
+            max_events: 2,
+            max_delay: Duration::from_secs(10),
+            buffer: 10,
+        });
+
+        let original = r#"{"op":0,"s":1,"t":"MESSAGE_CREATE","d":{}}"#;
+        sender.forward(ShardId(0), 1, &dispatch("MESSAGE_CREATE"), original);
+        sender.forward(ShardId(0), 2, &dispatch("TYPING_START"), "");
+        sender.forward(ShardId(1), 3, &dispatch("GUILD_CREATE"), "{}");
+        sender.forward(ShardId(1), 4, &dispatch("GUILD_DELETE"), "{}");
+
+        let batch = rx.recv().await.unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].payload, original);
+        assert_eq!((batch[1].shard_id, batch[1].seq), (ShardId(1), 3));
+        assert_eq!(batch[1].name, "GUILD_CREATE");
+
+        // The last batch is sent once the senders are gone.
+        drop(sender);
+        let batch = rx.recv().await.unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].seq, 4);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_sink() {
+        let (writer, mut reader) = tokio::io::duplex(1024);
+        let sink = NdjsonSink::new(writer);
+        let event = SinkEvent {
+            shard_id: ShardId(2),
+            seq: 7,
+            name: FixedString::from_static_trunc("READY"),
+            payload: FixedString::from_static_trunc(r#"{"op":0,"s":7,"t":"READY","d":{}}"#),
+        };
+        sink.send(&[event.clone(), event]).await.unwrap();
+        drop(sink);
+
+        let mut output = String::new();
+        reader.read_to_string(&mut output).await.unwrap();
+        let line =
+            r#"{"shard_id":2,"seq":7,"name":"READY","payload":{"op":0,"s":7,"t":"READY","d":{}}}"#;
+        assert_eq!(output, format!("{line}\n{line}\n"));
+    }
+}
@@ -0,0 +1,1078 @@
+//! A local stand-in for Discord's gateway, for testing shards and event handlers without Discord.
+//!
+//! The [`FakeGateway`] speaks enough of the gateway protocol for a [`Shard`] to connect, identify,
+//! heartbeat and resume, and lets tests dispatch events and inject faults such as reconnect
+//! requests, invalidated sessions and dropped connections. Point a [`Client`] at it with
+//! [`ClientBuilder::gateway_url`]:
+//!
+//! ```rust,no_run
+//! use serenity::gateway::fake::FakeGateway;
+//! use serenity::prelude::*;
+//! use serenity::Client;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let gateway = FakeGateway::bind().await?;
+//!
+//! let token: Token = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GHIJKL.MNOPQRSTUVWXYZabcdefghijklmnopqrstuv".parse()?;
+//! let mut client = Client::builder(token, GatewayIntents::non_privileged())
+//!     .gateway_url(gateway.url())
+//!     .await?;
+//!
+//! tokio::spawn(async move { client.start().await });
+//!
+//! gateway.dispatch("TYPING_START", serde_json::json!({
+//!     "channel_id": "2",
+//!     "user_id": "3",
+//!     "timestamp": 1_700_000_000,
+//! }));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Transport compression and the ETF encoding are supported, as requested by the connecting shard.
+//!
+//! [`Shard`]: super::Shard
+//! [`Client`]: super::client::Client
+//! [`ClientBuilder::gateway_url`]: super::client::ClientBuilder::gateway_url
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "transport_compression_zlib")]
+use flate2::{Compress as ZlibDeflater, Compression as ZlibLevel, FlushCompress};
+use futures::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use serde_json::json;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, warn};
+#[cfg(feature = "transport_compression_zstd")]
+use zstd_safe::{CCtx as ZstdDeflater, InBuffer, OutBuffer};
+
+use super::etf;
+use crate::constants::{self, Opcode};
+use crate::internal::prelude::*;
+
+/// The default heartbeat interval sent in the Hello payload, matching Discord's.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(41250);
+
+/// The ID of the user and application that shards connected to the fake gateway are logged in as.
+pub const FAKE_USER_ID: u64 = 1;
+
+/// A payload sent by a shard to the [`FakeGateway`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ReceivedPayload {
+    /// Identifies the connection the payload was received on, counting up from 0.
+    pub connection: u64,
+    pub op: Opcode,
+    pub data: Value,
+}
+
+#[derive(Clone, Debug)]
+enum Command {
+    Dispatch(Arc<str>, Value),
+    Reconnect,
+    InvalidateSession(bool),
+    Close(u16),
+    Disconnect,
+}
+
+#[derive(Debug)]
+struct State {
+    url: Arc<str>,
+    heartbeat_interval: Duration,
+    ack_heartbeats: bool,
+    /// The last sequence number of every session that can be resumed.
+    sessions: HashMap<String, u64>,
+    next_session: u64,
+}
+
+/// A local WebSocket server that stands in for Discord's gateway.
+///
+/// Shards that connect are sent a Hello, and get a Ready dispatch in response to an Identify,
+/// logged in as the user with Id [`FAKE_USER_ID`]. Heartbeats are acknowledged and sessions can be
+/// resumed, until they are invalidated.
+///
+/// Commands such as [`Self::dispatch`] apply to all shards that are connected at the time. The
+/// server shuts down when the [`FakeGateway`] is dropped.
+#[derive(Debug)]
+pub struct FakeGateway {
+    state: Arc<Mutex<State>>,
+    commands: broadcast::Sender<Command>,
+    payloads: mpsc::UnboundedReceiver<ReceivedPayload>,
+    task: JoinHandle<()>,
+}
+
+impl FakeGateway {
+    /// Starts a fake gateway listening on a random local port.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if binding the port fails.
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+        let url = format!("ws://{}", listener.local_addr()?);
+
+        let state = Arc::new(Mutex::new(State {
+            url: url.into(),
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            ack_heartbeats: true,
+            sessions: HashMap::new(),
+            next_session: 0,
+        }));
+        let (commands, _) = broadcast::channel(256);
+        let (payload_tx, payloads) = mpsc::unbounded_channel();
+
+        let task =
+            tokio::spawn(accept_loop(listener, Arc::clone(&state), commands.clone(), payload_tx));
+
+        Ok(Self {
+            state,
+            commands,
+            payloads,
+            task,
+        })
+    }
+
+    /// Returns the URL to connect to the fake gateway with.
+    #[must_use]
+    pub fn url(&self) -> Arc<str> {
+        Arc::clone(&self.state.lock().url)
+    }
+
+    /// Sets the heartbeat interval sent to shards that connect from now on.
+    pub fn set_heartbeat_interval(&self, interval: Duration) {
+        self.state.lock().heartbeat_interval = interval;
+    }
+
+    /// Sets whether heartbeats are acknowledged. Disabling this simulates a zombied connection.
+    pub fn set_heartbeat_acks(&self, enabled: bool) {
+        self.state.lock().ack_heartbeats = enabled;
+    }
+
+    /// Dispatches an event with the given name and data to all identified shards.
+    pub fn dispatch(&self, kind: &str, data: Value) {
+        self.send(Command::Dispatch(kind.into(), data));
+    }
+
+    /// Requests all connected shards to reconnect and resume.
+    pub fn reconnect(&self) {
+        self.send(Command::Reconnect);
+    }
+
+    /// Invalidates the sessions of all connected shards. Unless `resumable` is set, the sessions
+    /// can no longer be resumed.
+    pub fn invalidate_session(&self, resumable: bool) {
+        if !resumable {
+            self.state.lock().sessions.clear();
+        }
+
+        self.send(Command::InvalidateSession(resumable));
+    }
+
+    /// Closes all connections with the given close code.
+    pub fn close(&self, code: u16) {
+        self.send(Command::Close(code));
+    }
+
+    /// Drops all connections without a close frame, as if the network failed.
+    pub fn disconnect(&self) {
+        self.send(Command::Disconnect);
+    }
+
+    /// Waits for the next payload sent by a shard, such as an Identify or a heartbeat.
+    ///
+    /// Returns [`None`] if the server has stopped.
+    pub async fn next_payload(&mut self) -> Option<ReceivedPayload> {
+        self.payloads.recv().await
+    }
+
+    /// Waits for the next payload with the given opcode, skipping all others.
+    pub async fn next_payload_with(&mut self, op: Opcode) -> Option<ReceivedPayload> {
+        loop {
+            let payload = self.next_payload().await?;
+            if payload.op == op {
+                return Some(payload);
+            }
+        }
+    }
+
+    fn send(&self, command: Command) {
+        // Fails only if no shard is connected, in which case there is nobody to send to.
+        drop(self.commands.send(command));
+    }
+}
+
+impl Drop for FakeGateway {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    state: Arc<Mutex<State>>,
+    commands: broadcast::Sender<Command>,
+    payloads: mpsc::UnboundedSender<ReceivedPayload>,
+) {
+    let next_connection = AtomicU64::new(0);
+    let mut connections = Vec::new();
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(why) => {
+                warn!("[FakeGateway] Failed to accept connection: {why:?}");
+                continue;
+            },
+        };
+
+        let id = next_connection.fetch_add(1, Ordering::Relaxed);
+        let connection = Connection {
+            id,
+            state: Arc::clone(&state),
+            payloads: payloads.clone(),
+            session_id: None,
+            seq: 0,
+        };
+
+        connections.retain(|task: &JoinHandle<()>| !task.is_finished());
+        connections.push(tokio::spawn(connection.run(stream, commands.subscribe())));
+    }
+}
+
+struct Connection {
+    id: u64,
+    state: Arc<Mutex<State>>,
+    payloads: mpsc::UnboundedSender<ReceivedPayload>,
+    session_id: Option<String>,
+    seq: u64,
+}
+
+impl Connection {
+    async fn run(mut self, stream: TcpStream, mut commands: broadcast::Receiver<Command>) {
+        let mut query = String::new();
+        #[expect(clippy::result_large_err)] // The error type is dictated by tungstenite
+        let callback = |request: &Request, response: Response| {
+            request.uri().query().unwrap_or_default().clone_into(&mut query);
+            Ok(response)
+        };
+
+        let ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+            Ok(ws) => ws,
+            Err(why) => {
+                warn!("[FakeGateway] WebSocket handshake failed: {why:?}");
+                return;
+            },
+        };
+
+        let mut writer = match Writer::new(ws, &query) {
+            Ok(writer) => writer,
+            Err(why) => {
+                warn!("[FakeGateway] Unsupported connection parameters `{query}`: {why}");
+                return;
+            },
+        };
+
+        let heartbeat_interval = self.state.lock().heartbeat_interval;
+        let hello = json!({
+            "op": Opcode::Hello,
+            "d": {"heartbeat_interval": heartbeat_interval.as_millis()},
+        });
+        if writer.send(&hello).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                message = writer.ws.next() => {
+                    let Some(Ok(message)) = message else {
+                        break;
+                    };
+
+                    match self.handle_message(&mut writer, message).await {
+                        Ok(true) => {},
+                        Ok(false) | Err(_) => break,
+                    }
+                },
+                command = commands.recv() => {
+                    let command = match command {
+                        Ok(command) => command,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+
+                    match self.handle_command(&mut writer, command).await {
+                        Ok(true) => {},
+                        Ok(false) | Err(_) => break,
+                    }
+                },
+            }
+        }
+
+        debug!("[FakeGateway] Connection {} ended", self.id);
+    }
+
+    /// Returns whether the connection is still open.
+    async fn handle_message(&mut self, writer: &mut Writer, message: Message) -> Result<bool> {
+        #[derive(Deserialize)]
+        struct Payload {
+            op: Opcode,
+            #[serde(default)]
+            d: Value,
+        }
+
+        let payload: Payload = match message {
+            Message::Text(text) => serde_json::from_str(&text)?,
+            Message::Binary(bytes) => etf::from_slice(&bytes)?,
+            Message::Close(_) => return Ok(false),
+            _ => return Ok(true),
+        };
+
+        // The receiving half may have been dropped if the test does not care about payloads.
+        drop(self.payloads.send(ReceivedPayload {
+            connection: self.id,
+            op: payload.op,
+            data: payload.d.clone(),
+        }));
+
+        match payload.op {
+            Opcode::Heartbeat if self.state.lock().ack_heartbeats => {
+                writer.send(&json!({"op": Opcode::HeartbeatAck})).await?;
+            },
+            Opcode::Identify => {
+                let (session_id, url) = {
+                    let mut state = self.state.lock();
+                    state.next_session += 1;
+                    (format!("fake-session-{}", state.next_session), Arc::clone(&state.url))
+                };
+
+                self.seq = 0;
+                self.session_id = Some(session_id.clone());
+
+                let ready = json!({
+                    "v": constants::GATEWAY_VERSION,
+                    "user": {
+                        "id": FAKE_USER_ID.to_string(),
+                        "username": "fake",
+                        "discriminator": "0",
+                        "avatar": null,
+                        "bot": true,
+                    },
+                    "guilds": [],
+                    "session_id": session_id,
+                    "resume_gateway_url": &*url,
+                    "shard": payload.d.get("shard"),
+                    "application": {"id": FAKE_USER_ID.to_string(), "flags": 0},
+                });
+                self.dispatch(writer, "READY", ready).await?;
+            },
+            Opcode::Resume => {
+                let session_id = payload.d.get("session_id").and_then(Value::as_str);
+                let seq = session_id.and_then(|id| self.state.lock().sessions.get(id).copied());
+
+                if let (Some(session_id), Some(seq)) = (session_id, seq) {
+                    self.session_id = Some(session_id.to_owned());
+                    self.seq = seq;
+                    self.dispatch(writer, "RESUMED", json!({})).await?;
+                } else {
+                    let invalid = json!({"op": Opcode::InvalidSession, "d": false});
+                    writer.send(&invalid).await?;
+                }
+            },
+            _ => {},
+        }
+
+        Ok(true)
+    }
+
+    /// Returns whether the connection is still open.
+    async fn handle_command(&mut self, writer: &mut Writer, command: Command) -> Result<bool> {
+        match command {
+            Command::Dispatch(kind, data) => {
+                if self.session_id.is_some() {
+                    self.dispatch(writer, &kind, data).await?;
+                }
+            },
+            Command::Reconnect => writer.send(&json!({"op": Opcode::Reconnect})).await?,
+            Command::InvalidateSession(resumable) => {
+                if !resumable {
+                    self.session_id = None;
+                }
+
+                writer.send(&json!({"op": Opcode::InvalidSession, "d": resumable})).await?;
+            },
+            Command::Close(code) => {
+                let frame = CloseFrame {
+                    code: CloseCode::from(code),
+                    reason: "".into(),
+                };
+                // The connection is being closed either way.
+                drop(writer.ws.close(Some(frame)).await);
+                return Ok(false);
+            },
+            Command::Disconnect => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    async fn dispatch(&mut self, writer: &mut Writer, kind: &str, data: Value) -> Result<()> {
+        self.seq += 1;
+        if let Some(session_id) = &self.session_id {
+            self.state.lock().sessions.insert(session_id.clone(), self.seq);
+        }
+
+        writer.send(&json!({"op": Opcode::Dispatch, "s": self.seq, "t": kind, "d": data})).await
+    }
+}
+
+enum Compressor {
+    None,
+    #[cfg(feature = "transport_compression_zlib")]
+    Zlib(Box<ZlibDeflater>),
+    #[cfg(feature = "transport_compression_zstd")]
+    Zstd(ZstdDeflater<'static>),
+}
+
+impl Compressor {
+    fn compress(&mut self, bytes: Vec<u8>) -> Vec<u8> {
+        match self {
+            Self::None => bytes,
+            #[cfg(feature = "transport_compression_zlib")]
+            Self::Zlib(deflater) => {
+                let mut output = Vec::with_capacity(bytes.len() + 64);
+                let mut input = &bytes[..];
+                loop {
+                    let before = deflater.total_in();
+                    deflater
+                        .compress_vec(input, &mut output, FlushCompress::Sync)
+                        .expect("a sync flush into a growable buffer cannot fail");
+                    input = &input[(deflater.total_in() - before) as usize..];
+
+                    // The flush is complete once there is spare room left in the output.
+                    if input.is_empty() && output.len() < output.capacity() {
+                        return output;
+                    }
+
+                    output.reserve(output.capacity());
+                }
+            },
+            #[cfg(feature = "transport_compression_zstd")]
+            Self::Zstd(deflater) => {
+                let mut output = Vec::new();
+                let mut chunk = vec![0; bytes.len() + 64];
+                let mut input = InBuffer::around(&bytes);
+                loop {
+                    let mut out_buffer = OutBuffer::around(&mut chunk[..]);
+                    let remaining = deflater
+                        .compress_stream2(
+                            &mut out_buffer,
+                            &mut input,
+                            zstd_safe::zstd_sys::ZSTD_EndDirective::ZSTD_e_flush,
+                        )
+                        .expect("flushing a zstd stream cannot fail");
+
+                    output.extend_from_slice(out_buffer.as_slice());
+                    if remaining == 0 {
+                        return output;
+                    }
+                }
+            },
+        }
+    }
+}
+
+struct Writer {
+    ws: WebSocketStream<TcpStream>,
+    etf: bool,
+    compressor: Compressor,
+}
+
+impl Writer {
+    fn new(ws: WebSocketStream<TcpStream>, query: &str) -> StdResult<Self, String> {
+        let mut etf = false;
+        let mut compress = None;
+
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            match (key, value) {
+                ("encoding", "json") => etf = false,
+                ("encoding", "etf") => etf = true,
+                ("compress", _) => compress = Some(value),
+                ("encoding", _) => return Err(format!("unsupported encoding {value}")),
+                _ => {},
+            }
+        }
+
+        let compressor = match compress {
+            None => Compressor::None,
+            #[cfg(feature = "transport_compression_zlib")]
+            Some("zlib-stream") => {
+                Compressor::Zlib(Box::new(ZlibDeflater::new(ZlibLevel::fast(), true)))
+            },
+            #[cfg(feature = "transport_compression_zstd")]
+            Some("zstd-stream") => Compressor::Zstd(ZstdDeflater::create()),
+            Some(compress) => return Err(format!("unsupported compression {compress}")),
+        };
+
+        Ok(Self {
+            ws,
+            etf,
+            compressor,
+        })
+    }
+
+    async fn send(&mut self, payload: &Value) -> Result<()> {
+        let bytes = if self.etf { etf::to_vec(payload) } else { serde_json::to_vec(payload)? };
+
+        let message = match &self.compressor {
+            Compressor::None if !self.etf => {
+                Message::Text(String::from_utf8(bytes).expect("JSON is valid UTF-8"))
+            },
+            _ => Message::Binary(self.compressor.compress(bytes)),
+        };
+
+        self.ws.send(message).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU16;
+
+    use super::*;
+    use crate::gateway::ws::WantedEvents;
+    use crate::gateway::{
+        GatewayEncoding,
+        ReconnectType,
+        Shard,
+        ShardAction,
+        ShardSession,
+        TransportCompression,
+    };
+    use crate::model::event::{Event, GatewayEvent};
+    use crate::model::gateway::{GatewayIntents, ShardInfo};
+    use crate::model::id::ShardId;
+
+    async fn next_event(shard: &mut Shard) -> (Option<ShardAction>, Option<Event>) {
+        loop {
+            if let Some(event) = shard.client.recv_event(None).await.unwrap() {
+                return shard.handle_event(Ok(event)).unwrap();
+            }
+        }
+    }
+
+    const TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GHIJKL.MNOPQRSTUVWXYZabcdefghijklmnopqrstuv";
+
+    async fn identified_shard(
+        gateway: &FakeGateway,
+        compression: TransportCompression,
+        encoding: GatewayEncoding,
+    ) -> Shard {
+        let shard_info = ShardInfo {
+            id: ShardId(0),
+            total: NonZeroU16::MIN,
+        };
+
+        let mut shard = Shard::new(
+            gateway.url(),
+            TOKEN.parse().unwrap(),
+            shard_info,
+            GatewayIntents::non_privileged(),
+            None,
+            compression,
+            encoding,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(next_event(&mut shard).await, (Some(ShardAction::Identify), None)));
+        shard.identify().await.unwrap();
+        assert!(matches!(next_event(&mut shard).await, (None, Some(Event::Ready(_)))));
+
+        shard
+    }
+
+    #[tokio::test]
+    async fn test_identify_and_dispatch() {
+        let mut gateway = FakeGateway::bind().await.unwrap();
+        let mut shard =
+            identified_shard(&gateway, TransportCompression::None, GatewayEncoding::Etf).await;
+
+        let identify = gateway.next_payload_with(Opcode::Identify).await.unwrap();
+        assert_eq!(identify.data["token"], format!("Bot {TOKEN}"));
+        assert_eq!(shard.session_id(), Some("fake-session-1"));
+
+        gateway.dispatch("BRAND_NEW_EVENT", json!({"id": 5}));
+        let (_, event) = next_event(&mut shard).await;
+        assert!(matches!(event, Some(Event::Unknown { kind, .. }) if kind == "BRAND_NEW_EVENT"));
+        assert_eq!(shard.seq(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resume_saved_session() {
+        let gateway = FakeGateway::bind().await.unwrap();
+        let mut shard =
+            identified_shard(&gateway, TransportCompression::None, GatewayEncoding::Json).await;
+
+        gateway.dispatch("BRAND_NEW_EVENT", json!({}));
+        next_event(&mut shard).await;
+        let session = shard.session().unwrap();
+        assert_eq!(session.seq, 2);
+        drop(shard);
+
+        // Sessions survive serialization, as if the process was restarted.
+        let session: ShardSession =
+            serde_json::from_str(&serde_json::to_string(&session).unwrap()).unwrap();
+
+        let mut shard = Shard::new(
+            session.resume_ws_url.as_str().into(),
+            TOKEN.parse().unwrap(),
+            session.shard_info,
+            GatewayIntents::non_privileged(),
+            None,
+            TransportCompression::None,
+            GatewayEncoding::Json,
+        )
+        .await
+        .unwrap();
+        shard.resume_session(session).await.unwrap();
+
+        loop {
+            if let (_, Some(event)) = next_event(&mut shard).await {
+                assert!(matches!(event, Event::Resumed(_)));
+                break;
+            }
+        }
+        assert_eq!(shard.seq(), 3);
+        assert!(shard.heartbeat_interval().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_skip_unwanted_dispatch() {
+        let wanted = WantedEvents::from([Box::from("MESSAGE_CREATE")]);
+
+        for encoding in [GatewayEncoding::Json, GatewayEncoding::Etf] {
+            let gateway = FakeGateway::bind().await.unwrap();
+            let mut shard = identified_shard(&gateway, TransportCompression::None, encoding).await;
+
+            // The data is never deserialized, so it doesn't have to be valid.
+            gateway.dispatch("TYPING_START", json!({}));
+            let event = loop {
+                if let Some(event) = shard.client.recv_event(Some(&wanted)).await.unwrap() {
+                    break event;
+                }
+            };
+
+            let GatewayEvent::SkippedDispatch {
+                kind, ..
+            } = &event
+            else {
+                panic!("expected a skipped dispatch, got {event:?}");
+            };
+            assert_eq!(kind, "TYPING_START");
+            assert!(matches!(shard.handle_event(Ok(event)), Ok((None, None))));
+            assert_eq!(shard.seq(), 2);
+        }
+    }
+
+    #[cfg(feature = "transport_compression_zlib")]
+    #[tokio::test]
+    async fn test_reconnect_and_resume() {
+        let gateway = FakeGateway::bind().await.unwrap();
+        let mut shard =
+            identified_shard(&gateway, TransportCompression::Zlib, GatewayEncoding::Json).await;
+
+        gateway.reconnect();
+        let action = next_event(&mut shard).await.0;
+        assert!(matches!(action, Some(ShardAction::Reconnect(ReconnectType::Resume))));
+
+        shard.resume().await.unwrap();
+        loop {
+            if let (_, Some(event)) = next_event(&mut shard).await {
+                assert!(matches!(event, Event::Resumed(_)));
+                break;
+            }
+        }
+
+        gateway.invalidate_session(false);
+        let action = next_event(&mut shard).await.0;
+        assert!(matches!(action, Some(ShardAction::Reconnect(ReconnectType::Reidentify))));
+    }
+
+    fn manager_options(gateway: &FakeGateway) -> crate::gateway::ShardManagerOptions {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use crate::gateway::client::DispatchMode;
+        use crate::gateway::ShardManagerOptions;
+        use crate::http::Http;
+
+        ShardManagerOptions {
+            token: TOKEN.parse().unwrap(),
+            data: Arc::new(()),
+            event_handler: None,
+            raw_event_handler: None,
+            #[cfg(feature = "framework")]
+            framework: Arc::default(),
+            #[cfg(feature = "voice")]
+            voice_manager: None,
+            ws_url: gateway.url(),
+            shard_total: NonZeroU16::MIN,
+            #[cfg(feature = "cache")]
+            cache: Arc::default(),
+            http: Arc::new(Http::new(TOKEN.parse().unwrap())),
+            intents: GatewayIntents::non_privileged(),
+            presence: None,
+            max_concurrency: NonZeroU16::MIN,
+            wait_time_between_shard_start: Duration::from_millis(10),
+            compression: TransportCompression::None,
+            encoding: GatewayEncoding::Json,
+            event_recorder: None,
+            resume_sessions: Vec::new(),
+            identify_queue: None,
+            event_stream: None,
+            event_sink: None,
+            dispatch_mode: DispatchMode::Unbounded,
+            session_start_limit: None,
+            presence_rotation: None,
+            health_thresholds: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reshard() {
+        use std::time::Duration;
+
+        use crate::gateway::ShardManager;
+
+        let mut gateway = FakeGateway::bind().await.unwrap();
+        let (manager, _) = ShardManager::new(manager_options(&gateway));
+
+        manager.initialize(0, 1, NonZeroU16::MIN);
+        let identify = gateway.next_payload_with(Opcode::Identify).await.unwrap();
+        assert_eq!(identify.data["shard"], json!([0, 1]));
+
+        let shard_total = NonZeroU16::new(2).unwrap();
+        tokio::time::timeout(Duration::from_secs(10), manager.reshard(shard_total))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut shards = Vec::new();
+        for _ in 0..2 {
+            let identify = gateway.next_payload_with(Opcode::Identify).await.unwrap();
+            shards.push(identify.data["shard"].clone());
+        }
+        shards.sort_by_key(|shard| shard[0].as_u64());
+        assert_eq!(shards, [json!([0, 2]), json!([1, 2])]);
+        let mut shard_ids = manager.shards_instantiated().await;
+        shard_ids.sort_unstable();
+        assert_eq!(shard_ids, [ShardId(0), ShardId(1)]);
+
+        manager.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_reshard_abort() {
+        use std::time::Duration;
+
+        use crate::gateway::ShardManager;
+
+        let gateway = FakeGateway::bind().await.unwrap();
+        let (manager, _) = ShardManager::new(manager_options(&gateway));
+
+        manager.initialize(0, 1, NonZeroU16::MIN);
+
+        // Dropping the reshard before the switch aborts it, so that another one can be started.
+        let shard_total = NonZeroU16::new(2).unwrap();
+        let reshard = manager.reshard(shard_total);
+        assert!(tokio::time::timeout(Duration::from_millis(10), reshard).await.is_err());
+        tokio::time::timeout(Duration::from_secs(10), manager.reshard(shard_total))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut shard_ids = manager.shards_instantiated().await;
+        shard_ids.sort_unstable();
+        assert_eq!(shard_ids, [ShardId(0), ShardId(1)]);
+
+        manager.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_start_limit() {
+        use std::time::Duration;
+
+        use crate::gateway::ShardManager;
+
+        let mut gateway = FakeGateway::bind().await.unwrap();
+        let mut options = manager_options(&gateway);
+        options.session_start_limit = Some(
+            serde_json::from_value(json!({
+                "remaining": 0,
+                "reset_after": 3_600_000,
+                "total": 1000,
+                "max_concurrency": 1,
+            }))
+            .unwrap(),
+        );
+        let (manager, _) = ShardManager::new(options);
+
+        // The shard stays queued instead of identifying until the limit resets.
+        manager.initialize(0, 1, NonZeroU16::MIN);
+        let identify = gateway.next_payload_with(Opcode::Identify);
+        assert!(tokio::time::timeout(Duration::from_millis(200), identify).await.is_err());
+        assert!(manager.shards_instantiated().await.is_empty());
+
+        manager.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_presence_rotation() {
+        use std::time::Duration;
+
+        use crate::gateway::{ActivityData, PresenceRotation, ShardManager};
+
+        let mut gateway = FakeGateway::bind().await.unwrap();
+        let mut options = manager_options(&gateway);
+        options.presence_rotation = Some(PresenceRotation::new(
+            vec![ActivityData::playing("shard {shard_id} of {shard_total}")],
+            Duration::from_secs(60),
+        ));
+        let (manager, _) = ShardManager::new(options);
+
+        // The first activity is applied once the shard has connected.
+        manager.initialize(0, 1, NonZeroU16::MIN);
+        let update = gateway.next_payload_with(Opcode::PresenceUpdate).await.unwrap();
+        assert_eq!(update.data["activities"][0]["name"], "shard 0 of 1");
+
+        manager.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_event_sink() {
+        use std::sync::Arc;
+
+        use async_trait::async_trait;
+        use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+        use crate::gateway::{
+            EventSink,
+            EventSinkBatching,
+            EventSinkSender,
+            ShardManager,
+            SinkEvent,
+        };
+
+        struct ChannelSink(UnboundedSender<SinkEvent>);
+
+        #[async_trait]
+        impl EventSink for ChannelSink {
+            fn wants_event(&self, name: &str) -> bool {
+                name == "BRAND_NEW_EVENT"
+            }
+
+            async fn send(&self, events: &[SinkEvent]) -> Result<()> {
+                for event in events {
+                    drop(self.0.send(event.clone()));
+                }
+                Ok(())
+            }
+        }
+
+        // Payloads decoded from ETF are converted to JSON.
+        for encoding in [GatewayEncoding::Json, GatewayEncoding::Etf] {
+            let gateway = FakeGateway::bind().await.unwrap();
+            let (tx, mut rx) = unbounded_channel();
+            let mut options = manager_options(&gateway);
+            options.encoding = encoding;
+            options.event_sink = Some(EventSinkSender::spawn(
+                Arc::new(ChannelSink(tx)),
+                EventSinkBatching::default(),
+            ));
+            let (manager, _) = ShardManager::new(options);
+            manager.initialize(0, 1, NonZeroU16::MIN);
+
+            // Dispatch until the shard has connected and forwards the event.
+            let event = loop {
+                gateway.dispatch("BRAND_NEW_EVENT", json!({"answer": 42}));
+                let recv = tokio::time::timeout(std::time::Duration::from_millis(100), rx.recv());
+                if let Ok(event) = recv.await {
+                    break event.unwrap();
+                }
+            };
+            assert_eq!(event.shard_id, ShardId(0));
+            let payload: Value = serde_json::from_str(&event.payload).unwrap();
+            assert_eq!(payload["op"], 0, "{encoding:?}");
+            assert_eq!(payload["s"], event.seq, "{encoding:?}");
+            assert_eq!(payload["t"], "BRAND_NEW_EVENT", "{encoding:?}");
+            assert_eq!(payload["d"], json!({"answer": 42}), "{encoding:?}");
+
+            manager.shutdown_all().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use async_trait::async_trait;
+        use parking_lot::Mutex;
+        use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+        use crate::gateway::client::{Context, EventHandler};
+        use crate::gateway::ShardManager;
+
+        struct Handler {
+            started: UnboundedSender<()>,
+            log: Arc<Mutex<Vec<&'static str>>>,
+        }
+
+        #[async_trait]
+        impl EventHandler for Handler {
+            async fn unknown_event(&self, _: Context, _: FixedString, _: Value) {
+                self.started.send(()).unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                self.log.lock().push("handled");
+            }
+
+            async fn shutdown(&self) {
+                self.log.lock().push("shutdown");
+            }
+        }
+
+        let gateway = FakeGateway::bind().await.unwrap();
+        let (started, mut started_rx) = unbounded_channel();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut options = manager_options(&gateway);
+        options.event_handler = Some(Arc::new(Handler {
+            started,
+            log: Arc::clone(&log),
+        }));
+        let (manager, _) = ShardManager::new(options);
+        manager.initialize(0, 1, NonZeroU16::MIN);
+
+        // Dispatch until the shard has connected and a handler is running.
+        loop {
+            gateway.dispatch("BRAND_NEW_EVENT", json!({}));
+            let recv = tokio::time::timeout(Duration::from_millis(50), started_rx.recv());
+            if recv.await.is_ok() {
+                break;
+            }
+        }
+
+        // The running handler finishes before the shutdown hook, and later events are dropped.
+        assert!(manager.shutdown_gracefully(Duration::from_secs(5)).await);
+        let handled = log.lock().iter().filter(|entry| **entry == "handled").count();
+        assert!(handled >= 1);
+        assert_eq!(log.lock().last(), Some(&"shutdown"));
+        assert!(manager.runners.lock().await.is_empty());
+
+        gateway.dispatch("BRAND_NEW_EVENT", json!({}));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(log.lock().len(), handled + 1);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_shard_restart() {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use async_trait::async_trait;
+        use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+        use crate::gateway::client::{Context, EventHandler};
+        use crate::gateway::{
+            ShardHealthThresholds,
+            ShardManager,
+            ShardUnhealthyEvent,
+            ShardUnhealthyReason,
+        };
+
+        struct Handler(UnboundedSender<ShardUnhealthyEvent>);
+
+        #[async_trait]
+        impl EventHandler for Handler {
+            async fn shard_unhealthy(&self, _: Context, event: ShardUnhealthyEvent) {
+                self.0.send(event).unwrap();
+            }
+        }
+
+        let mut gateway = FakeGateway::bind().await.unwrap();
+        let (tx, mut rx) = unbounded_channel();
+        let mut options = manager_options(&gateway);
+        options.event_handler = Some(Arc::new(Handler(tx)));
+        options.health_thresholds = Some(ShardHealthThresholds {
+            max_since_heartbeat_ack: None,
+            max_since_dispatch: Some(Duration::from_millis(300)),
+            restart: true,
+        });
+        let (manager, _) = ShardManager::new(options);
+        manager.initialize(0, 1, NonZeroU16::MIN);
+        gateway.next_payload_with(Opcode::Identify).await.unwrap();
+
+        // Nothing is dispatched after `READY`, so the shard is restarted.
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.shard_id, ShardId(0));
+        assert_eq!(event.reason, ShardUnhealthyReason::NoDispatches);
+        assert!(event.restarting);
+        assert_eq!(event.health.dispatches, 1);
+        gateway.next_payload_with(Opcode::Identify).await.unwrap();
+
+        let health = loop {
+            let health = manager.health().await;
+            if health.first().is_some_and(|health| health.reidentifies == 1) {
+                break health;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(health[0].unhealthy_restarts, 1);
+        assert_eq!(health[0].reconnects, 1);
+
+        manager.shutdown_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        use futures::StreamExt;
+
+        use crate::gateway::client::{Client, FullEvent};
+
+        let gateway = FakeGateway::bind().await.unwrap();
+        let mut client = Client::builder(TOKEN.parse().unwrap(), GatewayIntents::non_privileged())
+            .gateway_url(gateway.url())
+            .event_stream(1)
+            .await
+            .unwrap();
+
+        let mut events = client.event_stream().unwrap();
+        assert!(client.event_stream().is_none());
+        tokio::spawn(async move { client.start().await });
+
+        // With the cache, `ShardsReady` is sent before `Ready`.
+        let ctx = loop {
+            let (ctx, event) = events.next().await.unwrap();
+            if matches!(event, FullEvent::Ready { .. }) {
+                break ctx;
+            }
+        };
+        assert_eq!(ctx.shard_id, ShardId(0));
+
+        gateway.dispatch("BRAND_NEW_EVENT", json!({}));
+        let (_, event) = events.next().await.unwrap();
+        assert!(matches!(event, FullEvent::Unknown { kind, .. } if kind == "BRAND_NEW_EVENT"));
+    }
+}
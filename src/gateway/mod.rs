@@ -12,7 +12,13 @@
 //! [`Client`]: client::Client
 
 pub mod client;
+mod consumer;
 mod error;
+mod etf;
+mod event_sink;
+#[cfg(feature = "fake_gateway")]
+pub mod fake;
+mod recording;
 pub mod sharding;
 #[cfg(feature = "voice")]
 mod voice;
@@ -22,7 +28,10 @@ mod ws;
 use reqwest::IntoUrl;
 use reqwest::Url;
 
+pub use self::consumer::{CommandForwarder, EventConsumer};
 pub use self::error::Error as GatewayError;
+pub use self::event_sink::{EventSink, EventSinkBatching, EventSinkSender, NdjsonSink, SinkEvent};
+pub use self::recording::{EventRecorder, EventReplayer, RecordedEvent};
 pub use self::sharding::*;
 #[cfg(feature = "voice")]
 pub use self::voice::VoiceGatewayManager;
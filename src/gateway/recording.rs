@@ -0,0 +1,544 @@
+//! Recording of gateway events, and deterministic replay of recordings into event handlers.
+//!
+//! An [`EventRecorder`] passed to [`ClientBuilder::event_recorder`] writes every dispatch payload
+//! received by the shards to a file, one JSON object per line. An [`EventReplayer`] can then feed
+//! such a recording into an [`EventHandler`] without connecting to Discord, which makes bugs that
+//! depend on a specific sequence of events reproducible:
+//!
+//! ```rust,no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use std::sync::Arc;
+//!
+//! use serenity::gateway::client::EventHandler;
+//! use serenity::gateway::EventReplayer;
+//! use serenity::http::Http;
+//! use serenity::secrets::Token;
+//!
+//! struct Handler;
+//!
+//! #[serenity::async_trait]
+//! impl EventHandler for Handler {}
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let http = Arc::new(Http::new(Token::from_env("DISCORD_TOKEN")?));
+//! let recording = BufReader::new(File::open("events.ndjson")?);
+//!
+//! EventReplayer::new(http).event_handler(Handler).replay(recording).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ClientBuilder::event_recorder`]: super::client::ClientBuilder::event_recorder
+//! [`EventHandler`]: super::client::EventHandler
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::num::NonZeroU16;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use futures::channel::mpsc;
+use serde::{Deserialize, Deserializer};
+use tokio::sync::{mpsc as tokio_mpsc, oneshot};
+use tokio::time::{sleep, Duration};
+use tracing::{debug, warn};
+
+use super::client::dispatch::dispatch_model;
+use super::client::{Context, EventHandler, RawEventHandler};
+#[cfg(feature = "collector")]
+use super::sharding::run_collectors;
+use super::{Shard, ShardMessenger};
+#[cfg(feature = "cache")]
+use crate::cache::{Cache, Settings as CacheSettings};
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+use crate::http::Http;
+use crate::internal::prelude::*;
+use crate::model::event::{Event, GatewayEvent};
+use crate::model::gateway::ShardInfo;
+use crate::model::id::ShardId;
+use crate::model::Timestamp;
+
+/// A dispatch payload received by a shard, as stored in a recording.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RecordedEvent {
+    /// The shard that received the event.
+    #[serde(deserialize_with = "deserialize_shard_id")]
+    pub shard_id: ShardId,
+    /// The sequence number of the event.
+    pub seq: u64,
+    /// When the event was received.
+    pub timestamp: Timestamp,
+    /// The name and data of the event, as the `t` and `d` fields of the payload.
+    pub payload: JsonMap,
+}
+
+fn deserialize_shard_id<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> StdResult<ShardId, D::Error> {
+    u16::deserialize(deserializer).map(ShardId)
+}
+
+// Avoids cloning the payload when recording.
+#[derive(Serialize)]
+struct RecordedEventRef<'a> {
+    shard_id: ShardId,
+    seq: u64,
+    timestamp: Timestamp,
+    payload: &'a JsonMap,
+}
+
+/// Writes every dispatch payload received by the shards to a recording, as newline-delimited JSON
+/// [`RecordedEvent`]s.
+///
+/// A single recorder is shared by all shards of a [`Client`], so events from different shards are
+/// interleaved in the order they were received. Clones of a recorder write to the same recording.
+///
+/// Events are written by a dedicated thread, so that slow writes don't hold up the shards. The
+/// writer is flushed after each burst of events. At most 4096 events wait to be written, and
+/// further events are dropped until the writer catches up, see [`Self::dropped_events`].
+///
+/// Dropping the recorder doesn't wait for the remaining events to be written, use [`Self::flush`]
+/// for that.
+///
+/// [`Client`]: super::client::Client
+#[derive(Clone)]
+pub struct EventRecorder {
+    tx: tokio_mpsc::Sender<WriterMessage>,
+    dropped: Arc<AtomicU64>,
+}
+
+enum WriterMessage {
+    Event(Vec<u8>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// The most events waiting to be written, beyond which events are dropped.
+const MAX_PENDING_EVENTS: usize = 4096;
+
+/// The most events written before flushing, when events are recorded faster than written.
+const MAX_UNFLUSHED_EVENTS: usize = 256;
+
+impl EventRecorder {
+    /// Creates a recorder writing to the given writer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread writing the events cannot be spawned.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        let (tx, rx) = tokio_mpsc::channel(MAX_PENDING_EVENTS);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer_dropped = Arc::clone(&dropped);
+        std::thread::Builder::new()
+            .name("serenity-event-recorder".into())
+            .spawn(move || write_events(writer, rx, &writer_dropped))
+            .expect("failed to spawn the event recorder thread");
+
+        Self {
+            tx,
+            dropped,
+        }
+    }
+
+    /// Creates a recorder writing to a new file at the given path, truncating any existing file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Returns the number of events that were dropped because they were received faster than
+    /// they could be written.
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Waits for the events recorded so far to be written, and for the writer to be flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the thread writing the events has stopped.
+    pub async fn flush(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        let stopped = || Error::Io(std::io::ErrorKind::BrokenPipe.into());
+        self.tx.send(WriterMessage::Flush(tx)).await.map_err(|_| stopped())?;
+        rx.await.map_err(|_| stopped())
+    }
+
+    pub(crate) fn record(&self, shard_id: ShardId, seq: u64, payload: &JsonMap) -> Result<()> {
+        let event = RecordedEventRef {
+            shard_id,
+            seq,
+            timestamp: Timestamp::now(),
+            payload,
+        };
+
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+
+        match self.tx.try_send(WriterMessage::Event(line)) {
+            Ok(()) => Ok(()),
+            Err(tokio_mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            },
+            Err(tokio_mpsc::error::TrySendError::Closed(_)) => {
+                Err(Error::Io(std::io::ErrorKind::BrokenPipe.into()))
+            },
+        }
+    }
+}
+
+fn write_events(
+    mut writer: impl Write,
+    mut rx: tokio_mpsc::Receiver<WriterMessage>,
+    dropped: &AtomicU64,
+) {
+    let mut reported_dropped = 0;
+    while let Some(first) = rx.blocking_recv() {
+        let mut result = Ok(());
+        let mut flushes = Vec::new();
+        let burst = std::iter::once(first).chain(std::iter::from_fn(|| rx.try_recv().ok()));
+        for message in burst.take(MAX_UNFLUSHED_EVENTS) {
+            match message {
+                WriterMessage::Event(line) => {
+                    result = result.and_then(|()| writer.write_all(&line));
+                },
+                WriterMessage::Flush(tx) => flushes.push(tx),
+            }
+        }
+
+        if let Err(why) = result.and_then(|()| writer.flush()) {
+            warn!("[EventRecorder] Failed to write events: {why:?}");
+        }
+        // Callers of `flush` that stopped waiting are of no concern.
+        for tx in flushes {
+            tx.send(()).ok();
+        }
+
+        let total_dropped = dropped.load(Ordering::Relaxed);
+        if total_dropped > reported_dropped {
+            warn!(
+                "[EventRecorder] Dropped {} events received faster than written",
+                total_dropped - reported_dropped
+            );
+            reported_dropped = total_dropped;
+        }
+    }
+}
+
+impl std::fmt::Debug for EventRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRecorder").finish_non_exhaustive()
+    }
+}
+
+/// Replays a recording made by an [`EventRecorder`] into event handlers, without connecting to
+/// Discord.
+///
+/// Events go through the same [`Shard::handle_event`], cache update and dispatch steps as when
+/// they are received by a shard. Unlike a shard, the replayer waits for the handlers of an event to
+/// finish before dispatching the next event, so that replays are deterministic.
+///
+/// The [`Context`] given to handlers is fully functional, except that messages sent to the shard,
+/// such as presence updates, are discarded.
+#[must_use]
+pub struct EventReplayer {
+    data: Arc<dyn std::any::Any + Send + Sync>,
+    http: Arc<Http>,
+    #[cfg(feature = "cache")]
+    cache: Arc<Cache>,
+    #[cfg(feature = "framework")]
+    framework: Option<Arc<dyn Framework>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    raw_event_handler: Option<Arc<dyn RawEventHandler>>,
+    recorded_speed: bool,
+}
+
+impl EventReplayer {
+    /// Creates a replayer with an empty cache and no handlers.
+    ///
+    /// The [`Http`] instance is given to handlers through the [`Context`]; use one with a token
+    /// that is not used in production to avoid side effects.
+    pub fn new(http: Arc<Http>) -> Self {
+        Self {
+            data: Arc::new(()),
+            http,
+            #[cfg(feature = "cache")]
+            cache: Arc::new(Cache::new_with_settings(CacheSettings::default())),
+            #[cfg(feature = "framework")]
+            framework: None,
+            event_handler: None,
+            raw_event_handler: None,
+            recorded_speed: false,
+        }
+    }
+
+    /// Sets the global data type that can be accessed from [`Context::data`].
+    pub fn data<D: std::any::Any + Send + Sync>(mut self, data: Arc<D>) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the cache to update with the replayed events.
+    #[cfg(feature = "cache")]
+    pub fn cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Sets the framework to dispatch the replayed events to.
+    ///
+    /// **Note**: Unlike with the [`ClientBuilder`], [`Framework::init`] is not called.
+    ///
+    /// [`ClientBuilder`]: super::client::ClientBuilder
+    #[cfg(feature = "framework")]
+    pub fn framework(mut self, framework: Arc<dyn Framework>) -> Self {
+        self.framework = Some(framework);
+        self
+    }
+
+    /// Sets the event handler to dispatch the replayed events to.
+    pub fn event_handler<H>(mut self, event_handler: impl Into<Arc<H>>) -> Self
+    where
+        H: EventHandler + 'static,
+    {
+        self.event_handler = Some(event_handler.into());
+        self
+    }
+
+    /// Sets the raw event handler to dispatch the replayed events to.
+    pub fn raw_event_handler<H>(mut self, raw_event_handler: impl Into<Arc<H>>) -> Self
+    where
+        H: RawEventHandler + 'static,
+    {
+        self.raw_event_handler = Some(raw_event_handler.into());
+        self
+    }
+
+    /// Sets whether to wait between events for as long as passed between them when they were
+    /// recorded. Defaults to `false`, replaying events as fast as the handlers allow.
+    pub fn recorded_speed(mut self, recorded_speed: bool) -> Self {
+        self.recorded_speed = recorded_speed;
+        self
+    }
+
+    /// Replays a recording, returning the number of events that were dispatched.
+    ///
+    /// Events that cannot be deserialized are skipped, as they would be by a shard.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if reading the recording fails, or [`Error::Json`] if a line is not a
+    /// valid [`RecordedEvent`].
+    pub async fn replay(&self, recording: impl BufRead) -> Result<usize> {
+        // Messages sent to the shard by handlers are discarded when the receiver is dropped.
+        let (tx, _rx) = mpsc::unbounded();
+        let messenger = ShardMessenger {
+            tx,
+            #[cfg(feature = "collector")]
+            collectors: Arc::default(),
+            chunk_requests: Arc::default(),
+            queued_commands: Arc::default(),
+            voice_requests: Arc::default(),
+        };
+
+        // Events go through a shard without a connection, to be handled like by a real shard.
+        let mut shards = HashMap::new();
+        let mut dispatched = 0;
+        let mut last_timestamp = None;
+        for line in recording.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let recorded: RecordedEvent = serde_json::from_str(&line)?;
+            if self.recorded_speed {
+                if let Some(last) = last_timestamp.replace(recorded.timestamp) {
+                    let elapsed =
+                        recorded.timestamp.unix_timestamp_millis() - last.unix_timestamp_millis();
+                    sleep(Duration::from_millis(elapsed.max(0).unsigned_abs())).await;
+                }
+            }
+
+            let shard =
+                shards.entry(recorded.shard_id).or_insert_with(|| self.shard(recorded.shard_id));
+            let dispatch = GatewayEvent::Dispatch {
+                seq: recorded.seq,
+                data: recorded.payload,
+                original_str: FixedString::from_string_trunc(line),
+            };
+
+            let Ok((_, Some(event))) = shard.handle_event(Ok(dispatch)) else {
+                continue;
+            };
+
+            if self.dispatch(event, recorded.shard_id, &messenger).await {
+                dispatched += 1;
+            }
+        }
+
+        Ok(dispatched)
+    }
+
+    fn shard(&self, shard_id: ShardId) -> Shard {
+        let shard_info = ShardInfo {
+            id: shard_id,
+            total: NonZeroU16::new(shard_id.0.saturating_add(1)).unwrap_or(NonZeroU16::MAX),
+        };
+
+        let mut shard = Shard::detached(shard_info);
+        let http = Arc::clone(&self.http);
+        shard.set_application_id_callback(move |id| http.set_application_id(id));
+        shard
+    }
+
+    async fn dispatch(&self, event: Event, shard_id: ShardId, messenger: &ShardMessenger) -> bool {
+        debug!("[EventReplayer] Replaying {} for shard {shard_id}", event.name());
+
+        let context = Context::new(
+            Arc::clone(&self.data),
+            messenger.clone(),
+            shard_id,
+            Arc::clone(&self.http),
+            #[cfg(feature = "cache")]
+            Arc::clone(&self.cache),
+        );
+
+        let can_dispatch = self
+            .event_handler
+            .as_ref()
+            .is_none_or(|handler| handler.filter_event(&context, &event))
+            && self
+                .raw_event_handler
+                .as_ref()
+                .is_none_or(|handler| handler.filter_event(&context, &event));
+
+        if !can_dispatch {
+            return false;
+        }
+
+        #[cfg(feature = "collector")]
+        run_collectors(&messenger.collectors, &event);
+
+        dispatch_model(
+            event,
+            context,
+            #[cfg(feature = "framework")]
+            self.framework.clone(),
+            self.event_handler.clone(),
+            self.raw_event_handler.clone(),
+        )
+        .await;
+
+        true
+    }
+}
+
+impl std::fmt::Debug for EventReplayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventReplayer")
+            .field("recorded_speed", &self.recorded_speed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Records a dispatch payload, logging any failure instead of interrupting the shard.
+pub(crate) fn record_event(recorder: &EventRecorder, shard_id: ShardId, seq: u64, data: &JsonMap) {
+    if let Err(why) = recorder.record(shard_id, seq, data) {
+        warn!("[EventRecorder] Failed to record event {seq} of shard {shard_id}: {why:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Names(Mutex<Vec<(ShardId, &'static str)>>);
+
+    #[async_trait::async_trait]
+    impl RawEventHandler for Names {
+        async fn raw_event(&self, ctx: Context, event: &Event) {
+            self.0.lock().unwrap().push((ctx.shard_id, event.name()));
+        }
+    }
+
+    fn payload(json: &str) -> JsonMap {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let buffer = SharedBuffer::default();
+        let recorder = EventRecorder::new(buffer.clone());
+        recorder.record(ShardId(3), 42, &payload(r#"{"t":"RESUMED","d":{}}"#)).unwrap();
+        recorder.record(ShardId(0), 7, &payload(r#"{"t":"NEW_EVENT","d":{"a":1}}"#)).unwrap();
+        recorder.record(ShardId(3), 43, &payload(r#"{"t":"PRESENCE_UPDATE","d":{}}"#)).unwrap();
+        recorder.flush().await.unwrap();
+
+        let recording = buffer.0.lock().unwrap().clone();
+        let line = std::str::from_utf8(&recording).unwrap().lines().next().unwrap();
+        let event: RecordedEvent = serde_json::from_str(line).unwrap();
+        assert_eq!(event.shard_id, ShardId(3));
+        assert_eq!(event.seq, 42);
+
+        // Like a shard, the replayer skips the invalid presence update.
+        let http = Arc::new(Http::without_token());
+        let names = Arc::new(Names::default());
+        let replayer = EventReplayer::new(http).raw_event_handler::<Names>(Arc::clone(&names));
+        assert_eq!(replayer.replay(recording.as_slice()).await.unwrap(), 2);
+        assert_eq!(*names.0.lock().unwrap(), [(ShardId(3), "RESUMED"), (ShardId(0), "UNKNOWN")]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_events() {
+        // A writer that blocks until it is released.
+        struct Blocked(std::sync::mpsc::Receiver<()>);
+
+        impl Write for Blocked {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.recv().ok();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let (release, blocked) = std::sync::mpsc::channel();
+        let recorder = EventRecorder::new(Blocked(blocked));
+        let event = payload(r#"{"t":"RESUMED","d":{}}"#);
+        for seq in 0..MAX_PENDING_EVENTS as u64 + 2 {
+            recorder.record(ShardId(0), seq, &event).unwrap();
+        }
+        assert!(recorder.dropped_events() > 0);
+
+        drop(release);
+        recorder.flush().await.unwrap();
+    }
+}
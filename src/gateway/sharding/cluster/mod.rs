@@ -0,0 +1,150 @@
+#[cfg(all(unix, feature = "unix_identify_queue"))]
+mod unix;
+
+use std::num::NonZeroU16;
+use std::ops::Range;
+
+use async_trait::async_trait;
+
+#[cfg(all(unix, feature = "unix_identify_queue"))]
+pub use self::unix::{UnixIdentifyQueue, UnixIdentifyQueueServer};
+use crate::internal::prelude::*;
+use crate::model::id::ShardId;
+
+/// A queue that decides when shards may identify, shared by all processes running shards of the
+/// same bot.
+///
+/// Discord allows one identify every 5 seconds per bucket, where the bucket of a shard is
+/// `shard_id % max_concurrency`. A [`ShardQueuer`] only spaces out the identifies of the shards it
+/// starts itself, so clusters running in separate processes need a shared queue, such as the
+/// `UnixIdentifyQueue` of the `unix_identify_queue` feature for clusters on the same host. Set it
+/// with [`ClientBuilder::identify_queue`].
+///
+/// [`ShardQueuer`]: super::ShardQueuer
+/// [`ClientBuilder::identify_queue`]: crate::gateway::client::ClientBuilder::identify_queue
+#[async_trait]
+pub trait IdentifyQueue: Send + Sync {
+    /// Waits until a shard in the given bucket may identify.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the queue could not be reached, in which case the start of the shard is
+    /// retried later.
+    async fn acquire(&self, bucket: u16) -> Result<()>;
+}
+
+/// The configuration of one cluster of shards, for running a bot across several processes.
+///
+/// The shards are split into contiguous ranges of nearly equal size, one per cluster.
+///
+/// # Examples
+///
+/// Running the second of 4 clusters of a bot with 16 shards:
+///
+/// ```rust,no_run
+/// use std::num::NonZeroU16;
+///
+/// use serenity::gateway::ClusterConfig;
+/// use serenity::prelude::*;
+///
+/// # async fn run(mut client: Client) -> Result<(), Box<dyn std::error::Error>> {
+/// let cluster_count = NonZeroU16::new(4).unwrap();
+/// let shard_total = NonZeroU16::new(16).unwrap();
+/// let cluster = ClusterConfig::new(1, cluster_count, shard_total);
+/// assert_eq!(cluster.shards(), 4..8);
+///
+/// client.start_cluster(cluster).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ClusterConfig {
+    /// The ID of this cluster, counting from `0`.
+    pub cluster_id: u16,
+    /// The total number of clusters.
+    pub cluster_count: NonZeroU16,
+    /// The total number of shards across all clusters.
+    pub shard_total: NonZeroU16,
+}
+
+impl ClusterConfig {
+    /// Creates the configuration of a cluster.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cluster_id` is not less than `cluster_count`, or if there are more clusters than
+    /// shards.
+    #[must_use]
+    pub fn new(cluster_id: u16, cluster_count: NonZeroU16, shard_total: NonZeroU16) -> Self {
+        assert!(cluster_id < cluster_count.get(), "cluster ID must be less than the cluster count");
+        assert!(cluster_count <= shard_total, "every cluster must have at least one shard");
+
+        Self {
+            cluster_id,
+            cluster_count,
+            shard_total,
+        }
+    }
+
+    /// Returns the range of shards run by this cluster.
+    #[must_use]
+    pub fn shards(self) -> Range<u16> {
+        self.shards_of(self.cluster_id)
+    }
+
+    /// Returns the range of shards run by the given cluster.
+    ///
+    /// The first `shard_total % cluster_count` clusters run one shard more than the others.
+    #[must_use]
+    pub fn shards_of(self, cluster_id: u16) -> Range<u16> {
+        let (per_cluster, remainder) = self.split();
+        let start = cluster_id * per_cluster + cluster_id.min(remainder);
+        let len = per_cluster + u16::from(cluster_id < remainder);
+
+        start..start + len
+    }
+
+    /// Returns the ID of the cluster running the given shard.
+    #[must_use]
+    pub fn cluster_of(self, shard_id: ShardId) -> u16 {
+        let (per_cluster, remainder) = self.split();
+        // The first `remainder` clusters each run `per_cluster + 1` shards.
+        let larger_shards = remainder * (per_cluster + 1);
+        if shard_id.0 < larger_shards {
+            shard_id.0 / (per_cluster + 1)
+        } else {
+            remainder + (shard_id.0 - larger_shards) / per_cluster
+        }
+    }
+
+    fn split(self) -> (u16, u16) {
+        let shard_total = self.shard_total.get();
+        let cluster_count = self.cluster_count.get();
+        (shard_total / cluster_count, shard_total % cluster_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU16;
+
+    use super::ClusterConfig;
+    use crate::model::id::ShardId;
+
+    #[test]
+    fn test_cluster_shards() {
+        let cluster_count = NonZeroU16::new(3).unwrap();
+        let shard_total = NonZeroU16::new(10).unwrap();
+        let cluster = ClusterConfig::new(0, cluster_count, shard_total);
+
+        assert_eq!(cluster.shards(), 0..4);
+        assert_eq!(cluster.shards_of(1), 4..7);
+        assert_eq!(cluster.shards_of(2), 7..10);
+
+        for cluster_id in 0..3 {
+            for shard_id in cluster.shards_of(cluster_id) {
+                assert_eq!(cluster.cluster_of(ShardId(shard_id)), cluster_id);
+            }
+        }
+    }
+}
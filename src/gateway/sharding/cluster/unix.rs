@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, warn};
+
+use super::IdentifyQueue;
+use crate::internal::prelude::*;
+use crate::internal::tokio::spawn_named;
+
+/// The time Discord requires between two identifies in the same bucket.
+const IDENTIFY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// An [`IdentifyQueue`] for clusters running on the same host, which asks a
+/// [`UnixIdentifyQueueServer`] for identify slots over a Unix socket.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+///
+/// use serenity::gateway::UnixIdentifyQueue;
+/// use serenity::prelude::*;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let token = Token::from_env("DISCORD_TOKEN")?;
+/// let queue = UnixIdentifyQueue::new("/run/my-bot/identify.sock");
+/// let client =
+///     Client::builder(token, GatewayIntents::default()).identify_queue(Arc::new(queue)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct UnixIdentifyQueue {
+    path: PathBuf,
+    stream: tokio::sync::Mutex<Option<UnixStream>>,
+}
+
+impl UnixIdentifyQueue {
+    /// Creates a queue that connects to the server listening on the given socket path.
+    ///
+    /// The connection is only made once the first shard identifies, and is made again if it
+    /// breaks.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            stream: tokio::sync::Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl IdentifyQueue for UnixIdentifyQueue {
+    async fn acquire(&self, bucket: u16) -> Result<()> {
+        let mut stream = self.stream.lock().await;
+        let result = request_slot(&mut stream, &self.path, bucket).await;
+        if result.is_err() {
+            *stream = None;
+        }
+
+        result.map_err(Error::Io)
+    }
+}
+
+async fn request_slot(stream: &mut Option<UnixStream>, path: &Path, bucket: u16) -> io::Result<()> {
+    if stream.is_none() {
+        *stream = Some(UnixStream::connect(path).await?);
+    }
+
+    let stream = stream.as_mut().expect("stream was just connected");
+    stream.write_u16(bucket).await?;
+    // The server answers once the slot has been granted.
+    stream.read_u8().await?;
+
+    Ok(())
+}
+
+/// The server handing out identify slots to [`UnixIdentifyQueue`]s.
+///
+/// This should be run by exactly one process on the host, such as the process running the first
+/// cluster, or a separate process.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use serenity::gateway::UnixIdentifyQueueServer;
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let server = UnixIdentifyQueueServer::bind("/run/my-bot/identify.sock")?;
+/// tokio::spawn(server.run());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct UnixIdentifyQueueServer {
+    listener: UnixListener,
+    interval: Duration,
+}
+
+impl UnixIdentifyQueueServer {
+    /// Listens on the given socket path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket could not be bound, for example if a file already exists at
+    /// the path, such as the socket of a previous server that did not shut down cleanly.
+    pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            listener: UnixListener::bind(path)?,
+            interval: IDENTIFY_INTERVAL,
+        })
+    }
+
+    /// Sets the time between two identifies in the same bucket, which defaults to 5 seconds.
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Hands out identify slots to connecting queues, until accepting a connection fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting a connection fails.
+    pub async fn run(self) -> io::Result<()> {
+        // The instant at which the next identify of each bucket may happen.
+        let next_slots = Arc::new(Mutex::new(HashMap::new()));
+
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            debug!("[Identify Queue] Accepted connection");
+
+            let next_slots = Arc::clone(&next_slots);
+            let interval = self.interval;
+            spawn_named("identify_queue::connection", async move {
+                if let Err(why) = serve_connection(stream, &next_slots, interval).await {
+                    if why.kind() != io::ErrorKind::UnexpectedEof {
+                        warn!("[Identify Queue] Connection failed: {why:?}");
+                    }
+                }
+            });
+        }
+    }
+}
+
+async fn serve_connection(
+    mut stream: UnixStream,
+    next_slots: &Mutex<HashMap<u16, Instant>>,
+    interval: Duration,
+) -> io::Result<()> {
+    loop {
+        let bucket = stream.read_u16().await?;
+
+        let slot = {
+            let now = Instant::now();
+            let mut next_slots = next_slots.lock();
+            let next_slot = next_slots.entry(bucket).or_insert(now);
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + interval;
+            slot
+        };
+
+        sleep_until(slot).await;
+        stream.write_u8(0).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::{UnixIdentifyQueue, UnixIdentifyQueueServer};
+    use crate::gateway::IdentifyQueue;
+
+    #[tokio::test]
+    async fn test_identify_slots() {
+        let path =
+            std::env::temp_dir().join(format!("serenity-identify-{}.sock", std::process::id()));
+        drop(std::fs::remove_file(&path));
+
+        let interval = Duration::from_millis(200);
+        let server = UnixIdentifyQueueServer::bind(&path).unwrap().interval(interval);
+        tokio::spawn(server.run());
+
+        let first = UnixIdentifyQueue::new(&path);
+        let second = UnixIdentifyQueue::new(&path);
+
+        let start = Instant::now();
+        first.acquire(0).await.unwrap();
+        second.acquire(1).await.unwrap();
+        assert!(start.elapsed() < interval);
+
+        // Another identify in the same bucket has to wait, even from another queue.
+        second.acquire(0).await.unwrap();
+        assert!(start.elapsed() >= interval);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
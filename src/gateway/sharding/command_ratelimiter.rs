@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::ShardRunnerMessage;
+
+/// The number of payloads Discord allows a connection to send per [`WINDOW`].
+const SEND_LIMIT: usize = 120;
+/// The period [`SEND_LIMIT`] applies to.
+const WINDOW: Duration = Duration::from_secs(60);
+/// The heartbeat interval to assume before Discord has sent the actual one.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(41_250);
+
+/// Holds back commands sent to a shard, such as presence updates and member requests, so that the
+/// shard stays below the gateway's send ratelimit.
+///
+/// Heartbeats, identifies and resumes are sent by the shard directly, so room for them is kept
+/// free instead.
+#[derive(Debug)]
+pub(crate) struct CommandRatelimiter {
+    // The times at which the commands of the last window were sent.
+    sent: VecDeque<Instant>,
+    limit: usize,
+    queue: VecDeque<ShardRunnerMessage>,
+    // The length of the queue, shared with the shard's messengers.
+    queued: Arc<AtomicUsize>,
+}
+
+impl CommandRatelimiter {
+    pub(crate) fn new(queued: Arc<AtomicUsize>) -> Self {
+        let mut ratelimiter = Self {
+            sent: VecDeque::new(),
+            limit: 0,
+            queue: VecDeque::new(),
+            queued,
+        };
+        ratelimiter.set_heartbeat_interval(None);
+        ratelimiter
+    }
+
+    /// Keeps enough room for the heartbeats sent at the given interval, along with one identify
+    /// or resume.
+    pub(crate) fn set_heartbeat_interval(&mut self, interval: Option<Duration>) {
+        let interval = interval.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL).max(Duration::from_secs(1));
+        let heartbeats = WINDOW.as_millis().div_ceil(interval.as_millis());
+        let reserved = usize::try_from(heartbeats).unwrap_or(SEND_LIMIT) + 1;
+
+        self.limit = SEND_LIMIT.saturating_sub(reserved).max(1);
+    }
+
+    /// Queues a command to be sent once the ratelimit allows it.
+    pub(crate) fn push(&mut self, command: ShardRunnerMessage) {
+        self.queue.push_back(command);
+        self.queued.store(self.queue.len(), Ordering::Relaxed);
+    }
+
+    /// Takes the next queued command, if the ratelimit allows sending it now.
+    pub(crate) fn pop(&mut self, now: Instant) -> Option<ShardRunnerMessage> {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= WINDOW) {
+            self.sent.pop_front();
+        }
+
+        if self.queue.is_empty() || self.sent.len() >= self.limit {
+            return None;
+        }
+
+        self.sent.push_back(now);
+        let command = self.queue.pop_front();
+        self.queued.store(self.queue.len(), Ordering::Relaxed);
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::{CommandRatelimiter, SEND_LIMIT, WINDOW};
+    use crate::gateway::ShardRunnerMessage;
+    use crate::model::user::OnlineStatus;
+
+    #[test]
+    fn test_command_ratelimit() {
+        let queued = Arc::new(AtomicUsize::new(0));
+        let mut ratelimiter = CommandRatelimiter::new(Arc::clone(&queued));
+        ratelimiter.set_heartbeat_interval(Some(Duration::from_secs(20)));
+        // 3 heartbeats and an identify have to fit into every window.
+        let limit = SEND_LIMIT - 4;
+
+        for _ in 0..=limit {
+            ratelimiter.push(ShardRunnerMessage::SetStatus(OnlineStatus::Idle));
+        }
+        assert_eq!(queued.load(Ordering::Relaxed), limit + 1);
+
+        let start = Instant::now();
+        for _ in 0..limit {
+            assert!(ratelimiter.pop(start).is_some());
+        }
+        assert!(ratelimiter.pop(start).is_none());
+        assert_eq!(queued.load(Ordering::Relaxed), 1);
+
+        // Room is made once the first commands are out of the window.
+        assert!(ratelimiter.pop(start + Duration::from_secs(59)).is_none());
+        assert!(ratelimiter.pop(start + WINDOW).is_some());
+        assert!(ratelimiter.pop(start + WINDOW).is_none());
+        assert_eq!(queued.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::ConnectionStage;
+use crate::model::id::ShardId;
+
+/// The number of heartbeat latencies kept for [`ShardHealth::recent_latencies`].
+const LATENCY_HISTORY: usize = 10;
+/// The window over which [`ShardHealth::events_per_second`] is measured.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// A snapshot of the health of a shard, as returned by [`ShardManager::health`].
+///
+/// [`ShardManager::health`]: super::ShardManager::health
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ShardHealth {
+    /// The Id of the shard.
+    pub shard_id: ShardId,
+    /// The current connection stage of the shard.
+    pub stage: ConnectionStage,
+    /// The latencies of the last few heartbeats, oldest first.
+    pub recent_latencies: Vec<Duration>,
+    /// The time since the last heartbeat was acknowledged, or [`None`] if none has been yet.
+    pub since_heartbeat_ack: Option<Duration>,
+    /// The time since the last dispatch was received, or [`None`] if none has been yet.
+    pub since_dispatch: Option<Duration>,
+    /// The number of dispatches received since the shard was first started.
+    pub dispatches: u64,
+    /// The number of dispatches received per second, measured over the last ten seconds.
+    pub events_per_second: f64,
+    /// The number of times the shard connected again after its first connection, by either
+    /// resuming or identifying.
+    pub reconnects: u64,
+    /// The number of times the shard resumed its session.
+    pub resumes: u64,
+    /// The number of times the shard started a new session after its first one.
+    pub reidentifies: u64,
+    /// The number of times the shard was restarted for being unhealthy.
+    pub unhealthy_restarts: u64,
+}
+
+/// Why a shard is considered unhealthy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub enum ShardUnhealthyReason {
+    /// No heartbeat was acknowledged for longer than
+    /// [`ShardHealthThresholds::max_since_heartbeat_ack`].
+    NoHeartbeatAck,
+    /// No dispatch was received for longer than [`ShardHealthThresholds::max_since_dispatch`].
+    NoDispatches,
+}
+
+/// An event denoting that a shard is considered unhealthy, as its connection seems to be dead
+/// without having been closed.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ShardUnhealthyEvent {
+    /// The Id of the unhealthy shard.
+    pub shard_id: ShardId,
+    /// Why the shard is considered unhealthy.
+    pub reason: ShardUnhealthyReason,
+    /// The health of the shard when it was considered unhealthy.
+    pub health: ShardHealth,
+    /// Whether the shard is being restarted, as set with [`ShardHealthThresholds::restart`].
+    pub restarting: bool,
+}
+
+/// When a connected shard is considered unhealthy, set with
+/// [`ClientBuilder::shard_health_thresholds`].
+///
+/// Such "zombie" connections are still open, but Discord doesn't respond over them anymore.
+/// Unhealthy shards are reported to [`EventHandler::shard_unhealthy`].
+///
+/// [`ClientBuilder::shard_health_thresholds`]: crate::gateway::client::ClientBuilder::shard_health_thresholds
+/// [`EventHandler::shard_unhealthy`]: crate::gateway::client::EventHandler::shard_unhealthy
+#[derive(Clone, Copy, Debug)]
+pub struct ShardHealthThresholds {
+    /// The longest time without a heartbeat acknowledgement. Defaults to 2 minutes, about three
+    /// heartbeat intervals.
+    pub max_since_heartbeat_ack: Option<Duration>,
+    /// The longest time without receiving a dispatch. Defaults to [`None`], as the shards of small
+    /// bots may not receive any events for a long time.
+    pub max_since_dispatch: Option<Duration>,
+    /// Whether to restart unhealthy shards. Defaults to `true`.
+    pub restart: bool,
+}
+
+impl Default for ShardHealthThresholds {
+    fn default() -> Self {
+        Self {
+            max_since_heartbeat_ack: Some(Duration::from_secs(120)),
+            max_since_dispatch: None,
+            restart: true,
+        }
+    }
+}
+
+/// Keeps track of the health of a shard across restarts of its runner.
+#[derive(Debug, Default)]
+pub(crate) struct HealthTracker(parking_lot::Mutex<HealthState>);
+
+#[derive(Debug, Default)]
+struct HealthState {
+    latencies: VecDeque<Duration>,
+    last_heartbeat_ack: Option<Instant>,
+    last_dispatch: Option<Instant>,
+    // When the current session was started or resumed, or `None` while disconnected.
+    connected_at: Option<Instant>,
+    // Whether the current connection has been reported as unhealthy.
+    reported: bool,
+    dispatches: u64,
+    identifies: u64,
+    resumes: u64,
+    unhealthy_restarts: u64,
+    window_start: Option<Instant>,
+    window_dispatches: u64,
+    events_per_second: f64,
+}
+
+impl HealthState {
+    fn roll_window(&mut self, now: Instant) {
+        let start = *self.window_start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= RATE_WINDOW {
+            #[expect(clippy::cast_precision_loss)]
+            let events_per_second = self.window_dispatches as f64 / elapsed.as_secs_f64();
+            self.events_per_second = events_per_second;
+            self.window_start = Some(now);
+            self.window_dispatches = 0;
+        }
+    }
+
+    // The time since the later of the given instant and the start of the connection.
+    fn since(&self, instant: Option<Instant>, now: Instant) -> Option<Duration> {
+        let connected_at = self.connected_at?;
+        let since = instant.map_or(connected_at, |instant| instant.max(connected_at));
+        Some(now.saturating_duration_since(since))
+    }
+}
+
+impl HealthTracker {
+    pub(crate) fn record_heartbeat_ack(&self, latency: Option<Duration>, now: Instant) {
+        let mut state = self.0.lock();
+        state.last_heartbeat_ack = Some(now);
+        if let Some(latency) = latency {
+            if state.latencies.len() == LATENCY_HISTORY {
+                state.latencies.pop_front();
+            }
+            state.latencies.push_back(latency);
+        }
+    }
+
+    pub(crate) fn record_dispatch(&self, now: Instant) {
+        let mut state = self.0.lock();
+        state.last_dispatch = Some(now);
+        state.dispatches += 1;
+        state.roll_window(now);
+        state.window_dispatches += 1;
+    }
+
+    pub(crate) fn record_ready(&self, now: Instant) {
+        let mut state = self.0.lock();
+        state.identifies += 1;
+        state.connected_at = Some(now);
+        state.reported = false;
+    }
+
+    pub(crate) fn record_resumed(&self, now: Instant) {
+        let mut state = self.0.lock();
+        state.resumes += 1;
+        state.connected_at = Some(now);
+        state.reported = false;
+    }
+
+    pub(crate) fn record_unhealthy_restart(&self) {
+        let mut state = self.0.lock();
+        state.unhealthy_restarts += 1;
+        state.connected_at = None;
+    }
+
+    /// Checks whether a connected shard is unhealthy according to the thresholds, which is only
+    /// reported once per connection.
+    pub(crate) fn check(
+        &self,
+        thresholds: &ShardHealthThresholds,
+        now: Instant,
+    ) -> Option<ShardUnhealthyReason> {
+        let mut state = self.0.lock();
+        if state.reported {
+            return None;
+        }
+
+        let exceeds = |instant, max: Option<Duration>| {
+            max.zip(state.since(instant, now)).is_some_and(|(max, since)| since > max)
+        };
+
+        let reason = if exceeds(state.last_heartbeat_ack, thresholds.max_since_heartbeat_ack) {
+            ShardUnhealthyReason::NoHeartbeatAck
+        } else if exceeds(state.last_dispatch, thresholds.max_since_dispatch) {
+            ShardUnhealthyReason::NoDispatches
+        } else {
+            return None;
+        };
+
+        state.reported = true;
+        Some(reason)
+    }
+
+    pub(crate) fn snapshot(
+        &self,
+        shard_id: ShardId,
+        stage: ConnectionStage,
+        now: Instant,
+    ) -> ShardHealth {
+        let mut tracked = self.0.lock();
+        tracked.roll_window(now);
+
+        let reidentifies = tracked.identifies.saturating_sub(1);
+        ShardHealth {
+            shard_id,
+            stage,
+            recent_latencies: tracked.latencies.iter().copied().collect(),
+            since_heartbeat_ack: tracked
+                .last_heartbeat_ack
+                .map(|ack| now.saturating_duration_since(ack)),
+            since_dispatch: tracked.last_dispatch.map(|last| now.saturating_duration_since(last)),
+            dispatches: tracked.dispatches,
+            events_per_second: tracked.events_per_second,
+            reconnects: reidentifies + tracked.resumes,
+            resumes: tracked.resumes,
+            reidentifies,
+            unhealthy_restarts: tracked.unhealthy_restarts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_health_tracker() {
+        let tracker = HealthTracker::default();
+        let thresholds = ShardHealthThresholds {
+            max_since_heartbeat_ack: Some(Duration::from_secs(60)),
+            max_since_dispatch: Some(Duration::from_secs(30)),
+            restart: true,
+        };
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Shards are only checked once connected, and measured from the start of the connection.
+        assert_eq!(tracker.check(&thresholds, at(100)), None);
+        tracker.record_ready(at(0));
+        for second in 0..20 {
+            tracker.record_dispatch(at(second));
+        }
+        assert_eq!(tracker.check(&thresholds, at(25)), None);
+        assert_eq!(tracker.check(&thresholds, at(50)), Some(ShardUnhealthyReason::NoDispatches));
+        assert_eq!(tracker.check(&thresholds, at(51)), None);
+        tracker.record_resumed(at(50));
+
+        tracker.record_heartbeat_ack(Some(Duration::from_millis(40)), at(50));
+        tracker.record_dispatch(at(50));
+        assert_eq!(tracker.check(&thresholds, at(70)), None);
+        assert_eq!(tracker.check(&thresholds, at(111)), Some(ShardUnhealthyReason::NoHeartbeatAck));
+
+        tracker.record_unhealthy_restart();
+        assert_eq!(tracker.check(&thresholds, at(200)), None);
+        tracker.record_ready(at(200));
+
+        let health = tracker.snapshot(ShardId(1), ConnectionStage::Connected, at(300));
+        assert_eq!(health.recent_latencies, [Duration::from_millis(40)]);
+        assert_eq!(health.since_dispatch, Some(Duration::from_secs(250)));
+        assert_eq!(health.dispatches, 21);
+        assert_eq!((health.reconnects, health.resumes, health.reidentifies), (2, 1, 1));
+        assert_eq!(health.unhealthy_restarts, 1);
+        // The last window saw one dispatch over 250 seconds.
+        assert!((health.events_per_second - 0.004).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::internal::prelude::*;
+use crate::model::event::GuildMembersChunkEvent;
+use crate::model::gateway::Presence;
+use crate::model::guild::Member;
+use crate::model::id::{GenericId, GuildId, UserId};
+
+/// The members received in response to [`ShardMessenger::request_members`].
+///
+/// [`ShardMessenger::request_members`]: super::ShardMessenger::request_members
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct GuildMembers {
+    /// The Id of the guild the members belong to.
+    pub guild_id: GuildId,
+    /// The members that were found, from all chunks.
+    pub members: ExtractMap<UserId, Member>,
+    /// The requested user Ids that are not members of the guild, when requesting members by Id.
+    pub not_found: Vec<GenericId>,
+    /// The presences of the members, if they were requested.
+    pub presences: Vec<Presence>,
+}
+
+impl GuildMembers {
+    fn new(guild_id: GuildId) -> Self {
+        Self {
+            guild_id,
+            members: ExtractMap::new(),
+            not_found: Vec::new(),
+            presences: Vec::new(),
+        }
+    }
+
+    fn add_chunk(&mut self, chunk: GuildMembersChunkEvent) {
+        self.members.extend(chunk.members);
+        self.not_found.extend(chunk.not_found);
+        self.presences.extend(chunk.presences.into_iter().flatten());
+    }
+}
+
+/// The member chunk requests of a shard that are being awaited, by nonce.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkRequests {
+    next_nonce: AtomicU64,
+    pending: Mutex<HashMap<String, UnboundedSender<GuildMembersChunkEvent>>>,
+}
+
+impl ChunkRequests {
+    /// Registers a new request, which stays registered until the returned handle is dropped.
+    pub(crate) fn register(&self) -> ChunkRequest<'_> {
+        let nonce = format!("serenity-{}", self.next_nonce.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.pending.lock().insert(nonce.clone(), tx);
+
+        ChunkRequest {
+            requests: self,
+            nonce,
+            rx,
+        }
+    }
+
+    /// Passes a received chunk on to the request it belongs to, if it is being awaited.
+    pub(crate) fn deliver(&self, chunk: &GuildMembersChunkEvent) {
+        let Some(nonce) = &chunk.nonce else { return };
+        if let Some(tx) = self.pending.lock().get(nonce.as_str()) {
+            drop(tx.send(chunk.clone()));
+        }
+    }
+}
+
+/// A registered member chunk request.
+pub(crate) struct ChunkRequest<'a> {
+    requests: &'a ChunkRequests,
+    pub(crate) nonce: String,
+    rx: UnboundedReceiver<GuildMembersChunkEvent>,
+}
+
+impl ChunkRequest<'_> {
+    /// Collects the chunks of the request until all of them have been received.
+    pub(crate) async fn collect(&mut self, guild_id: GuildId) -> GuildMembers {
+        let mut members = GuildMembers::new(guild_id);
+        let mut received = HashSet::new();
+
+        // The sender is only dropped along with the request.
+        while let Some(chunk) = self.rx.recv().await {
+            // Every chunk carries the total, so the order they arrive in doesn't matter.
+            let chunk_count = chunk.chunk_count;
+            received.insert(chunk.chunk_index);
+            members.add_chunk(chunk);
+
+            if received.len() >= chunk_count as usize {
+                break;
+            }
+        }
+
+        members
+    }
+}
+
+impl Drop for ChunkRequest<'_> {
+    fn drop(&mut self) {
+        self.requests.pending.lock().remove(&self.nonce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChunkRequests;
+    use crate::model::event::GuildMembersChunkEvent;
+    use crate::model::id::{GenericId, GuildId};
+
+    fn chunk(nonce: &str, index: u32, count: u32, not_found: &[&str]) -> GuildMembersChunkEvent {
+        serde_json::from_value(serde_json::json!({
+            "guild_id": "1",
+            "members": [],
+            "chunk_index": index,
+            "chunk_count": count,
+            "not_found": not_found,
+            "nonce": nonce,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_collect_chunks() {
+        let requests = ChunkRequests::default();
+        let mut request = requests.register();
+        let other = requests.register();
+        assert_ne!(request.nonce, other.nonce);
+
+        requests.deliver(&chunk(&request.nonce, 1, 2, &["2"]));
+        requests.deliver(&chunk(&other.nonce, 0, 1, &["3"]));
+        requests.deliver(&chunk(&request.nonce, 0, 2, &["4"]));
+
+        let members = request.collect(GuildId::new(1)).await;
+        assert_eq!(members.not_found, [GenericId::new(2), GenericId::new(4)]);
+
+        // Dropping a request stops chunks from being passed on to it.
+        let nonce = request.nonce.clone();
+        drop(request);
+        assert!(!requests.pending.lock().contains_key(&nonce));
+        assert!(requests.pending.lock().contains_key(&other.nonce));
+    }
+}
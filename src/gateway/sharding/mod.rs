@@ -35,12 +35,19 @@
 //!
 //! [docs]: https://discordapp.com/developers/docs/topics/gateway#sharding
 
+mod cluster;
+mod command_ratelimiter;
+mod health;
+mod member_chunks;
+mod presence_rotation;
 mod shard_manager;
 mod shard_messenger;
 mod shard_queuer;
 mod shard_runner;
+mod voice_requests;
 
 use std::fmt;
+use std::num::NonZeroU16;
 use std::sync::Arc;
 use std::time::{Duration as StdDuration, Instant};
 
@@ -53,6 +60,20 @@ use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
 use tracing::{debug, error, info, trace, warn};
 use url::Url;
 
+pub use self::cluster::{ClusterConfig, IdentifyQueue};
+#[cfg(all(unix, feature = "unix_identify_queue"))]
+pub use self::cluster::{UnixIdentifyQueue, UnixIdentifyQueueServer};
+use self::command_ratelimiter::CommandRatelimiter;
+pub(crate) use self::health::HealthTracker;
+pub use self::health::{
+    ShardHealth,
+    ShardHealthThresholds,
+    ShardUnhealthyEvent,
+    ShardUnhealthyReason,
+};
+pub(crate) use self::member_chunks::ChunkRequests;
+pub use self::member_chunks::GuildMembers;
+pub use self::presence_rotation::PresenceRotation;
 pub use self::shard_manager::{
     ShardManager,
     ShardManagerOptions,
@@ -60,13 +81,17 @@ pub use self::shard_manager::{
 };
 pub use self::shard_messenger::ShardMessenger;
 pub use self::shard_queuer::{ShardQueue, ShardQueuer, ShardQueuerMessage};
+#[cfg(feature = "collector")]
+pub(crate) use self::shard_runner::run_collectors;
 pub use self::shard_runner::{ShardRunner, ShardRunnerMessage, ShardRunnerOptions};
+pub use self::voice_requests::VoiceConnectionInfo;
+pub(crate) use self::voice_requests::VoiceRequests;
 use super::{ActivityData, ChunkGuildFilter, GatewayError, PresenceData, WsClient};
 use crate::constants::{self, close_codes};
 use crate::internal::prelude::*;
 use crate::model::event::{Event, GatewayEvent};
 use crate::model::gateway::{GatewayIntents, ShardInfo};
-use crate::model::id::{ApplicationId, GuildId, ShardId};
+use crate::model::id::{ApplicationId, ChannelId, GuildId, ShardId, UserId};
 use crate::model::user::OnlineStatus;
 
 /// An abstract handler for a websocket connection to Discord's gateway.
@@ -101,6 +126,8 @@ pub struct Shard {
     last_heartbeat_ack: Option<Instant>,
     heartbeat_interval: Option<std::time::Duration>,
     application_id_callback: Option<Box<dyn FnOnce(ApplicationId) + Send + Sync>>,
+    application_id: Option<ApplicationId>,
+    user_id: Option<UserId>,
     /// This is used by the heartbeater to determine whether the last heartbeat was sent without an
     /// acknowledgement, and whether to reconnect.
     // This must be set to `true` in `Shard::handle_event`'s `Ok(GatewayEvent::HeartbeatAck)` arm.
@@ -117,6 +144,7 @@ pub struct Shard {
     ws_url: Arc<str>,
     resume_ws_url: Option<FixedString>,
     compression: TransportCompression,
+    encoding: GatewayEncoding,
     pub intents: GatewayIntents,
 }
 
@@ -133,7 +161,7 @@ impl Shard {
     /// use std::num::NonZeroU16;
     /// use std::sync::Arc;
     ///
-    /// use serenity::gateway::{Shard, TransportCompression};
+    /// use serenity::gateway::{GatewayEncoding, Shard, TransportCompression};
     /// use serenity::model::gateway::{GatewayIntents, ShardInfo};
     /// use serenity::model::id::ShardId;
     /// use serenity::secrets::Token;
@@ -158,6 +186,7 @@ impl Shard {
     ///     GatewayIntents::all(),
     ///     None,
     ///     TransportCompression::None,
+    ///     GatewayEncoding::Json,
     /// )
     /// .await?;
     ///
@@ -178,9 +207,48 @@ impl Shard {
         intents: GatewayIntents,
         presence: Option<PresenceData>,
         compression: TransportCompression,
+        encoding: GatewayEncoding,
     ) -> Result<Shard> {
-        let client = connect(&ws_url, compression).await?;
+        let client = connect(&ws_url, compression, encoding).await?;
+
+        Ok(Self::with_client(
+            client,
+            ws_url,
+            token,
+            shard_info,
+            intents,
+            presence,
+            compression,
+            encoding,
+        ))
+    }
+
+    /// Creates a shard that never connects, used to feed recorded dispatches through
+    /// [`Self::handle_event`] when replaying them.
+    pub(crate) fn detached(shard_info: ShardInfo) -> Shard {
+        Self::with_client(
+            WsClient::detached(),
+            Arc::from(""),
+            Token::placeholder(),
+            shard_info,
+            GatewayIntents::empty(),
+            None,
+            TransportCompression::None,
+            GatewayEncoding::Json,
+        )
+    }
 
+    #[expect(clippy::too_many_arguments)]
+    fn with_client(
+        client: WsClient,
+        ws_url: Arc<str>,
+        token: Token,
+        shard_info: ShardInfo,
+        intents: GatewayIntents,
+        presence: Option<PresenceData>,
+        compression: TransportCompression,
+        encoding: GatewayEncoding,
+    ) -> Shard {
         let presence = presence.unwrap_or_default();
         let last_heartbeat_sent = None;
         let last_heartbeat_ack = None;
@@ -190,13 +258,15 @@ impl Shard {
         let stage = ConnectionStage::Handshake;
         let session_id = None;
 
-        Ok(Shard {
+        Shard {
             client,
             presence,
             last_heartbeat_sent,
             last_heartbeat_ack,
             heartbeat_interval,
             application_id_callback: None,
+            application_id: None,
+            user_id: None,
             last_heartbeat_acknowledged,
             seq,
             stage,
@@ -207,8 +277,9 @@ impl Shard {
             ws_url,
             resume_ws_url: None,
             compression,
+            encoding,
             intents,
-        })
+        }
     }
 
     /// Sets a callback to be called when the gateway receives the application's ID from Discord.
@@ -286,6 +357,25 @@ impl Shard {
         self.session_id.as_deref()
     }
 
+    /// Returns the Id of the current user, once the session has been started.
+    pub fn user_id(&self) -> Option<UserId> {
+        self.user_id
+    }
+
+    /// Returns the state needed to resume the shard's session later, if it has one.
+    ///
+    /// See [`ShardSession`] for details.
+    pub fn session(&self) -> Option<ShardSession> {
+        Some(ShardSession {
+            shard_info: self.shard_info,
+            session_id: self.session_id.clone()?,
+            seq: self.seq,
+            resume_ws_url: self.resume_ws_url.clone()?,
+            application_id: self.application_id?,
+            user_id: self.user_id?,
+        })
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     pub fn set_activity(&mut self, activity: Option<ActivityData>) {
         self.presence.activity = activity;
@@ -326,11 +416,7 @@ impl Shard {
         event: JsonMap,
         original_str: &str,
     ) -> Result<(Option<ShardAction>, Option<Event>)> {
-        if seq > self.seq + 1 {
-            warn!("[{:?}] Sequence off; them: {}, us: {}", self.shard_info, seq, self.seq);
-        }
-
-        self.seq = seq;
+        self.update_seq(seq);
         let event = deserialize_and_log_event(event, original_str)?;
 
         match &event {
@@ -339,6 +425,8 @@ impl Shard {
 
                 self.resume_ws_url = Some(ready.ready.resume_gateway_url.clone());
                 self.session_id = Some(ready.ready.session_id.clone());
+                self.application_id = Some(ready.ready.application.id);
+                self.user_id = Some(ready.ready.user.id);
                 self.stage = ConnectionStage::Connected;
 
                 if let Some(callback) = self.application_id_callback.take() {
@@ -359,6 +447,14 @@ impl Shard {
         Ok((None, Some(event)))
     }
 
+    fn update_seq(&mut self, seq: u64) {
+        if seq > self.seq + 1 {
+            warn!("[{:?}] Sequence off; them: {}, us: {}", self.shard_info, seq, self.seq);
+        }
+
+        self.seq = seq;
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     fn handle_heartbeat_event(&mut self, s: u64) -> ShardAction {
         info!("[{:?}] Received shard heartbeat", self.shard_info);
@@ -501,6 +597,15 @@ impl Shard {
             }) => {
                 return self.handle_gateway_dispatch(seq, data, &original_str);
             },
+            Ok(GatewayEvent::SkippedDispatch {
+                seq,
+                kind,
+            }) => {
+                trace!("[{:?}] Skipped {kind} dispatch", self.shard_info);
+                self.update_seq(seq);
+
+                Ok(None)
+            },
             Ok(GatewayEvent::Heartbeat(s)) => Ok(Some(self.handle_heartbeat_event(s))),
             Ok(GatewayEvent::HeartbeatAck) => {
                 self.last_heartbeat_ack = Some(Instant::now());
@@ -513,12 +618,12 @@ impl Shard {
             Ok(GatewayEvent::Hello(interval)) => {
                 debug!("[{:?}] Received a Hello; interval: {}", self.shard_info, interval);
 
+                self.heartbeat_interval = Some(std::time::Duration::from_millis(interval));
+
                 if self.stage == ConnectionStage::Resuming {
                     return Ok((None, None));
                 }
 
-                self.heartbeat_interval = Some(std::time::Duration::from_millis(interval));
-
                 Ok(Some(if self.stage == ConnectionStage::Handshake {
                     ShardAction::Identify
                 } else {
@@ -716,6 +821,24 @@ impl Shard {
             .await
     }
 
+    /// Updates the current user's voice state in a guild, joining, moving between or leaving
+    /// voice channels.
+    ///
+    /// # Errors
+    /// Errors if there is a problem with the WS connection.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn update_voice_state(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        self_mute: bool,
+        self_deaf: bool,
+    ) -> Result<()> {
+        self.client
+            .send_voice_state_update(&self.shard_info, guild_id, channel_id, self_mute, self_deaf)
+            .await
+    }
+
     /// Sets the shard as going into identifying stage, which sets:
     /// - the time that the last heartbeat sent as being now
     /// - the `stage` to [`ConnectionStage::Identifying`]
@@ -762,7 +885,7 @@ impl Shard {
         // Hello is received.
         self.stage = ConnectionStage::Connecting;
         self.started = Instant::now();
-        let client = connect(ws_url, self.compression).await?;
+        let client = connect(ws_url, self.compression, self.encoding).await?;
         self.stage = ConnectionStage::Handshake;
 
         Ok(client)
@@ -799,6 +922,38 @@ impl Shard {
         }
     }
 
+    /// Resumes a session saved with [`Self::session`] over the current connection, instead of
+    /// identifying.
+    ///
+    /// The shard should have been connected to the session's [`ShardSession::resume_ws_url`], and
+    /// must not have identified yet. If Discord rejects the session, the shard will receive an
+    /// invalid session event and must be identified from scratch.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there is a problem with the WS connection.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self, session)))]
+    pub async fn resume_session(&mut self, session: ShardSession) -> Result<()> {
+        debug!("[{:?}] Resuming saved session", self.shard_info);
+
+        self.session_id = Some(session.session_id);
+        self.seq = session.seq;
+        self.resume_ws_url = Some(session.resume_ws_url);
+        self.application_id = Some(session.application_id);
+        self.user_id = Some(session.user_id);
+        self.stage = ConnectionStage::Resuming;
+
+        // There won't be a Ready to learn the application ID from.
+        if let Some(callback) = self.application_id_callback.take() {
+            callback(session.application_id);
+        }
+
+        let session_id = self.session_id.as_deref().unwrap_or_default();
+        self.client
+            .send_resume(&self.shard_info, session_id, self.seq, self.token.expose_secret())
+            .await
+    }
+
     /// # Errors
     ///
     /// Errors if unable to re-establish a websocket connection.
@@ -821,11 +976,16 @@ impl Shard {
     }
 }
 
-async fn connect(base_url: &str, compression: TransportCompression) -> Result<WsClient> {
+async fn connect(
+    base_url: &str,
+    compression: TransportCompression,
+    encoding: GatewayEncoding,
+) -> Result<WsClient> {
     let url = Url::parse(&aformat!(
-        "{}?v={}{}",
+        "{}?v={}&encoding={}{}",
         CapStr::<64>(base_url),
         constants::GATEWAY_VERSION,
+        CapStr::<5>(encoding.query_param()),
         compression.query_param()
     ))
     .map_err(|why| {
@@ -833,21 +993,30 @@ async fn connect(base_url: &str, compression: TransportCompression) -> Result<Ws
         Error::Gateway(GatewayError::BuildingUrl)
     })?;
 
-    WsClient::connect(url, compression).await
+    WsClient::connect(url, compression, encoding).await
 }
 
-fn deserialize_and_log_event(map: JsonMap, original_str: &str) -> Result<Event> {
+pub(crate) fn deserialize_and_log_event(mut map: JsonMap, original_str: &str) -> Result<Event> {
+    // Only the top-level event name decides whether an event is unknown, so that a known event
+    // with an unknown nested variant is reported as an error instead. `Event::Unknown` itself is
+    // never deserialized.
+    if let Some(Value::String(kind)) = map.get("t") {
+        if !Event::is_known_wire_name(kind) {
+            debug!("Unknown event: {kind}");
+
+            return Ok(Event::Unknown {
+                kind: FixedString::from_str_trunc(kind),
+                data: map.remove("d").unwrap_or_default(),
+            });
+        }
+    }
+
     Event::deserialize(Value::Object(map)).map_err(|err| {
-        let err = serde::de::Error::custom(err);
-        let err_dbg = format!("{err:?}");
-        if let Some((variant_name, _)) =
-            err_dbg.strip_prefix(r#"Error("unknown variant `"#).and_then(|s| s.split_once('`'))
-        {
-            debug!("Unknown event: {variant_name}");
-        } else {
-            warn!("Err deserializing text: {err_dbg}");
+        warn!("Err deserializing text: {err:?}");
+        // Dispatches decoded from ETF have no original text.
+        if !original_str.is_empty() {
+            debug!("Failing text: {original_str}");
         }
-        debug!("Failing text: {original_str}");
         Error::Json(err)
     })
 }
@@ -860,6 +1029,35 @@ pub enum ShardAction {
     Reconnect(ReconnectType),
 }
 
+/// The state needed to resume a [`Shard`]'s gateway session, such as after restarting the process.
+///
+/// Resuming skips identifying and the [`GuildCreate`] events for every guild, so a restart of the
+/// bot becomes much cheaper. Sessions can be saved with [`ShardManager::shutdown_all_resumable`],
+/// serialized to storage and passed to [`ClientBuilder::resume_sessions`] on the next start.
+///
+/// **Note**: Discord only keeps sessions resumable for a short amount of time, after which shards
+/// identify as usual. Because no guilds are received when resuming, the cache will not contain
+/// guilds and channels that were known to the previous process.
+///
+/// [`GuildCreate`]: crate::model::event::Event::GuildCreate
+/// [`ClientBuilder::resume_sessions`]: crate::gateway::client::ClientBuilder::resume_sessions
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ShardSession {
+    /// The shard that the session belongs to.
+    pub shard_info: ShardInfo,
+    /// The ID of the session.
+    pub session_id: FixedString,
+    /// The sequence number of the last event received in the session.
+    pub seq: u64,
+    /// The URL to connect to for resuming the session.
+    pub resume_ws_url: FixedString,
+    /// The ID of the application the session was created for.
+    pub application_id: ApplicationId,
+    /// The ID of the current user.
+    pub user_id: UserId,
+}
+
 /// Information about a [`ShardRunner`].
 ///
 /// The [`ShardId`] is not included because, as it stands, you probably already know the Id if you
@@ -891,6 +1089,18 @@ pub struct ShardStageUpdateEvent {
     pub shard_id: ShardId,
 }
 
+/// An event denoting the progress of a [`ShardManager::reshard`].
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct ReshardProgressEvent {
+    /// The shard total that is being switched to.
+    pub shard_total: NonZeroU16,
+    /// The number of new shards that are connected and have loaded their guilds.
+    pub shards_ready: u16,
+    /// Whether event dispatch has been switched over to the new shards.
+    pub switched: bool,
+}
+
 /// Indicates the current connection stage of a [`Shard`].
 ///
 /// This can be useful for knowing which shards are currently "down"/"up".
@@ -990,6 +1200,34 @@ impl PartialEq for CollectorCallback {
     }
 }
 
+/// The encoding of payloads sent and received over the gateway.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GatewayEncoding {
+    /// JSON encoding.
+    #[default]
+    Json,
+    /// [Erlang External Term Format][ETF] encoding, whose payloads are smaller than JSON.
+    ///
+    /// Payloads are decoded straight from their bytes like JSON ones, so they cost no more to
+    /// decode, and dispatches that nothing needs are skipped just as cheaply. Failing
+    /// payloads are logged as bytes rather than text, and the payloads passed to [`EventSink`]s
+    /// are converted to JSON.
+    ///
+    /// [ETF]: https://www.erlang.org/doc/apps/erts/erl_ext_dist.html
+    /// [`EventSink`]: crate::gateway::EventSink
+    Etf,
+}
+
+impl GatewayEncoding {
+    fn query_param(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Etf => "etf",
+        }
+    }
+}
+
 /// The transport compression method to use.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[non_exhaustive]
@@ -1027,3 +1265,75 @@ impl TransportCompression {
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn deserialize(payload: Value) -> Result<Event> {
+        let Value::Object(map) = payload else { unreachable!() };
+        deserialize_and_log_event(map, "")
+    }
+
+    #[test]
+    fn test_deserialize_unknown_event() {
+        let event = deserialize(json!({"t": "NEW_EVENT", "d": {"a": 1}})).unwrap();
+        assert!(matches!(
+            event,
+            Event::Unknown { kind, data } if kind == "NEW_EVENT" && data == json!({"a": 1})
+        ));
+
+        let event = deserialize(json!({"t": "UNKNOWN", "d": null})).unwrap();
+        assert!(matches!(event, Event::Unknown { kind, .. } if kind == "UNKNOWN"));
+
+        // A known event with an unknown nested variant is an error, not an unknown event.
+        let result = deserialize(json!({
+            "t": "PRESENCE_UPDATE",
+            "d": {"user": {"id": "1"}, "status": "busy"},
+        }));
+        assert!(
+            matches!(&result, Err(Error::Json(err)) if err.to_string().contains("unknown variant")),
+            "{result:?}"
+        );
+
+        let event = deserialize(json!({
+            "t": "USER_UPDATE",
+            "d": {"id": "1", "username": "a", "discriminator": "0"},
+        }))
+        .unwrap();
+        assert!(matches!(event, Event::UserUpdate(_)));
+    }
+
+    #[test]
+    fn test_deserialize_renamed_events() {
+        // Events whose variant names differ from the names Discord sends.
+        let names = [
+            "APPLICATION_COMMAND_PERMISSIONS_UPDATE",
+            "AUTO_MODERATION_RULE_CREATE",
+            "AUTO_MODERATION_RULE_UPDATE",
+            "AUTO_MODERATION_RULE_DELETE",
+            "AUTO_MODERATION_ACTION_EXECUTION",
+            "MESSAGE_REACTION_ADD",
+            "MESSAGE_REACTION_REMOVE",
+            "MESSAGE_REACTION_REMOVE_ALL",
+            "MESSAGE_REACTION_REMOVE_EMOJI",
+            "WEBHOOKS_UPDATE",
+        ];
+
+        for name in names {
+            // The data is invalid, so known events fail to deserialize instead of being unknown.
+            let result = deserialize(json!({"t": name, "d": {}}));
+            assert!(matches!(result, Err(Error::Json(_))), "{name}: {result:?}");
+        }
+
+        let event = deserialize(json!({
+            "t": "WEBHOOKS_UPDATE",
+            "d": {"guild_id": "1", "channel_id": "2"},
+        }))
+        .unwrap();
+        assert!(matches!(event, Event::WebhookUpdate(_)));
+        assert_eq!(event.name(), "WEBHOOK_UPDATE");
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::{Duration, Instant};
+
+use crate::gateway::ActivityData;
+use crate::internal::prelude::*;
+
+/// A list of activities that all shards of a [`ShardManager`] cycle through at a fixed interval,
+/// set with [`ClientBuilder::presence_rotation`] or [`ShardManager::set_presence_rotation`].
+///
+/// Shards that are started or restarted later pick up the rotation as well, and all shards show
+/// the same activity at the same time. The updates count towards the gateway's send ratelimit
+/// like any other presence update, so they are held back instead of getting a shard disconnected.
+///
+/// The names and states of the activities may contain placeholders that are filled in whenever
+/// the activity is applied:
+///
+/// - `{shard_id}`: the Id of the shard showing the activity.
+/// - `{shard_total}`: the total number of shards.
+/// - `{guild_count}`: the number of guilds in the cache, if the `cache` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use serenity::gateway::{ActivityData, PresenceRotation};
+/// use serenity::prelude::*;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let rotation = PresenceRotation::new(
+///     vec![ActivityData::watching("{guild_count} guilds"), ActivityData::listening("!help")],
+///     Duration::from_secs(60),
+/// );
+///
+/// let token = Token::from_env("DISCORD_TOKEN")?;
+/// let client =
+///     Client::builder(token, GatewayIntents::default()).presence_rotation(rotation).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`ShardManager`]: super::ShardManager
+/// [`ShardManager::set_presence_rotation`]: super::ShardManager::set_presence_rotation
+/// [`ClientBuilder::presence_rotation`]: crate::gateway::client::ClientBuilder::presence_rotation
+#[derive(Clone, Debug)]
+pub struct PresenceRotation {
+    activities: Vec<ActivityData>,
+    interval: Duration,
+    started: Instant,
+}
+
+impl PresenceRotation {
+    /// Creates a rotation that switches to the next of the given activities every `interval`,
+    /// starting with the first one.
+    ///
+    /// Intervals shorter than a second are raised to one second. An empty list of activities
+    /// leaves the presence of the shards alone.
+    #[must_use]
+    pub fn new(activities: Vec<ActivityData>, interval: Duration) -> Self {
+        Self {
+            activities,
+            interval: interval.max(Duration::from_secs(1)),
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the number of intervals that have passed since the rotation was created.
+    pub(crate) fn tick(&self, now: Instant) -> u128 {
+        now.saturating_duration_since(self.started).as_millis() / self.interval.as_millis()
+    }
+
+    /// Returns the activity to show during the given tick, with its placeholders filled in.
+    pub(crate) fn activity(
+        &self,
+        tick: u128,
+        placeholders: &[(&str, String)],
+    ) -> Option<ActivityData> {
+        let len = u128::try_from(self.activities.len()).ok().filter(|len| *len > 0)?;
+        let index = usize::try_from(tick % len).ok()?;
+        let mut activity = self.activities.get(index)?.clone();
+
+        activity.name = fill_placeholders(&activity.name, placeholders);
+        activity.state = activity.state.map(|state| fill_placeholders(&state, placeholders));
+
+        Some(activity)
+    }
+}
+
+fn fill_placeholders(text: &str, placeholders: &[(&str, String)]) -> FixedString {
+    let mut text = text.to_owned();
+    for (placeholder, value) in placeholders {
+        text = text.replace(placeholder, value);
+    }
+    text.trunc_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::PresenceRotation;
+    use crate::gateway::ActivityData;
+
+    #[test]
+    fn test_presence_rotation() {
+        let rotation = PresenceRotation::new(
+            vec![ActivityData::watching("{guild_count} guilds"), ActivityData::playing("games")],
+            Duration::from_millis(10),
+        );
+        let started = rotation.started;
+
+        // The interval is raised to a second.
+        assert_eq!(rotation.tick(started + Duration::from_millis(999)), 0);
+        assert_eq!(rotation.tick(started + Duration::from_secs(3)), 3);
+        assert_eq!(rotation.tick(started.checked_sub(Duration::from_secs(1)).unwrap()), 0);
+
+        let placeholders = [("{guild_count}", "12".to_owned())];
+        let first = rotation.activity(2, &placeholders).unwrap();
+        assert_eq!(first.name, "12 guilds");
+        let second = rotation.activity(3, &placeholders).unwrap();
+        assert_eq!(second.name, "games");
+
+        let empty = PresenceRotation::new(Vec::new(), Duration::from_secs(1));
+        assert!(empty.activity(0, &placeholders).is_none());
+        assert_eq!(empty.tick(Instant::now()), 0);
+    }
+}
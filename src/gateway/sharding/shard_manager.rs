@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU16;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 #[cfg(feature = "framework")]
 use std::sync::OnceLock;
@@ -7,34 +8,46 @@ use std::time::Duration;
 
 use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
 use futures::{SinkExt, StreamExt};
-use tokio::sync::Mutex;
-use tokio::time::timeout;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::time::{timeout, timeout_at, Instant};
 use tracing::{info, warn};
 
 use super::{
+    GatewayEncoding,
+    HealthTracker,
+    IdentifyQueue,
+    PresenceRotation,
+    ReshardProgressEvent,
+    ShardHealth,
+    ShardHealthThresholds,
     ShardId,
     ShardQueue,
     ShardQueuer,
     ShardQueuerMessage,
     ShardRunnerInfo,
+    ShardSession,
     TransportCompression,
 };
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::client::{EventHandler, RawEventHandler};
+use crate::gateway::client::dispatch::Dispatcher;
+use crate::gateway::client::{DispatchMode, EventHandler, EventStreamSender, RawEventHandler};
 #[cfg(feature = "voice")]
 use crate::gateway::VoiceGatewayManager;
-use crate::gateway::{ConnectionStage, GatewayError, PresenceData};
+use crate::gateway::{ConnectionStage, EventRecorder, EventSinkSender, GatewayError, PresenceData};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
-use crate::model::gateway::GatewayIntents;
+use crate::model::gateway::{GatewayIntents, SessionStartLimit};
 
 /// The default time to wait between starting each shard or set of shards.
 pub const DEFAULT_WAIT_BETWEEN_SHARD_START: Duration = Duration::from_secs(5);
 
+// How long the new shards of a reshard wait for the replaced ones to stop dispatching events.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// A manager for handling the status of shards by starting them, restarting them, and stopping
 /// them when required.
 ///
@@ -59,8 +72,9 @@ pub const DEFAULT_WAIT_BETWEEN_SHARD_START: Duration = Duration::from_secs(5);
 /// use std::env;
 /// use std::sync::{Arc, OnceLock};
 ///
-/// use serenity::gateway::client::EventHandler;
+/// use serenity::gateway::client::{DispatchMode, EventHandler};
 /// use serenity::gateway::{
+///     GatewayEncoding,
 ///     ShardManager,
 ///     ShardManagerOptions,
 ///     TransportCompression,
@@ -103,13 +117,22 @@ pub const DEFAULT_WAIT_BETWEEN_SHARD_START: Duration = Duration::from_secs(5);
 ///     max_concurrency,
 ///     wait_time_between_shard_start: DEFAULT_WAIT_BETWEEN_SHARD_START,
 ///     compression: TransportCompression::None,
+///     encoding: GatewayEncoding::Json,
+///     event_recorder: None,
+///     resume_sessions: Vec::new(),
+///     identify_queue: None,
+///     event_stream: None,
+///     event_sink: None,
+///     dispatch_mode: DispatchMode::Unbounded,
+///     session_start_limit: Some(gateway_info.session_start_limit),
+///     presence_rotation: None,
+///     health_thresholds: None,
 /// });
 /// # Ok(())
 /// # }
 /// ```
 ///
 /// [`Client`]: crate::Client
-#[derive(Debug)]
 pub struct ShardManager {
     return_value_tx: Mutex<Sender<Result<(), GatewayError>>>,
     /// The shard runners currently managed.
@@ -123,6 +146,82 @@ pub struct ShardManager {
     shard_shutdown: Mutex<Receiver<ShardId>>,
     shard_shutdown_send: Sender<ShardId>,
     gateway_intents: GatewayIntents,
+    // The resumable sessions of shards that have been shut down.
+    sessions: Mutex<HashMap<ShardId, ShardSession>>,
+    // The generation of shards that events are dispatched from, which is incremented by every
+    // reshard.
+    active_generation: AtomicU64,
+    // The generation of the last reshard, which may have been aborted before becoming active.
+    last_generation: AtomicU64,
+    resharding: parking_lot::Mutex<Option<Resharding>>,
+    generation_switched: Notify,
+    // The replaced shards of the last switch, by generation, that have yet to stop dispatching.
+    handoff: watch::Sender<HashSet<(u64, ShardId)>>,
+    dispatcher: Dispatcher,
+    presence_rotation: parking_lot::RwLock<Option<Arc<PresenceRotation>>>,
+    event_handler: Option<Arc<dyn EventHandler>>,
+    health: parking_lot::Mutex<HashMap<ShardId, Arc<HealthTracker>>>,
+    health_thresholds: Option<ShardHealthThresholds>,
+}
+
+impl std::fmt::Debug for ShardManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardManager")
+            .field("runners", &self.runners)
+            .field("gateway_intents", &self.gateway_intents)
+            .field("active_generation", &self.active_generation)
+            .field("dispatcher", &self.dispatcher)
+            .field("presence_rotation", &self.presence_rotation)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Waits for SIGTERM or Ctrl+C.
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = terminate.recv() => Ok(()),
+            result = tokio::signal::ctrl_c() => result,
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+// The state of a reshard in progress.
+#[derive(Debug)]
+struct Resharding {
+    generation: u64,
+    shard_total: NonZeroU16,
+    // The new shards that are connected and have loaded their guilds.
+    ready: HashSet<ShardId>,
+    all_ready: Arc<Notify>,
+}
+
+// Clears the reshard in progress once [`ShardManager::reshard`] returns, and aborts it if the
+// future was dropped before the switch.
+struct ReshardGuard<'a> {
+    manager: &'a ShardManager,
+    generation: u64,
+}
+
+impl Drop for ReshardGuard<'_> {
+    fn drop(&mut self) {
+        *self.manager.resharding.lock() = None;
+
+        if !self.manager.is_active_generation(self.generation) {
+            info!("Aborting reshard");
+            let msg = ShardQueuerMessage::AbortReshard {
+                generation: self.generation,
+            };
+            drop(self.manager.shard_queuer.unbounded_send(msg));
+        }
+    }
 }
 
 impl ShardManager {
@@ -143,6 +242,17 @@ impl ShardManager {
             shard_shutdown_send: shutdown_send,
             runners: Arc::clone(&runners),
             gateway_intents: opt.intents,
+            sessions: Mutex::new(HashMap::new()),
+            active_generation: AtomicU64::new(0),
+            last_generation: AtomicU64::new(0),
+            resharding: parking_lot::Mutex::new(None),
+            generation_switched: Notify::new(),
+            handoff: watch::Sender::new(HashSet::new()),
+            dispatcher: Dispatcher::new(opt.dispatch_mode),
+            presence_rotation: parking_lot::RwLock::new(opt.presence_rotation.map(Arc::new)),
+            event_handler: opt.event_handler.clone(),
+            health: parking_lot::Mutex::default(),
+            health_thresholds: opt.health_thresholds,
         });
 
         let mut shard_queuer = ShardQueuer {
@@ -161,7 +271,23 @@ impl ShardManager {
             voice_manager: opt.voice_manager,
             ws_url: opt.ws_url,
             compression: opt.compression,
+            encoding: opt.encoding,
+            event_recorder: opt.event_recorder,
+            resume_sessions: opt
+                .resume_sessions
+                .into_iter()
+                .map(|session| (session.shard_info.id, session))
+                .collect(),
+            identify_queue: opt.identify_queue,
+            event_stream: opt.event_stream,
+            event_sink: opt.event_sink,
+            session_start_limit: None,
+            session_start_reset: Instant::now(),
+            session_start_fetched: Instant::now(),
+            session_start_limit_low_reported: false,
             shard_total: opt.shard_total,
+            generation: 0,
+            resharding: None,
             #[cfg(feature = "cache")]
             cache: opt.cache,
             http: opt.http,
@@ -170,6 +296,10 @@ impl ShardManager {
             wait_time_between_shard_start: opt.wait_time_between_shard_start,
         };
 
+        if let Some(limit) = opt.session_start_limit {
+            shard_queuer.set_session_start_limit(limit);
+        }
+
         spawn_named("shard_queuer::run", async move {
             shard_queuer.run().await;
         });
@@ -177,6 +307,11 @@ impl ShardManager {
         (Arc::clone(&manager), return_value_rx)
     }
 
+    /// Returns the dispatcher shared by all shards, which spawns the tasks handling events.
+    pub(crate) fn dispatcher(&self) -> &Dispatcher {
+        &self.dispatcher
+    }
+
     /// Returns whether the shard manager contains either an active instance of a shard runner
     /// responsible for the given ID.
     ///
@@ -226,6 +361,93 @@ impl ShardManager {
         self.boot(shard_id, false);
     }
 
+    /// Switches to a new shard total without downtime.
+    ///
+    /// This starts a full set of shards with the new total in the background, while the current
+    /// shards keep dispatching events. Once all new shards are connected and have loaded their
+    /// guilds, event dispatch is switched over to them at once, and the current shards are shut
+    /// down. Progress is reported to [`EventHandler::reshard_progress`].
+    ///
+    /// This returns once the switch has happened, which can take a while for large bots, as the
+    /// new shards are started in accordance with the identify ratelimit.
+    ///
+    /// **Note**: This starts shards `0` up to the new total, so it should only be used if this
+    /// manager is responsible for all shards. Events for the new shards are not dispatched until
+    /// the switch, so the cache will keep being updated by the current shards only.
+    ///
+    /// Events received before the switch are dispatched by the current shards, and events received
+    /// after it by the new shards, which start dispatching once the current shards have dispatched
+    /// everything they received before.
+    ///
+    /// If the returned future is dropped before the switch, the reshard is aborted and the new
+    /// shards are shut down, which allows bounding it with a timeout.
+    ///
+    /// # Examples
+    ///
+    /// Doubling the shard count, giving up after an hour:
+    ///
+    /// ```rust,no_run
+    /// use std::num::NonZeroU16;
+    /// use std::time::Duration;
+    ///
+    /// use serenity::prelude::*;
+    ///
+    /// # async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let shard_total = client.shard_manager.shards_instantiated().await.len() as u16 * 2;
+    /// let reshard = client.shard_manager.reshard(NonZeroU16::new(shard_total).unwrap());
+    /// tokio::time::timeout(Duration::from_secs(3600), reshard).await??;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::ReshardInProgress`] if another reshard has not finished yet.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn reshard(&self, shard_total: NonZeroU16) -> Result<()> {
+        let (generation, all_ready) = {
+            let mut resharding = self.resharding.lock();
+            if resharding.is_some() {
+                return Err(Error::Gateway(GatewayError::ReshardInProgress));
+            }
+
+            let generation = self.last_generation.fetch_add(1, Ordering::Relaxed) + 1;
+            let all_ready = Arc::new(Notify::new());
+            *resharding = Some(Resharding {
+                generation,
+                shard_total,
+                ready: HashSet::new(),
+                all_ready: Arc::clone(&all_ready),
+            });
+
+            info!("Resharding to {shard_total} shards");
+            drop(self.shard_queuer.unbounded_send(ShardQueuerMessage::Reshard {
+                shard_total,
+                generation,
+            }));
+
+            (generation, all_ready)
+        };
+        let _guard = ReshardGuard {
+            manager: self,
+            generation,
+        };
+
+        all_ready.notified().await;
+
+        info!("Switching to {shard_total} shards");
+        drop(self.shard_queuer.unbounded_send(ShardQueuerMessage::FinishReshard));
+        loop {
+            let switched = self.generation_switched.notified();
+            if self.is_active_generation(generation) {
+                break;
+            }
+            switched.await;
+        }
+
+        Ok(())
+    }
+
     /// Returns the [`ShardId`]s of the shards that have been instantiated and currently have a
     /// valid [`ShardRunner`].
     ///
@@ -288,6 +510,106 @@ impl ShardManager {
     /// [`Self::shutdown`] method.
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     pub async fn shutdown_all(&self) {
+        self.shutdown_all_with_code(1000).await;
+    }
+
+    /// Shuts down all shards after the handlers of the events they already received have
+    /// finished, returning whether they finished before the timeout.
+    ///
+    /// Events received from this point on are dropped. Once the handlers have finished,
+    /// [`EventHandler::shutdown`] is called, and then all shards are shut down like with
+    /// [`Self::shutdown_all`]. If the handlers or the shutdown hook take longer than `timeout`,
+    /// the shards are shut down without waiting further, and handlers that are still running are
+    /// killed when the runtime ends.
+    ///
+    /// Events that are sent to an [`EventStream`] are not waited for.
+    ///
+    /// [`EventStream`]: crate::gateway::client::EventStream
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn shutdown_gracefully(&self, timeout: Duration) -> bool {
+        info!("Shutting down gracefully, waiting for {} events", self.dispatcher.in_flight());
+
+        let deadline = Instant::now() + timeout;
+        self.dispatcher.close();
+        let mut finished = timeout_at(deadline, self.dispatcher.wait_idle()).await.is_ok();
+        if !finished {
+            warn!("Timed out waiting for the handlers of {} events", self.dispatcher.in_flight());
+        }
+
+        if let Some(event_handler) = &self.event_handler {
+            if timeout_at(deadline, event_handler.shutdown()).await.is_err() {
+                warn!("Timed out waiting for the shutdown hook of the event handler");
+                finished = false;
+            }
+        }
+
+        self.shutdown_all().await;
+        finished
+    }
+
+    /// Spawns a task that calls [`Self::shutdown_gracefully`] once the process receives SIGTERM,
+    /// or Ctrl+C on any platform, such as when a container is stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use serenity::prelude::*;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let token = Token::from_env("DISCORD_TOKEN")?;
+    /// let mut client = Client::builder(token, GatewayIntents::default()).await?;
+    ///
+    /// client.shard_manager.shutdown_gracefully_on_signal(Duration::from_secs(30));
+    /// client.start().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shutdown_gracefully_on_signal(self: &Arc<Self>, timeout: Duration) {
+        let manager = Arc::clone(self);
+        spawn_named("shard_manager::shutdown_on_signal", async move {
+            if let Err(why) = shutdown_signal().await {
+                warn!("Failed to listen for shutdown signals: {why:?}");
+                return;
+            }
+
+            info!("Received shutdown signal");
+            manager.shutdown_gracefully(timeout).await;
+        });
+    }
+
+    /// Shuts down all shards like [`Self::shutdown_all`], but without invalidating their
+    /// sessions, and returns the sessions so that they can be resumed after a restart.
+    ///
+    /// Shards that had not connected yet have no session and are left out.
+    ///
+    /// # Examples
+    ///
+    /// Saving the sessions on shutdown, to resume them with [`ClientBuilder::resume_sessions`]:
+    ///
+    /// ```rust,no_run
+    /// use serenity::prelude::*;
+    ///
+    /// # async fn run(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let sessions = client.shard_manager.shutdown_all_resumable().await;
+    /// std::fs::write("sessions.json", serde_json::to_vec(&sessions)?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ClientBuilder::resume_sessions`]: crate::gateway::client::ClientBuilder::resume_sessions
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn shutdown_all_resumable(&self) -> Vec<ShardSession> {
+        self.sessions.lock().await.clear();
+        self.shutdown_all_with_code(4000).await;
+
+        let mut sessions: Vec<_> = self.sessions.lock().await.drain().map(|(_, s)| s).collect();
+        sessions.sort_unstable_by_key(|session| session.shard_info.id);
+        sessions
+    }
+
+    async fn shutdown_all_with_code(&self, code: u16) {
         let keys = {
             let runners = self.runners.lock().await;
 
@@ -301,7 +623,7 @@ impl ShardManager {
         info!("Shutting down all shards");
 
         for shard_id in keys {
-            self.shutdown(shard_id, 1000).await;
+            self.shutdown(shard_id, code).await;
         }
 
         drop(self.shard_queuer.unbounded_send(ShardQueuerMessage::Shutdown));
@@ -340,6 +662,129 @@ impl ShardManager {
         }
     }
 
+    /// Sets the activities that all shards cycle through, replacing the current rotation, or stops
+    /// rotating if [`None`] is given.
+    ///
+    /// Stopping leaves the current activity of each shard as it is. Refer to [`PresenceRotation`]
+    /// for more information.
+    pub fn set_presence_rotation(&self, rotation: Option<PresenceRotation>) {
+        *self.presence_rotation.write() = rotation.map(Arc::new);
+    }
+
+    pub(super) fn presence_rotation(&self) -> Option<Arc<PresenceRotation>> {
+        self.presence_rotation.read().clone()
+    }
+
+    /// Returns the health of all running shards, ordered by their Ids.
+    ///
+    /// # Examples
+    ///
+    /// Logging shards that haven't received a heartbeat acknowledgement for a while:
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// # async fn run(client: serenity::Client) {
+    /// for health in client.shard_manager.health().await {
+    ///     if health.since_heartbeat_ack.is_some_and(|since| since > Duration::from_secs(60)) {
+    ///         println!("Shard {} may be a zombie: {health:?}", health.shard_id);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn health(&self) -> Vec<ShardHealth> {
+        let now = std::time::Instant::now();
+        let mut health: Vec<_> = self
+            .runners
+            .lock()
+            .await
+            .iter()
+            .map(|(shard_id, runner)| {
+                self.health_tracker(*shard_id).snapshot(*shard_id, runner.stage, now)
+            })
+            .collect();
+        health.sort_unstable_by_key(|health| health.shard_id);
+        health
+    }
+
+    /// Returns the health tracker of the shard, which is kept when the shard is restarted.
+    pub(super) fn health_tracker(&self, shard_id: ShardId) -> Arc<HealthTracker> {
+        Arc::clone(self.health.lock().entry(shard_id).or_default())
+    }
+
+    pub(super) fn health_thresholds(&self) -> Option<ShardHealthThresholds> {
+        self.health_thresholds
+    }
+
+    /// Returns whether events should be dispatched from shards of the given generation.
+    pub(super) fn is_active_generation(&self, generation: u64) -> bool {
+        self.active_generation.load(Ordering::Acquire) == generation
+    }
+
+    /// Switches event dispatch over to the shards of the given generation, which first wait for
+    /// the replaced shards to dispatch the events they have already received, see
+    /// [`Self::wait_for_handoff`].
+    pub(super) fn set_active_generation(
+        &self,
+        generation: u64,
+        replaced: impl IntoIterator<Item = ShardId>,
+    ) {
+        let previous = self.active_generation.load(Ordering::Acquire);
+        self.handoff.send_replace(replaced.into_iter().map(|id| (previous, id)).collect());
+        self.active_generation.store(generation, Ordering::Release);
+        self.generation_switched.notify_waiters();
+    }
+
+    /// Marks a shard replaced by a reshard as no longer dispatching events.
+    pub(super) fn generation_handed_off(&self, generation: u64, shard_id: ShardId) {
+        self.handoff.send_if_modified(|pending| pending.remove(&(generation, shard_id)));
+    }
+
+    /// Waits for the shards replaced by the last switch to stop dispatching events, so that the
+    /// events of the new shards are dispatched after theirs.
+    pub(super) async fn wait_for_handoff(&self) {
+        let mut pending = self.handoff.subscribe();
+        if timeout(HANDOFF_TIMEOUT, pending.wait_for(HashSet::is_empty)).await.is_err() {
+            warn!("Replaced shards did not stop dispatching in time");
+        }
+    }
+
+    /// Marks a shard started by [`Self::reshard`] as ready, returning the progress of the reshard
+    /// if the shard belongs to it.
+    pub(super) fn reshard_shard_ready(
+        &self,
+        generation: u64,
+        shard_id: ShardId,
+    ) -> Option<ReshardProgressEvent> {
+        let mut resharding = self.resharding.lock();
+        let resharding = resharding.as_mut().filter(|r| r.generation == generation)?;
+
+        resharding.ready.insert(shard_id);
+        let shards_ready = resharding.ready.len() as u16;
+        if shards_ready == resharding.shard_total.get() {
+            resharding.all_ready.notify_one();
+        }
+
+        Some(ReshardProgressEvent {
+            shard_total: resharding.shard_total,
+            shards_ready,
+            switched: false,
+        })
+    }
+
+    /// Restarts a shard started by [`Self::reshard`], before the switch to the new shards.
+    pub(super) fn restart_reshard_shard(&self, generation: u64, shard_id: ShardId) {
+        let msg = ShardQueuerMessage::RestartReshardShard {
+            shard_id,
+            generation,
+        };
+        drop(self.shard_queuer.unbounded_send(msg));
+    }
+
+    pub(super) async fn store_session(&self, session: ShardSession) {
+        self.sessions.lock().await.insert(session.shard_info.id, session);
+    }
+
     pub fn shutdown_finished(&self, id: ShardId) {
         if let Err(e) = self.shard_shutdown_send.unbounded_send(id) {
             tracing::warn!("failed to notify about finished shutdown: {}", e);
@@ -398,4 +843,29 @@ pub struct ShardManagerOptions {
     /// Number of seconds to wait between starting each shard/set of shards start
     pub wait_time_between_shard_start: Duration,
     pub compression: TransportCompression,
+    pub encoding: GatewayEncoding,
+    /// The recorder to write received events to, if any.
+    pub event_recorder: Option<Arc<EventRecorder>>,
+    /// Sessions saved by [`ShardManager::shutdown_all_resumable`], which shards will try to
+    /// resume instead of identifying.
+    pub resume_sessions: Vec<ShardSession>,
+    /// The queue shared with other processes to coordinate identifies with, if any.
+    pub identify_queue: Option<Arc<dyn IdentifyQueue>>,
+    /// The sending half of [`Client::event_stream`], if enabled.
+    ///
+    /// [`Client::event_stream`]: crate::Client::event_stream
+    pub event_stream: Option<EventStreamSender>,
+    /// The sender forwarding raw dispatches to an [`EventSink`], if any.
+    ///
+    /// [`EventSink`]: crate::gateway::EventSink
+    pub event_sink: Option<EventSinkSender>,
+    /// How events are dispatched to the event handlers and the framework.
+    pub dispatch_mode: DispatchMode,
+    /// The session start limit returned by [`Http::get_bot_gateway`], if known, which keeps shards
+    /// from identifying once it is exhausted.
+    pub session_start_limit: Option<SessionStartLimit>,
+    /// The activities that all shards cycle through, if any.
+    pub presence_rotation: Option<PresenceRotation>,
+    /// When shards are considered unhealthy, or [`None`] to not check their health.
+    pub health_thresholds: Option<ShardHealthThresholds>,
 }
@@ -1,13 +1,23 @@
-#[cfg(feature = "collector")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use futures::channel::mpsc::UnboundedSender as Sender;
 use tokio_tungstenite::tungstenite::Message;
 
 #[cfg(feature = "collector")]
 use super::CollectorCallback;
-use super::{ChunkGuildFilter, ShardRunner, ShardRunnerMessage};
-use crate::gateway::ActivityData;
+use super::{
+    ChunkGuildFilter,
+    ChunkRequests,
+    GuildMembers,
+    ShardRunner,
+    ShardRunnerMessage,
+    VoiceConnectionInfo,
+    VoiceRequests,
+};
+use crate::gateway::{ActivityData, GatewayError};
+use crate::internal::prelude::*;
 use crate::model::prelude::*;
 
 /// A handle to a [`ShardRunner`].
@@ -22,6 +32,9 @@ pub struct ShardMessenger {
     pub(crate) tx: Sender<ShardRunnerMessage>,
     #[cfg(feature = "collector")]
     pub(crate) collectors: Arc<parking_lot::RwLock<Vec<CollectorCallback>>>,
+    pub(crate) chunk_requests: Arc<ChunkRequests>,
+    pub(crate) queued_commands: Arc<AtomicUsize>,
+    pub(crate) voice_requests: Arc<VoiceRequests>,
 }
 
 impl ShardMessenger {
@@ -36,9 +49,22 @@ impl ShardMessenger {
             tx: shard.runner_tx(),
             #[cfg(feature = "collector")]
             collectors: Arc::clone(&shard.collectors),
+            chunk_requests: Arc::clone(&shard.chunk_requests),
+            queued_commands: Arc::clone(&shard.queued_commands),
+            voice_requests: Arc::clone(&shard.voice_requests),
         }
     }
 
+    /// Returns the number of commands, such as presence updates and member requests, that are
+    /// waiting to be sent because the shard reached the gateway's send ratelimit.
+    ///
+    /// Discord allows 120 payloads per minute on each connection, part of which is kept free for
+    /// heartbeats. A growing number means commands are being sent faster than that.
+    #[must_use]
+    pub fn queued_commands(&self) -> usize {
+        self.queued_commands.load(Ordering::Relaxed)
+    }
+
     /// Requests that one or multiple [`Guild`]s be chunked.
     ///
     /// This will ask the gateway to start sending member chunks for large guilds (250 members+).
@@ -107,6 +133,59 @@ impl ShardMessenger {
         });
     }
 
+    /// Requests members of a [`Guild`] like [`Self::chunk_guild`] does, and waits for all of the
+    /// member chunks to be received.
+    ///
+    /// The chunks are still dispatched as [`Event::GuildMembersChunk`] events, and update the cache
+    /// if the `cache` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// Fetch two members by Id, waiting up to 10 seconds:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::gateway::{ChunkGuildFilter, ShardMessenger};
+    /// # async fn run(shard: ShardMessenger) -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::time::Duration;
+    ///
+    /// use serenity::model::id::{GuildId, UserId};
+    ///
+    /// let user_ids = vec![UserId::new(114941315417899012), UserId::new(80351110224678912)];
+    /// let response = shard
+    ///     .request_members(
+    ///         GuildId::new(81384788765712384),
+    ///         None,
+    ///         false,
+    ///         ChunkGuildFilter::UserIds(user_ids),
+    ///         Duration::from_secs(10),
+    ///     )
+    ///     .await?;
+    ///
+    /// println!("Found {} members", response.members.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::MemberChunksTimedOut`] if not all chunks were received before the
+    /// timeout, such as when the guild is not available to the shard, or the shard is shut down.
+    pub async fn request_members(
+        &self,
+        guild_id: GuildId,
+        limit: Option<u16>,
+        presences: bool,
+        filter: ChunkGuildFilter,
+        timeout: Duration,
+    ) -> Result<GuildMembers> {
+        let mut request = self.chunk_requests.register();
+        self.chunk_guild(guild_id, limit, presences, filter, Some(request.nonce.clone()));
+
+        tokio::time::timeout(timeout, request.collect(guild_id))
+            .await
+            .map_err(|_| Error::Gateway(GatewayError::MemberChunksTimedOut))
+    }
+
     /// Sets the user's current activity, if any.
     ///
     /// Other presence settings are maintained.
@@ -187,6 +266,92 @@ impl ShardMessenger {
         self.send_to_shard(ShardRunnerMessage::SetStatus(online_status));
     }
 
+    /// Joins, moves between or leaves voice channels of a guild, by updating the current user's
+    /// voice state.
+    ///
+    /// Passing [`None`] as the channel leaves the current voice channel of the guild. Requires
+    /// [`GatewayIntents::GUILD_VOICE_STATES`] to receive the resulting voice state updates.
+    ///
+    /// # Examples
+    ///
+    /// Leave the voice channel of a guild:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::gateway::ShardMessenger;
+    /// # fn run(shard: ShardMessenger) {
+    /// use serenity::model::id::GuildId;
+    ///
+    /// shard.update_voice_state(GuildId::new(81384788765712384), None, false, false);
+    /// # }
+    /// ```
+    pub fn update_voice_state(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        self_mute: bool,
+        self_deaf: bool,
+    ) {
+        self.send_to_shard(ShardRunnerMessage::UpdateVoiceState {
+            guild_id,
+            channel_id,
+            self_mute,
+            self_deaf,
+        });
+    }
+
+    /// Joins a voice channel like [`Self::update_voice_state`] does, and waits for the voice
+    /// state and voice server updates that follow, which hold what is needed to connect to the
+    /// voice server.
+    ///
+    /// The updates are still dispatched as [`Event::VoiceStateUpdate`] and
+    /// [`Event::VoiceServerUpdate`] events. Joining another channel of the same guild before this
+    /// completes makes it time out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::gateway::ShardMessenger;
+    /// # async fn run(shard: ShardMessenger) -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::time::Duration;
+    ///
+    /// use serenity::model::id::{ChannelId, GuildId};
+    ///
+    /// let guild_id = GuildId::new(81384788765712384);
+    /// let channel_id = ChannelId::new(381880193700069377);
+    /// let info = shard
+    ///     .join_voice_channel(guild_id, channel_id, false, true, Duration::from_secs(10))
+    ///     .await?;
+    ///
+    /// println!("Connecting to {} with session {}", info.endpoint, info.session_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GatewayError::VoiceConnectionTimedOut`] if the updates were not received before
+    /// the timeout, such as when the current user lacks permission to join the channel, the
+    /// [`GatewayIntents::GUILD_VOICE_STATES`] intent is missing, or the shard is shut down.
+    pub async fn join_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        self_mute: bool,
+        self_deaf: bool,
+        timeout: Duration,
+    ) -> Result<VoiceConnectionInfo> {
+        let rx = self.voice_requests.register(guild_id, channel_id);
+        self.update_voice_state(guild_id, Some(channel_id), self_mute, self_deaf);
+
+        // The sender is dropped when the join is replaced by another.
+        if let Ok(Ok(info)) = tokio::time::timeout(timeout, rx).await {
+            return Ok(info);
+        }
+
+        self.voice_requests.remove_abandoned(guild_id);
+        Err(Error::Gateway(GatewayError::VoiceConnectionTimedOut))
+    }
+
     /// Shuts down the websocket by attempting to cleanly close the connection.
     pub fn shutdown_clean(&self) {
         self.send_to_shard(ShardRunnerMessage::Close(1000, None));
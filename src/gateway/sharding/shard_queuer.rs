@@ -11,26 +11,40 @@ use tokio::time::{sleep, timeout, Duration, Instant};
 use tracing::{debug, info, warn};
 
 use super::{
+    GatewayEncoding,
+    IdentifyQueue,
     ShardId,
     ShardManager,
     ShardMessenger,
     ShardRunner,
     ShardRunnerInfo,
     ShardRunnerOptions,
+    ShardSession,
     TransportCompression,
 };
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::client::{EventHandler, RawEventHandler};
+use crate::gateway::client::{EventHandler, EventStreamSender, RawEventHandler};
 #[cfg(feature = "voice")]
 use crate::gateway::VoiceGatewayManager;
-use crate::gateway::{ConnectionStage, PresenceData, Shard, ShardRunnerMessage};
+use crate::gateway::{
+    ConnectionStage,
+    EventRecorder,
+    EventSinkSender,
+    GatewayError,
+    PresenceData,
+    Shard,
+    ShardRunnerMessage,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
-use crate::model::gateway::{GatewayIntents, ShardInfo};
+use crate::model::gateway::{GatewayIntents, SessionStartLimit, ShardInfo};
+
+// How often the session start limit is fetched again while it runs low.
+const SESSION_START_LIMIT_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
 
 /// The shard queuer is a simple loop that runs indefinitely to manage the startup of shards.
 ///
@@ -68,6 +82,8 @@ pub struct ShardQueuer {
     pub ws_url: Arc<str>,
     /// The compression method to use for the WebSocket connection.
     pub compression: TransportCompression,
+    /// The encoding to use for the WebSocket connection.
+    pub encoding: GatewayEncoding,
     /// The total amount of shards to start.
     pub shard_total: NonZeroU16,
     /// Number of seconds to wait between each start
@@ -77,6 +93,43 @@ pub struct ShardQueuer {
     pub http: Arc<Http>,
     pub intents: GatewayIntents,
     pub presence: Option<PresenceData>,
+    /// The recorder to write received events to, if any.
+    pub event_recorder: Option<Arc<EventRecorder>>,
+    /// Saved sessions to resume when starting the respective shards, instead of identifying.
+    ///
+    /// Sessions are removed once used, so that restarts of a shard identify as usual.
+    pub resume_sessions: HashMap<ShardId, ShardSession>,
+    /// The queue shared with other processes to coordinate identifies with, if any.
+    pub identify_queue: Option<Arc<dyn IdentifyQueue>>,
+    /// The sending half of the event stream, if enabled.
+    pub event_stream: Option<EventStreamSender>,
+    /// The sender forwarding raw dispatches to an event sink, if any.
+    pub event_sink: Option<EventSinkSender>,
+    /// The session start limit as last reported by Discord, if known.
+    ///
+    /// The remaining sessions are counted down with every identify, and fetched again once they
+    /// run low or the limit resets.
+    pub session_start_limit: Option<SessionStartLimit>,
+    /// When the session start limit resets.
+    pub(super) session_start_reset: Instant,
+    /// When the session start limit was last fetched, or attempted to be.
+    pub(super) session_start_fetched: Instant,
+    /// Whether [`EventHandler::session_start_limit_low`] has been dispatched since the limit was
+    /// last fetched with plenty of sessions remaining.
+    pub(super) session_start_limit_low_reported: bool,
+    /// The generation of the shards being started, see [`ShardManager::reshard`].
+    pub(super) generation: u64,
+    /// The new shards of a reshard in progress.
+    pub(super) resharding: Option<ReshardingShards>,
+}
+
+/// The shards started by [`ShardManager::reshard`], which replace the current shards once they
+/// are all ready.
+pub(super) struct ReshardingShards {
+    shard_total: NonZeroU16,
+    generation: u64,
+    queue: ShardQueue,
+    runners: HashMap<ShardId, ShardRunnerInfo>,
 }
 
 impl ShardQueuer {
@@ -92,6 +145,8 @@ impl ShardQueuer {
     ///    passed
     /// 3. Start the shard by ID
     ///
+    /// While the session start limit is exhausted, queued shards are not started until it resets.
+    ///
     /// If a [`ShardQueuerMessage::Shutdown`] is received, this will return and the loop will be
     /// over.
     ///
@@ -104,8 +159,22 @@ impl ShardQueuer {
         // queue is popped in batches of shards, which are started in parallel. A batch is fired
         // every WAIT_TIME_BETWEEN_SHARD_START at minimum in order to avoid being ratelimited.
 
+        let mut parked_until = None;
         loop {
-            if let Ok(msg) = timeout(self.wait_time_between_shard_start, self.rx.next()).await {
+            // Once the session start limit is exhausted, queued shards wait for it to reset.
+            let exhausted_until = self.session_start_exhausted_until();
+            if let Some(reset) = exhausted_until.filter(|reset| parked_until != Some(*reset)) {
+                warn!(
+                    "[Shard Queuer] Session start limit exhausted, waiting {:?} for it to reset",
+                    reset.saturating_duration_since(Instant::now())
+                );
+            }
+            parked_until = exhausted_until;
+            let wait = exhausted_until.map_or(self.wait_time_between_shard_start, |reset| {
+                reset.saturating_duration_since(Instant::now())
+            });
+
+            if let Ok(msg) = timeout(wait, self.rx.next()).await {
                 match msg {
                     Some(ShardQueuerMessage::SetShardTotal(shard_total)) => {
                         self.shard_total = shard_total;
@@ -114,14 +183,18 @@ impl ShardQueuer {
                         shard_id,
                         concurrent,
                     }) => {
-                        if concurrent {
+                        if self.resume_session(shard_id).is_some() {
+                            // Resuming doesn't count towards the identify ratelimit, so there is
+                            // no need to queue the shard.
+                            self.try_start(shard_id, false).await;
+                        } else if concurrent {
                             // If we're starting multiple shards, we can start them concurrently
                             // according to `max_concurrency`, and want our batches to be of
                             // maximal size.
                             self.queue.push_back(shard_id);
                             if self.queue.buckets_filled() {
                                 let batch = self.queue.pop_batch();
-                                self.checked_start_batch(batch).await;
+                                self.checked_start_batch(batch, false).await;
                             }
                         } else {
                             // In cases where we're only starting a single shard (e.g. if we're
@@ -140,6 +213,41 @@ impl ShardQueuer {
                         );
                         self.shutdown(shard_id, code).await;
                     },
+                    Some(ShardQueuerMessage::Reshard {
+                        shard_total,
+                        generation,
+                    }) => {
+                        let mut queue = ShardQueue::new(self.queue.max_concurrency());
+                        for shard_id in 0..shard_total.get() {
+                            queue.push_back(ShardId(shard_id));
+                        }
+
+                        self.resharding = Some(ReshardingShards {
+                            shard_total,
+                            generation,
+                            queue,
+                            runners: HashMap::new(),
+                        });
+                    },
+                    Some(ShardQueuerMessage::RestartReshardShard {
+                        shard_id,
+                        generation,
+                    }) => {
+                        if let Some(resharding) =
+                            self.resharding.as_mut().filter(|r| r.generation == generation)
+                        {
+                            resharding.runners.remove(&shard_id);
+                            resharding.queue.push_back(shard_id);
+                        }
+                    },
+                    Some(ShardQueuerMessage::FinishReshard) => self.finish_reshard().await,
+                    Some(ShardQueuerMessage::AbortReshard {
+                        generation,
+                    }) => {
+                        if self.resharding.as_ref().is_some_and(|r| r.generation == generation) {
+                            self.abort_reshard();
+                        }
+                    },
                     Some(ShardQueuerMessage::Shutdown) => {
                         debug!("[Shard Queuer] Received to shutdown all shards");
                         self.shutdown_runners().await;
@@ -147,15 +255,28 @@ impl ShardQueuer {
                     },
                     None => break,
                 }
-            } else {
+            } else if self.session_start_exhausted_until().is_none() {
                 // Once we've stopped receiving `Start` commands, we no longer care about the size
                 // of our batches being maximal.
                 let batch = self.queue.pop_batch();
-                self.checked_start_batch(batch).await;
+                self.checked_start_batch(batch, false).await;
+
+                // The shards of a reshard are started in the background, one batch at a time.
+                if let Some(resharding) = &mut self.resharding {
+                    let batch = resharding.queue.pop_batch();
+                    self.checked_start_batch(batch, true).await;
+                }
             }
         }
     }
 
+    /// Returns the saved session of the shard, if it can be resumed.
+    fn resume_session(&self, shard_id: ShardId) -> Option<&ShardSession> {
+        // A session can only be resumed with the shard total it was created with.
+        let session = self.resume_sessions.get(&shard_id)?;
+        (session.shard_info.total == self.shard_total).then_some(session)
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn check_last_start(&mut self) {
         let Some(instant) = self.last_start else { return };
@@ -177,13 +298,13 @@ impl ShardQueuer {
         debug!("[Shard Queuer] Checked start for shard {shard_id}");
 
         self.check_last_start().await;
-        self.try_start(shard_id).await;
+        self.try_start(shard_id, false).await;
 
         self.last_start = Some(Instant::now());
     }
 
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
-    async fn checked_start_batch(&mut self, shard_ids: Vec<ShardId>) {
+    async fn checked_start_batch(&mut self, shard_ids: Vec<ShardId>, resharding: bool) {
         if shard_ids.is_empty() {
             return;
         }
@@ -192,41 +313,79 @@ impl ShardQueuer {
         self.check_last_start().await;
         for shard_id in shard_ids {
             debug!("[Shard Queuer] Starting shard {shard_id}");
-            self.try_start(shard_id).await;
+            self.try_start(shard_id, resharding).await;
         }
         self.last_start = Some(Instant::now());
     }
 
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
-    async fn try_start(&mut self, shard_id: ShardId) {
-        if let Err(why) = self.start(shard_id).await {
-            warn!("[Shard Queuer] Err starting shard {shard_id}: {why:?}");
-            info!("[Shard Queuer] Re-queueing start of shard {shard_id}");
+    async fn try_start(&mut self, shard_id: ShardId, resharding: bool) {
+        if let Err(why) = self.start(shard_id, resharding).await {
+            // The exhausted session start limit is reported once by the queuer loop.
+            if !matches!(why, Error::Gateway(GatewayError::SessionStartLimitExhausted)) {
+                warn!("[Shard Queuer] Err starting shard {shard_id}: {why:?}");
+                info!("[Shard Queuer] Re-queueing start of shard {shard_id}");
+            }
 
             // Try again in the next batch.
-            self.queue.push_front(shard_id);
+            match &mut self.resharding {
+                Some(resharding_shards) if resharding => {
+                    resharding_shards.queue.push_front(shard_id);
+                },
+                _ => self.queue.push_front(shard_id),
+            }
         }
     }
 
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
-    async fn start(&mut self, shard_id: ShardId) -> Result<()> {
+    async fn start(&mut self, shard_id: ShardId, resharding: bool) -> Result<()> {
+        let (total, generation) = match &self.resharding {
+            Some(resharding_shards) if resharding => {
+                (resharding_shards.shard_total, resharding_shards.generation)
+            },
+            _ => (self.shard_total, self.generation),
+        };
         let shard_info = ShardInfo {
             id: shard_id,
-            total: self.shard_total,
+            total,
         };
+
+        // Shards of a reshard have a new shard total, so they can't resume any saved sessions.
+        let resumable = !resharding && self.resume_session(shard_id).is_some();
+        let session = self.resume_sessions.remove(&shard_id).filter(|_| resumable);
+
+        let ws_url = match &session {
+            Some(session) => Arc::from(&*session.resume_ws_url),
+            None => Arc::clone(&self.ws_url),
+        };
+
+        if session.is_none() {
+            self.check_session_start_limit().await?;
+        }
+
+        if let (None, Some(identify_queue)) = (&session, &self.identify_queue) {
+            let bucket = shard_id.0 % self.queue.max_concurrency();
+            identify_queue.acquire(bucket).await?;
+        }
+
         let mut shard = Shard::new(
-            Arc::clone(&self.ws_url),
+            ws_url,
             self.token.clone(),
             shard_info,
             self.intents,
             self.presence.clone(),
             self.compression,
+            self.encoding,
         )
         .await?;
 
         let cloned_http = Arc::clone(&self.http);
         shard.set_application_id_callback(move |id| cloned_http.set_application_id(id));
 
+        if let Some(session) = session {
+            shard.resume_session(session).await?;
+        }
+
         let mut runner = ShardRunner::new(ShardRunnerOptions {
             data: Arc::clone(&self.data),
             event_handler: self.event_handler.clone(),
@@ -240,7 +399,11 @@ impl ShardQueuer {
             #[cfg(feature = "cache")]
             cache: Arc::clone(&self.cache),
             http: Arc::clone(&self.http),
+            event_recorder: self.event_recorder.clone(),
+            event_stream: self.event_stream.clone(),
+            event_sink: self.event_sink.clone(),
         });
+        runner.generation = generation;
 
         let runner_info = ShardRunnerInfo {
             latency: None,
@@ -253,11 +416,118 @@ impl ShardQueuer {
             debug!("[ShardRunner {:?}] Stopping", runner.shard.shard_info());
         });
 
-        self.runners.lock().await.insert(shard_id, runner_info);
+        match &mut self.resharding {
+            Some(resharding_shards) if resharding => {
+                resharding_shards.runners.insert(shard_id, runner_info);
+            },
+            _ => {
+                self.runners.lock().await.insert(shard_id, runner_info);
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Returns when the session start limit resets if it is exhausted.
+    fn session_start_exhausted_until(&self) -> Option<Instant> {
+        let limit = self.session_start_limit.as_ref()?;
+        (limit.remaining == 0 && Instant::now() < self.session_start_reset)
+            .then_some(self.session_start_reset)
+    }
+
+    /// Makes sure another session can be started before a shard identifies, and counts it towards
+    /// the session start limit.
+    async fn check_session_start_limit(&mut self) -> Result<()> {
+        let Some(limit) = &self.session_start_limit else { return Ok(()) };
+
+        // Other processes may start sessions as well, so the limit is fetched again every now and
+        // then once it runs low. Once it is exhausted, it can only change by resetting.
+        let now = Instant::now();
+        let reset = now >= self.session_start_reset;
+        let refetch = limit.remaining > 0
+            && limit.remaining <= limit.total / 10
+            && now >= self.session_start_fetched + SESSION_START_LIMIT_REFETCH_INTERVAL;
+        if reset || refetch {
+            self.session_start_fetched = now;
+            match self.http.get_bot_gateway().await {
+                Ok(gateway) => self.set_session_start_limit(gateway.session_start_limit),
+                Err(why) => {
+                    warn!("[Shard Queuer] Failed to fetch the session start limit: {why:?}");
+                    if reset {
+                        return Ok(());
+                    }
+                },
+            }
+        }
+
+        let Some(limit) = &mut self.session_start_limit else { return Ok(()) };
+        if limit.remaining == 0 {
+            return Err(Error::Gateway(GatewayError::SessionStartLimitExhausted));
+        }
+
+        limit.remaining -= 1;
+        if limit.remaining <= limit.total / 10 && !self.session_start_limit_low_reported {
+            self.session_start_limit_low_reported = true;
+            warn!(
+                "[Shard Queuer] Only {} of {} session starts remaining",
+                limit.remaining, limit.total
+            );
+
+            if let Some(event_handler) = &self.event_handler {
+                let event_handler = Arc::clone(event_handler);
+                let limit = limit.clone();
+                spawn_named("dispatch::event_handler::session_start_limit_low", async move {
+                    event_handler.session_start_limit_low(limit).await;
+                });
+            }
+        }
 
         Ok(())
     }
 
+    pub(super) fn set_session_start_limit(&mut self, limit: SessionStartLimit) {
+        let now = Instant::now();
+        self.session_start_reset = now + Duration::from_millis(limit.reset_after);
+        self.session_start_fetched = now;
+        if limit.remaining > limit.total / 10 {
+            self.session_start_limit_low_reported = false;
+        }
+        self.session_start_limit = Some(limit);
+    }
+
+    /// Switches event dispatch over to the shards of the reshard in progress, and shuts down the
+    /// shards they replace.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    async fn finish_reshard(&mut self) {
+        let Some(resharding) = self.resharding.take() else { return };
+
+        self.shard_total = resharding.shard_total;
+        self.generation = resharding.generation;
+        // Restarts of the replaced shards that are still queued are dropped along with the queue.
+        self.queue = resharding.queue;
+
+        let replaced_runners = {
+            let mut runners = self.runners.lock().await;
+            self.manager.set_active_generation(resharding.generation, runners.keys().copied());
+            std::mem::replace(&mut *runners, resharding.runners)
+        };
+
+        #[cfg(feature = "cache")]
+        {
+            let mut shard_data = self.cache.shard_data.write();
+            shard_data.total = resharding.shard_total;
+            shard_data.connected = (0..resharding.shard_total.get()).map(ShardId).collect();
+        }
+
+        info!("[Shard Queuer] Switched to {} shards", resharding.shard_total);
+
+        for (shard_id, runner) in replaced_runners {
+            // The sessions of the replaced shards are of no use anymore.
+            let msg = ShardRunnerMessage::Shutdown(shard_id, 1000);
+            drop(runner.runner_tx.tx.unbounded_send(msg));
+        }
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn shutdown_runners(&mut self) {
         let keys = {
@@ -275,6 +545,18 @@ impl ShardQueuer {
         for shard_id in keys {
             self.shutdown(shard_id, 1000).await;
         }
+
+        self.abort_reshard();
+    }
+
+    /// Shuts down the shards of the reshard in progress, if any.
+    fn abort_reshard(&mut self) {
+        if let Some(resharding) = self.resharding.take() {
+            for (shard_id, runner) in resharding.runners {
+                let msg = ShardRunnerMessage::Shutdown(shard_id, 1000);
+                drop(runner.runner_tx.tx.unbounded_send(msg));
+            }
+        }
     }
 
     /// Attempts to shut down the shard runner by Id.
@@ -333,6 +615,12 @@ impl ShardQueue {
         self.buckets[bucket].push_front(shard_id);
     }
 
+    /// Returns the number of buckets, which is the number of shards that can be started at once.
+    #[must_use]
+    pub fn max_concurrency(&self) -> NonZeroU16 {
+        NonZeroU16::new(self.buckets.len()).expect("there is at least one bucket")
+    }
+
     /// Pops a `ShardId` from every bucket containing at least one and returns them all as a `Vec`.
     pub fn pop_batch(&mut self) -> Vec<ShardId> {
         self.buckets.iter_mut().filter_map(VecDeque::pop_front).collect()
@@ -356,4 +644,13 @@ pub enum ShardQueuerMessage {
     Shutdown,
     /// Message to dequeue/shutdown a shard.
     ShutdownShard { shard_id: ShardId, code: u16 },
+    /// Message to start a full set of shards with a new shard total in the background, see
+    /// [`ShardManager::reshard`].
+    Reshard { shard_total: NonZeroU16, generation: u64 },
+    /// Message to restart a shard started by a reshard.
+    RestartReshardShard { shard_id: ShardId, generation: u64 },
+    /// Message to switch over to the shards started by a reshard.
+    FinishReshard,
+    /// Message to shut down the shards started by a reshard that was cancelled before the switch.
+    AbortReshard { generation: u64 },
 }
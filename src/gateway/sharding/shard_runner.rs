@@ -1,5 +1,8 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
+use std::time::Instant;
 
 use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
 use tokio_tungstenite::tungstenite;
@@ -10,21 +13,46 @@ use tracing::{debug, error, info, trace, warn};
 
 #[cfg(feature = "collector")]
 use super::CollectorCallback;
-use super::{ReconnectType, Shard, ShardAction, ShardId, ShardManager, ShardStageUpdateEvent};
+use super::{
+    ChunkRequests,
+    CommandRatelimiter,
+    ConnectionStage,
+    HealthTracker,
+    PresenceRotation,
+    ReconnectType,
+    ReshardProgressEvent,
+    Shard,
+    ShardAction,
+    ShardId,
+    ShardManager,
+    ShardMessenger,
+    ShardStageUpdateEvent,
+    ShardUnhealthyEvent,
+    VoiceRequests,
+};
 #[cfg(feature = "cache")]
-use crate::cache::Cache;
+use crate::cache::{cache_update_events, Cache};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::client::dispatch::dispatch_model;
-use crate::gateway::client::{Context, EventHandler, RawEventHandler};
+use crate::gateway::client::dispatch::{dispatch_model, dispatch_to_stream};
+use crate::gateway::client::{Context, EventHandler, EventStreamSender, RawEventHandler};
+use crate::gateway::recording::record_event;
+use crate::gateway::ws::WantedEvents;
 #[cfg(feature = "voice")]
 use crate::gateway::VoiceGatewayManager;
-use crate::gateway::{ActivityData, ChunkGuildFilter, GatewayError};
+use crate::gateway::{
+    ActivityData,
+    ChunkGuildFilter,
+    EventRecorder,
+    EventSinkSender,
+    GatewayError,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
 use crate::model::event::{Event, GatewayEvent};
-use crate::model::id::GuildId;
+use crate::model::gateway::GatewayIntents;
+use crate::model::id::{ChannelId, GuildId};
 use crate::model::user::OnlineStatus;
 
 /// A runner for managing a [`Shard`] and its respective WebSocket client.
@@ -47,12 +75,33 @@ pub struct ShardRunner {
     pub http: Arc<Http>,
     #[cfg(feature = "collector")]
     pub(crate) collectors: Arc<parking_lot::RwLock<Vec<CollectorCallback>>>,
+    pub(crate) chunk_requests: Arc<ChunkRequests>,
+    pub(crate) voice_requests: Arc<VoiceRequests>,
+    // Holds back commands that would exceed the gateway's send ratelimit.
+    command_ratelimiter: CommandRatelimiter,
+    pub(crate) queued_commands: Arc<AtomicUsize>,
+    health: Arc<HealthTracker>,
+    // The presence rotation and its tick whose activity was last applied.
+    rotated_presence: Option<(Arc<PresenceRotation>, u128)>,
+    event_recorder: Option<Arc<EventRecorder>>,
+    event_sink: Option<EventSinkSender>,
+    // The generation of the shard, which only dispatches events while it is the active one.
+    pub(super) generation: u64,
+    active: bool,
+    // The guilds that have yet to be received while the shard is part of a reshard in progress.
+    unloaded_guilds: Option<HashSet<GuildId>>,
+    // The dispatch events to deserialize, or `None` for all of them.
+    wanted_events: Option<WantedEvents>,
+    event_stream: Option<EventStreamSender>,
 }
 
 impl ShardRunner {
     /// Creates a new runner for a Shard.
     pub fn new(opt: ShardRunnerOptions) -> Self {
         let (tx, rx) = mpsc::unbounded();
+        let wanted_events = wanted_events(&opt);
+        let queued_commands = Arc::new(AtomicUsize::new(0));
+        let health = opt.manager.health_tracker(opt.shard.shard_info().id);
 
         Self {
             runner_rx: rx,
@@ -71,6 +120,19 @@ impl ShardRunner {
             http: opt.http,
             #[cfg(feature = "collector")]
             collectors: Arc::new(parking_lot::RwLock::new(vec![])),
+            chunk_requests: Arc::default(),
+            voice_requests: Arc::default(),
+            command_ratelimiter: CommandRatelimiter::new(Arc::clone(&queued_commands)),
+            queued_commands,
+            health,
+            rotated_presence: None,
+            event_recorder: opt.event_recorder,
+            event_sink: opt.event_sink,
+            generation: 0,
+            active: true,
+            unloaded_guilds: None,
+            wanted_events,
+            event_stream: opt.event_stream,
         }
     }
 
@@ -100,13 +162,22 @@ impl ShardRunner {
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     pub async fn run(&mut self) -> Result<()> {
         info!("[ShardRunner {:?}] Running", self.shard.shard_info());
+        self.active = self.manager.is_active_generation(self.generation);
 
         loop {
             trace!("[ShardRunner {:?}] loop iteration started.", self.shard.shard_info());
+            self.update_generation().await;
+
             if !self.recv().await {
                 return Ok(());
             }
 
+            if self.check_health() {
+                self.health.record_unhealthy_restart();
+                self.request_restart().await;
+                return Ok(());
+            }
+
             // check heartbeat
             if !self.shard.do_heartbeat().await {
                 warn!("[ShardRunner {:?}] Error heartbeating", self.shard.shard_info(),);
@@ -119,7 +190,7 @@ impl ShardRunner {
             let (event, action, successful) = self.recv_event().await?;
             let post = self.shard.stage();
 
-            if post != pre {
+            if post != pre && self.active {
                 self.update_manager().await;
 
                 if let Some(event_handler) = &self.event_handler {
@@ -173,7 +244,19 @@ impl ShardRunner {
                 None => {},
             }
 
-            if let Some(event) = event {
+            match &event {
+                Some(Event::Ready(_)) => self.health.record_ready(Instant::now()),
+                Some(Event::Resumed(_)) => self.health.record_resumed(Instant::now()),
+                Some(Event::GuildMembersChunk(chunk)) => self.chunk_requests.deliver(chunk),
+                Some(event @ (Event::VoiceStateUpdate(_) | Event::VoiceServerUpdate(_))) => {
+                    self.voice_requests.deliver(event, self.shard.user_id());
+                },
+                _ => {},
+            }
+
+            if let Some(event) = event.as_ref().filter(|_| !self.active) {
+                self.track_reshard_progress(event);
+            } else if let Some(event) = event {
                 let context = self.make_context();
                 let can_dispatch = self
                     .event_handler
@@ -186,32 +269,35 @@ impl ShardRunner {
 
                 if can_dispatch {
                     #[cfg(feature = "collector")]
-                    {
-                        let read_lock = self.collectors.read();
-                        // search all collectors to be removed and clone the Arcs
-                        let to_remove: Vec<_> = read_lock
-                            .iter()
-                            .filter(|callback| !callback.0(&event))
-                            .cloned()
-                            .collect();
-                        drop(read_lock);
-                        // remove all found arcs from the collection
-                        // this compares the inner pointer of the Arc
-                        if !to_remove.is_empty() {
-                            self.collectors.write().retain(|f| !to_remove.contains(f));
-                        }
-                    }
-                    spawn_named(
-                        "shard_runner::dispatch",
-                        dispatch_model(
+                    run_collectors(&self.collectors, &event);
+                    if let Some(event_stream) = &mut self.event_stream {
+                        dispatch_to_stream(
                             event,
                             context,
                             #[cfg(feature = "framework")]
                             self.framework.clone(),
                             self.event_handler.clone(),
                             self.raw_event_handler.clone(),
-                        ),
-                    );
+                            event_stream,
+                        )
+                        .await;
+                    } else {
+                        let dispatcher = self.manager.dispatcher();
+                        let lane = dispatcher.lane(&event);
+                        dispatcher
+                            .dispatch(
+                                lane,
+                                Box::pin(dispatch_model(
+                                    event,
+                                    context,
+                                    #[cfg(feature = "framework")]
+                                    self.framework.clone(),
+                                    self.event_handler.clone(),
+                                    self.raw_event_handler.clone(),
+                                )),
+                            )
+                            .await;
+                    }
                 }
             }
 
@@ -223,6 +309,106 @@ impl ShardRunner {
         }
     }
 
+    /// Follows the switch to another generation after a reshard, see [`ShardManager::reshard`].
+    ///
+    /// [`ShardManager::reshard`]: super::ShardManager::reshard
+    async fn update_generation(&mut self) {
+        if !self.manager.is_active_generation(self.generation) {
+            self.hand_off();
+        } else if !self.active {
+            self.active = true;
+            self.activate().await;
+        }
+    }
+
+    /// Stops dispatching events once the shard has been replaced by a reshard. Everything it
+    /// received before the switch has been dispatched at this point, so the new shards can take
+    /// over.
+    fn hand_off(&mut self) {
+        if self.active && !self.manager.is_active_generation(self.generation) {
+            debug!("[ShardRunner {:?}] Replaced", self.shard.shard_info());
+
+            self.active = false;
+            self.manager.generation_handed_off(self.generation, self.shard.shard_info().id);
+        }
+    }
+
+    /// Called when the shard has become part of the active generation after a reshard, at which
+    /// point it starts dispatching events.
+    async fn activate(&mut self) {
+        debug!("[ShardRunner {:?}] Activated", self.shard.shard_info());
+
+        self.manager.wait_for_handoff().await;
+
+        self.update_manager().await;
+
+        #[cfg(feature = "voice")]
+        if let Some(voice_manager) = &self.voice_manager {
+            if self.shard.session_id().is_some() {
+                voice_manager
+                    .register_shard(self.shard.shard_info().id.0, self.runner_tx.clone())
+                    .await;
+            }
+        }
+
+        let shard_info = self.shard.shard_info();
+        if shard_info.id.0 == 0 {
+            self.dispatch_reshard_progress(ReshardProgressEvent {
+                shard_total: shard_info.total,
+                shards_ready: shard_info.total.get(),
+                switched: true,
+            });
+        }
+    }
+
+    /// Keeps track of the guilds received by a shard of a reshard in progress, and reports the
+    /// shard as ready once all of them are available.
+    fn track_reshard_progress(&mut self, event: &Event) {
+        match event {
+            Event::Ready(event) => {
+                // Without the `GUILDS` intent, guilds are never sent, so there is nothing to wait
+                // for.
+                let guilds = if self.shard.intents.contains(GatewayIntents::GUILDS) {
+                    event.ready.guilds.iter().map(|guild| guild.id).collect()
+                } else {
+                    HashSet::new()
+                };
+                self.unloaded_guilds = Some(guilds);
+            },
+            Event::GuildCreate(event) => {
+                if let Some(guilds) = &mut self.unloaded_guilds {
+                    guilds.remove(&event.guild.id);
+                }
+            },
+            Event::GuildDelete(event) => {
+                if let Some(guilds) = &mut self.unloaded_guilds {
+                    guilds.remove(&event.guild.id);
+                }
+            },
+            _ => return,
+        }
+
+        if self.unloaded_guilds.as_ref().is_some_and(HashSet::is_empty) {
+            self.unloaded_guilds = None;
+
+            let shard_id = self.shard.shard_info().id;
+            if let Some(event) = self.manager.reshard_shard_ready(self.generation, shard_id) {
+                self.dispatch_reshard_progress(event);
+            }
+        }
+    }
+
+    fn dispatch_reshard_progress(&self, event: ReshardProgressEvent) {
+        if let Some(event_handler) = &self.event_handler {
+            let event_handler = Arc::clone(event_handler);
+            let context = self.make_context();
+
+            spawn_named("dispatch::event_handler::reshard_progress", async move {
+                event_handler.reshard_progress(context, event).await;
+            });
+        }
+    }
+
     /// Clones the internal copy of the Sender to the shard runner.
     pub(super) fn runner_tx(&self) -> Sender<ShardRunnerMessage> {
         self.runner_tx.clone()
@@ -263,6 +449,17 @@ impl ShardRunner {
             return true;
         }
 
+        self.hand_off();
+
+        // Closing with 1000 or 1001 invalidates the session, any other code keeps it resumable.
+        // Events received while waiting for the close below are not dispatched, so the session is
+        // saved before closing to have Discord send them again.
+        if self.active && !matches!(close_code, 1000 | 1001) {
+            if let Some(session) = self.shard.session() {
+                self.manager.store_session(session).await;
+            }
+        }
+
         // Send a Close Frame to Discord, which allows a bot to "log off"
         drop(
             self.shard
@@ -290,15 +487,18 @@ impl ShardRunner {
             }
         }
 
-        // Inform the manager that shutdown for this shard has finished.
-        self.manager.shutdown_finished(id);
+        // Inform the manager that shutdown for this shard has finished. Shards that are not part
+        // of the active generation are unknown to it.
+        if self.active {
+            self.manager.shutdown_finished(id);
+        }
         false
     }
 
     fn make_context(&self) -> Context {
         Context::new(
             Arc::clone(&self.data),
-            self,
+            ShardMessenger::new(self),
             self.shard.shard_info().id,
             Arc::clone(&self.http),
             #[cfg(feature = "cache")]
@@ -349,6 +549,16 @@ impl ShardRunner {
                 self.shard.set_status(status);
                 self.shard.update_presence().await.is_ok()
             },
+            ShardRunnerMessage::UpdateVoiceState {
+                guild_id,
+                channel_id,
+                self_mute,
+                self_deaf,
+            } => self
+                .shard
+                .update_voice_state(guild_id, channel_id, self_mute, self_deaf)
+                .await
+                .is_ok(),
         }
     }
 
@@ -388,6 +598,7 @@ impl ShardRunner {
     async fn recv(&mut self) -> bool {
         loop {
             match self.runner_rx.try_next() {
+                Ok(Some(value)) if value.is_command() => self.command_ratelimiter.push(value),
                 Ok(Some(value)) => {
                     if !self.handle_rx_value(value).await {
                         return false;
@@ -406,15 +617,67 @@ impl ShardRunner {
             }
         }
 
+        self.rotate_presence();
+
+        // Send as many of the commands as the ratelimit allows, leaving the rest for later.
+        self.command_ratelimiter.set_heartbeat_interval(self.shard.heartbeat_interval());
+        while let Some(command) = self.command_ratelimiter.pop(Instant::now()) {
+            if !self.handle_rx_value(command).await {
+                return false;
+            }
+        }
+
         // There are no longer any values available.
         true
     }
 
+    /// Queues the next activity of the manager's presence rotation, once it is due.
+    fn rotate_presence(&mut self) {
+        let Some(rotation) = self.manager.presence_rotation() else {
+            self.rotated_presence = None;
+            return;
+        };
+        if self.shard.stage() != ConnectionStage::Connected {
+            return;
+        }
+
+        let tick = rotation.tick(Instant::now());
+        if let Some((rotated, rotated_tick)) = &self.rotated_presence {
+            if Arc::ptr_eq(rotated, &rotation) && *rotated_tick == tick {
+                return;
+            }
+        }
+
+        let shard_info = self.shard.shard_info();
+        #[cfg_attr(not(feature = "cache"), expect(unused_mut))]
+        let mut placeholders = vec![
+            ("{shard_id}", shard_info.id.to_string()),
+            ("{shard_total}", shard_info.total.to_string()),
+        ];
+        #[cfg(feature = "cache")]
+        placeholders.push(("{guild_count}", self.cache.guild_count().to_string()));
+
+        if let Some(activity) = rotation.activity(tick, &placeholders) {
+            self.command_ratelimiter.push(ShardRunnerMessage::SetActivity(Some(activity)));
+        }
+        self.rotated_presence = Some((rotation, tick));
+    }
+
     /// Returns a received event, as well as whether reading the potentially present event was
     /// successful.
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn recv_event(&mut self) -> Result<(Option<Event>, Option<ShardAction>, bool)> {
-        let gateway_event = match self.shard.client.recv_json().await {
+        #[cfg(feature = "collector")]
+        let collecting = !self.collectors.read().is_empty();
+        #[cfg(not(feature = "collector"))]
+        let collecting = false;
+
+        // Collectors may be waiting for any event, voice channel joins wait for voice updates, and
+        // shards of a reshard in progress keep track of their guilds instead of dispatching.
+        let joining_voice = !self.voice_requests.is_empty();
+        let wanted =
+            self.wanted_events.as_ref().filter(|_| self.active && !collecting && !joining_voice);
+        let gateway_event = match self.shard.client.recv_event(wanted).await {
             Ok(Some(inner)) => Ok(inner),
             Ok(None) => {
                 return Ok((None, None, true));
@@ -441,7 +704,40 @@ impl ShardRunner {
             Err(why) => Err(why),
         };
 
+        // The generation may have been switched while waiting for the event, which then belongs
+        // to the new one.
+        self.update_generation().await;
+
+        if let (
+            Some(recorder),
+            Ok(GatewayEvent::Dispatch {
+                seq,
+                data,
+                ..
+            }),
+        ) = (&self.event_recorder, &gateway_event)
+        {
+            record_event(recorder, self.shard.shard_info().id, *seq, data);
+        }
+        if let (
+            Some(sink),
+            Ok(GatewayEvent::Dispatch {
+                seq,
+                data,
+                original_str,
+            }),
+        ) = (&self.event_sink, &gateway_event)
+        {
+            sink.forward(self.shard.shard_info().id, *seq, data, original_str);
+        }
+
         let is_ack = matches!(gateway_event, Ok(GatewayEvent::HeartbeatAck));
+        if matches!(
+            gateway_event,
+            Ok(GatewayEvent::Dispatch { .. } | GatewayEvent::SkippedDispatch { .. })
+        ) {
+            self.health.record_dispatch(Instant::now());
+        }
         let (action, event) = match self.shard.handle_event(gateway_event) {
             Ok((action, event)) => (action, event),
             Err(Error::Gateway(
@@ -471,11 +767,12 @@ impl ShardRunner {
         };
 
         if is_ack {
+            self.health.record_heartbeat_ack(self.shard.latency(), Instant::now());
             self.update_manager().await;
         }
 
         #[cfg(feature = "voice")]
-        {
+        if self.active {
             if let Some(event) = &event {
                 self.handle_voice_event(event).await;
             }
@@ -484,13 +781,58 @@ impl ShardRunner {
         Ok((event, action, true))
     }
 
+    /// Checks the health of the connection against the thresholds of the manager, notifying the
+    /// event handler once the shard is unhealthy. Returns whether the shard should be restarted.
+    fn check_health(&self) -> bool {
+        let Some(thresholds) = self.manager.health_thresholds() else {
+            return false;
+        };
+        if !self.active || self.shard.stage() != ConnectionStage::Connected {
+            return false;
+        }
+
+        let now = Instant::now();
+        let Some(reason) = self.health.check(&thresholds, now) else {
+            return false;
+        };
+
+        warn!("[ShardRunner {:?}] Shard is unhealthy: {reason:?}", self.shard.shard_info());
+
+        if let Some(event_handler) = &self.event_handler {
+            let event_handler = Arc::clone(event_handler);
+            let context = self.make_context();
+            let shard_id = self.shard.shard_info().id;
+            let event = ShardUnhealthyEvent {
+                shard_id,
+                reason,
+                health: self.health.snapshot(shard_id, self.shard.stage(), now),
+                restarting: thresholds.restart,
+            };
+
+            spawn_named("dispatch::event_handler::shard_unhealthy", async move {
+                event_handler.shard_unhealthy(context, event).await;
+            });
+        }
+
+        thresholds.restart
+    }
+
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn request_restart(&mut self) {
         debug!("[ShardRunner {:?}] Requesting restart", self.shard.shard_info());
 
-        self.update_manager().await;
+        self.hand_off();
 
         let shard_id = self.shard.shard_info().id;
+        if !self.active {
+            // Only shards of the reshard in progress are restarted, the queuer ignores the
+            // request from any other inactive shard.
+            self.manager.restart_reshard_shard(self.generation, shard_id);
+            return;
+        }
+
+        self.update_manager().await;
+
         self.manager.restart_shard(shard_id).await;
 
         #[cfg(feature = "voice")]
@@ -501,6 +843,10 @@ impl ShardRunner {
 
     #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
     async fn update_manager(&self) {
+        if !self.active {
+            return;
+        }
+
         self.manager
             .update_shard_latency_and_stage(
                 self.shard.shard_info().id,
@@ -511,6 +857,62 @@ impl ShardRunner {
     }
 }
 
+/// Collects the names of the dispatch events that are needed by any part of the client, or returns
+/// `None` if all of them are.
+fn wanted_events(opt: &ShardRunnerOptions) -> Option<WantedEvents> {
+    // The framework, event recorder, event stream and event sink receive every event.
+    #[cfg(feature = "framework")]
+    if opt.framework.is_some() {
+        return None;
+    }
+    if opt.event_recorder.is_some() || opt.event_stream.is_some() || opt.event_sink.is_some() {
+        return None;
+    }
+
+    // The shard needs these to keep track of its session, and member chunks are only sent when
+    // requested, possibly by `ShardMessenger::request_members`.
+    let mut wanted: WantedEvents =
+        ["READY", "RESUMED", "GUILD_MEMBERS_CHUNK"].into_iter().map(Box::from).collect();
+
+    // Dispatches are skipped by the names sent by Discord, which differ for some events.
+    if let Some(event_handler) = &opt.event_handler {
+        let names = event_handler.wanted_events()?;
+        wanted.extend(names.iter().map(|name| Box::from(Event::wire_name(name))));
+    }
+    if let Some(raw_event_handler) = &opt.raw_event_handler {
+        let names = raw_event_handler.wanted_events()?;
+        wanted.extend(names.iter().map(|name| Box::from(Event::wire_name(name))));
+    }
+
+    #[cfg(feature = "cache")]
+    wanted.extend(cache_update_events(&opt.cache.settings()).map(Box::from));
+
+    #[cfg(feature = "voice")]
+    if opt.voice_manager.is_some() {
+        wanted.extend(["VOICE_SERVER_UPDATE", "VOICE_STATE_UPDATE"].map(Box::from));
+    }
+
+    Some(wanted)
+}
+
+/// Passes the event to all collectors, removing those that are finished.
+#[cfg(feature = "collector")]
+pub(crate) fn run_collectors(
+    collectors: &parking_lot::RwLock<Vec<CollectorCallback>>,
+    event: &Event,
+) {
+    let read_lock = collectors.read();
+    // search all collectors to be removed and clone the Arcs
+    let to_remove: Vec<_> =
+        read_lock.iter().filter(|callback| !callback.0(event)).cloned().collect();
+    drop(read_lock);
+    // remove all found arcs from the collection
+    // this compares the inner pointer of the Arc
+    if !to_remove.is_empty() {
+        collectors.write().retain(|f| !to_remove.contains(f));
+    }
+}
+
 /// Options to be passed to [`ShardRunner::new`].
 pub struct ShardRunnerOptions {
     pub data: Arc<dyn std::any::Any + Send + Sync>,
@@ -525,6 +927,9 @@ pub struct ShardRunnerOptions {
     #[cfg(feature = "cache")]
     pub cache: Arc<Cache>,
     pub http: Arc<Http>,
+    pub event_recorder: Option<Arc<EventRecorder>>,
+    pub event_sink: Option<EventSinkSender>,
+    pub event_stream: Option<EventStreamSender>,
 }
 
 /// A message to send from a shard over a WebSocket.
@@ -572,4 +977,30 @@ pub enum ShardRunnerMessage {
     SetPresence(Option<ActivityData>, OnlineStatus),
     /// Indicates that the client is to update the shard's presence's status.
     SetStatus(OnlineStatus),
+    /// Indicates that the client is to join, move between or leave voice channels of a guild.
+    UpdateVoiceState {
+        /// The Id of the guild of the voice channel.
+        guild_id: GuildId,
+        /// The voice channel to join, or [`None`] to leave the current one.
+        channel_id: Option<ChannelId>,
+        /// Whether the current user is muted.
+        self_mute: bool,
+        /// Whether the current user is deafened.
+        self_deaf: bool,
+    },
+}
+
+impl ShardRunnerMessage {
+    /// Whether the message results in a payload that counts towards the gateway's send ratelimit.
+    fn is_command(&self) -> bool {
+        matches!(
+            self,
+            Self::ChunkGuild { .. }
+                | Self::Message(_)
+                | Self::SetActivity(_)
+                | Self::SetPresence(..)
+                | Self::SetStatus(_)
+                | Self::UpdateVoiceState { .. }
+        )
+    }
 }
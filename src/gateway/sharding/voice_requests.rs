@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+use crate::internal::prelude::*;
+use crate::model::event::Event;
+use crate::model::id::{ChannelId, GuildId, UserId};
+
+/// The information needed to connect to a voice server, as returned by
+/// [`ShardMessenger::join_voice_channel`].
+///
+/// [`ShardMessenger::join_voice_channel`]: super::ShardMessenger::join_voice_channel
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct VoiceConnectionInfo {
+    /// The Id of the guild of the voice channel.
+    pub guild_id: GuildId,
+    /// The Id of the voice channel that was joined.
+    pub channel_id: ChannelId,
+    /// The Id of the current user's voice session.
+    pub session_id: FixedString,
+    /// The hostname of the voice server to connect to.
+    pub endpoint: FixedString,
+    /// The token to authenticate with at the voice server.
+    pub token: FixedString,
+}
+
+/// The voice channel joins of a shard that are waiting for their voice state and voice server
+/// updates, by guild.
+#[derive(Debug, Default)]
+pub(crate) struct VoiceRequests {
+    pending: Mutex<HashMap<GuildId, PendingJoin>>,
+}
+
+#[derive(Debug)]
+struct PendingJoin {
+    channel_id: ChannelId,
+    session_id: Option<FixedString>,
+    server: Option<(FixedString, FixedString)>,
+    tx: oneshot::Sender<VoiceConnectionInfo>,
+}
+
+impl VoiceRequests {
+    /// Registers a join of the given channel, replacing any other join in the same guild.
+    pub(crate) fn register(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> oneshot::Receiver<VoiceConnectionInfo> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(guild_id, PendingJoin {
+            channel_id,
+            session_id: None,
+            server: None,
+            tx,
+        });
+
+        rx
+    }
+
+    /// Removes the join of the guild if it is no longer being waited for, but not a join that
+    /// replaced it.
+    pub(crate) fn remove_abandoned(&self, guild_id: GuildId) {
+        let mut pending = self.pending.lock();
+        if pending.get(&guild_id).is_some_and(|join| join.tx.is_closed()) {
+            pending.remove(&guild_id);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pending.lock().is_empty()
+    }
+
+    /// Passes a received voice state or voice server update on to the join it belongs to, and
+    /// completes the join once both have been received.
+    pub(crate) fn deliver(&self, event: &Event, user_id: Option<UserId>) {
+        let mut pending = self.pending.lock();
+        let guild_id = match event {
+            Event::VoiceStateUpdate(event) => {
+                let state = &event.voice_state;
+                let Some(guild_id) = state.guild_id.filter(|_| Some(state.user_id) == user_id)
+                else {
+                    return;
+                };
+                let Some(join) = pending.get_mut(&guild_id) else { return };
+                if state.channel_id != Some(join.channel_id) {
+                    return;
+                }
+
+                join.session_id = Some(state.session_id.clone());
+                guild_id
+            },
+            Event::VoiceServerUpdate(event) => {
+                // Without an endpoint, the voice server is unavailable until another update.
+                let Some(endpoint) = &event.endpoint else { return };
+                let Some(join) = pending.get_mut(&event.guild_id) else { return };
+
+                join.server = Some((endpoint.clone(), event.token.clone()));
+                event.guild_id
+            },
+            _ => return,
+        };
+
+        let complete = pending
+            .get(&guild_id)
+            .is_some_and(|join| join.session_id.is_some() && join.server.is_some());
+        if !complete {
+            return;
+        }
+
+        if let Some(PendingJoin {
+            channel_id,
+            session_id: Some(session_id),
+            server: Some((endpoint, token)),
+            tx,
+        }) = pending.remove(&guild_id)
+        {
+            drop(tx.send(VoiceConnectionInfo {
+                guild_id,
+                channel_id,
+                session_id,
+                endpoint,
+                token,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::VoiceRequests;
+    use crate::model::event::Event;
+    use crate::model::id::{ChannelId, GuildId, UserId};
+
+    fn event(kind: &str, data: &serde_json::Value) -> Event {
+        serde_json::from_value(json!({"t": kind, "d": data})).unwrap()
+    }
+
+    fn state_update(user_id: &str, channel_id: &str) -> Event {
+        event(
+            "VOICE_STATE_UPDATE",
+            &json!({
+                "guild_id": "1",
+                "channel_id": channel_id,
+                "user_id": user_id,
+                "session_id": "session",
+                "deaf": false,
+                "mute": false,
+                "self_deaf": false,
+                "self_mute": false,
+                "self_video": false,
+                "suppress": false,
+                "request_to_speak_timestamp": null,
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_voice_join() {
+        let requests = VoiceRequests::default();
+        let mut rx = requests.register(GuildId::new(1), ChannelId::new(2));
+        let user_id = Some(UserId::new(3));
+
+        // Updates of other users and other channels are ignored.
+        requests.deliver(&state_update("4", "2"), user_id);
+        requests.deliver(&state_update("3", "5"), user_id);
+        requests.deliver(
+            &event(
+                "VOICE_SERVER_UPDATE",
+                &json!({"guild_id": "1", "token": "token", "endpoint": "voice.discord.media"}),
+            ),
+            user_id,
+        );
+        assert!(rx.try_recv().is_err());
+
+        requests.deliver(&state_update("3", "2"), user_id);
+        let info = rx.await.unwrap();
+        assert_eq!(info.session_id, "session");
+        assert_eq!(info.endpoint, "voice.discord.media");
+        assert_eq!(info.token, "token");
+        assert!(requests.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::env::consts;
 use std::io::Read;
 use std::time::SystemTime;
@@ -18,11 +19,19 @@ use url::Url;
 #[cfg(feature = "transport_compression_zstd")]
 use zstd_safe::{DStream as ZstdInflater, InBuffer, OutBuffer};
 
-use super::{ActivityData, ChunkGuildFilter, GatewayError, PresenceData, TransportCompression};
+use super::{
+    etf,
+    ActivityData,
+    ChunkGuildFilter,
+    GatewayEncoding,
+    GatewayError,
+    PresenceData,
+    TransportCompression,
+};
 use crate::constants::{self, Opcode};
 use crate::model::event::GatewayEvent;
 use crate::model::gateway::{GatewayIntents, ShardInfo};
-use crate::model::id::{GuildId, UserId};
+use crate::model::id::{ChannelId, GuildId, UserId};
 use crate::{Error, Result};
 
 #[derive(Serialize)]
@@ -72,6 +81,12 @@ enum WebSocketMessageData<'a> {
         token: &'a str,
         seq: u64,
     },
+    VoiceStateUpdate {
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        self_mute: bool,
+        self_deaf: bool,
+    },
 }
 
 #[derive(Serialize)]
@@ -219,15 +234,90 @@ impl From<TransportCompression> for Compression {
     }
 }
 
+/// The names of the dispatch events to decode in full.
+pub(crate) type WantedEvents = HashSet<Box<str>>;
+
+/// The fields of a payload needed to tell whether it is a dispatch that isn't wanted.
+#[derive(Deserialize)]
+struct Header<'a> {
+    op: Opcode,
+    s: Option<u64>,
+    #[serde(borrow)]
+    t: Option<Cow<'a, str>>,
+}
+
+/// Returns a [`GatewayEvent::SkippedDispatch`] if the payload is a dispatch that isn't wanted.
+///
+/// Only the header is decoded, which is much cheaper than building the full payload.
+fn skip_unwanted(header: Header<'_>, wanted: &WantedEvents) -> Option<GatewayEvent> {
+    let (Opcode::Dispatch, Some(seq), Some(kind)) = (header.op, header.s, header.t) else {
+        return None;
+    };
+
+    (!wanted.contains(&*kind)).then(|| GatewayEvent::SkippedDispatch {
+        seq,
+        kind: FixedString::from_str_trunc(&kind),
+    })
+}
+
+fn decode_json(json_bytes: &[u8], wanted: Option<&WantedEvents>) -> Result<GatewayEvent> {
+    if let Some(event) = wanted.and_then(|wanted| {
+        let header = serde_json::from_slice(json_bytes).ok()?;
+        skip_unwanted(header, wanted)
+    }) {
+        return Ok(event);
+    }
+
+    // TODO: Use `String::from_utf8_lossy_owned` when stable.
+    let json_str = || String::from_utf8_lossy(json_bytes);
+    match serde_json::from_slice(json_bytes) {
+        Ok(mut event) => {
+            if let GatewayEvent::Dispatch {
+                original_str, ..
+            } = &mut event
+            {
+                *original_str = FixedString::from_string_trunc(json_str().into_owned());
+            }
+
+            Ok(event)
+        },
+        Err(err) => {
+            debug!("Failing text: {}", json_str());
+            Err(Error::Json(err))
+        },
+    }
+}
+
+/// Like [`decode_json`], but the original text of dispatches is left empty, as there is none.
+fn decode_etf(bytes: &[u8], wanted: Option<&WantedEvents>) -> Result<GatewayEvent> {
+    let term = etf::term_bytes(bytes)?;
+    if let Some(event) = wanted.and_then(|wanted| {
+        let header = etf::from_term_bytes(&term).ok()?;
+        skip_unwanted(header, wanted)
+    }) {
+        return Ok(event);
+    }
+
+    etf::from_term_bytes(&term).inspect_err(|_| {
+        debug!("Failing bytes: {bytes:?}");
+    })
+}
+
 pub struct WsClient {
-    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    // `None` for detached clients, which never connect.
+    stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
     compression: Compression,
+    encoding: GatewayEncoding,
 }
 
 const TIMEOUT: Duration = Duration::from_millis(500);
 
 impl WsClient {
-    pub(crate) async fn connect(url: Url, compression: TransportCompression) -> Result<Self> {
+    pub(crate) async fn connect(
+        url: Url,
+        compression: TransportCompression,
+        encoding: GatewayEncoding,
+    ) -> Result<Self> {
         let config = WebSocketConfig {
             max_message_size: None,
             max_frame_size: None,
@@ -236,20 +326,54 @@ impl WsClient {
         let (stream, _) = connect_async_with_config(url, Some(config), false).await?;
 
         Ok(Self {
-            stream,
+            stream: Some(stream),
             compression: compression.into(),
+            encoding,
         })
     }
 
-    pub(crate) async fn recv_json(&mut self) -> Result<Option<GatewayEvent>> {
-        let message = match timeout(TIMEOUT, self.stream.next()).await {
+    /// Creates a client without a connection, for shards that only replay recorded events. Sending
+    /// fails with [`GatewayError::Closed`], and nothing is ever received.
+    pub(crate) fn detached() -> Self {
+        Self {
+            stream: None,
+            compression: TransportCompression::None.into(),
+            encoding: GatewayEncoding::Json,
+        }
+    }
+
+    fn stream(&mut self) -> Result<&mut WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        self.stream.as_mut().ok_or(Error::Gateway(GatewayError::Closed(None)))
+    }
+
+    /// Receives an event, only decoding dispatches in full if their name is in `wanted`, and
+    /// returning [`GatewayEvent::SkippedDispatch`] for the others.
+    ///
+    /// All dispatches are decoded in full if `wanted` is `None`.
+    pub(crate) async fn recv_event(
+        &mut self,
+        wanted: Option<&WantedEvents>,
+    ) -> Result<Option<GatewayEvent>> {
+        let Some(stream) = &mut self.stream else {
+            return Ok(None);
+        };
+
+        let message = match timeout(TIMEOUT, stream.next()).await {
             Ok(Some(Ok(msg))) => msg,
             Ok(Some(Err(e))) => return Err(e.into()),
             Ok(None) | Err(_) => return Ok(None),
         };
 
-        let json_bytes = match message {
+        let bytes = match message {
             Message::Text(payload) => Cow::Owned(payload.into_bytes()),
+            // With payload compression, ETF payloads are only compressed when they are large.
+            Message::Binary(bytes)
+                if self.encoding == GatewayEncoding::Etf
+                    && matches!(self.compression, Compression::Payload { .. })
+                    && bytes.first() == Some(&etf::VERSION) =>
+            {
+                Cow::Owned(bytes)
+            },
             Message::Binary(bytes) => {
                 let Some(decompressed) = self.compression.inflate(&bytes)? else {
                     return Ok(None);
@@ -263,47 +387,36 @@ impl WsClient {
             _ => return Ok(None),
         };
 
-        // TODO: Use `String::from_utf8_lossy_owned` when stable.
-        let json_str = || String::from_utf8_lossy(&json_bytes);
-        match serde_json::from_slice(&json_bytes) {
-            Ok(mut event) => {
-                if let GatewayEvent::Dispatch {
-                    original_str, ..
-                } = &mut event
-                {
-                    *original_str = FixedString::from_string_trunc(json_str().into_owned());
-                }
-
-                Ok(Some(event))
-            },
-            Err(err) => {
-                debug!("Failing text: {}", json_str());
-                Err(Error::Json(err))
-            },
+        match self.encoding {
+            GatewayEncoding::Json => decode_json(&bytes, wanted).map(Some),
+            GatewayEncoding::Etf => decode_etf(&bytes, wanted).map(Some),
         }
     }
 
-    pub(crate) async fn send_json(&mut self, value: &impl serde::Serialize) -> Result<()> {
-        let message = serde_json::to_string(value).map(Message::Text)?;
+    pub(crate) async fn send_payload(&mut self, value: &impl serde::Serialize) -> Result<()> {
+        let message = match self.encoding {
+            GatewayEncoding::Json => serde_json::to_string(value).map(Message::Text)?,
+            GatewayEncoding::Etf => Message::Binary(etf::to_vec(&serde_json::to_value(value)?)),
+        };
 
-        self.stream.send(message).await?;
+        self.stream()?.send(message).await?;
         Ok(())
     }
 
     /// Delegate to `StreamExt::next`
     pub(crate) async fn next(&mut self) -> Option<std::result::Result<Message, WsError>> {
-        self.stream.next().await
+        self.stream.as_mut()?.next().await
     }
 
     /// Delegate to `SinkExt::send`
     pub(crate) async fn send(&mut self, message: Message) -> Result<()> {
-        self.stream.send(message).await?;
+        self.stream()?.send(message).await?;
         Ok(())
     }
 
     /// Delegate to `WebSocketStream::close`
     pub(crate) async fn close(&mut self, msg: Option<CloseFrame<'_>>) -> Result<()> {
-        self.stream.close(msg).await?;
+        self.stream()?.close(msg).await?;
         Ok(())
     }
 
@@ -325,7 +438,7 @@ impl WsClient {
             ChunkGuildFilter::UserIds(user_ids) => (None, Some(user_ids)),
         };
 
-        self.send_json(&WebSocketMessage {
+        self.send_payload(&WebSocketMessage {
             op: Opcode::RequestGuildMembers,
             d: WebSocketMessageData::ChunkGuild(ChunkGuildMessage {
                 guild_id,
@@ -346,7 +459,7 @@ impl WsClient {
     pub async fn send_heartbeat(&mut self, shard_info: &ShardInfo, seq: Option<u64>) -> Result<()> {
         trace!("[{:?}] Sending heartbeat d: {:?}", shard_info, seq);
 
-        self.send_json(&WebSocketMessage {
+        self.send_payload(&WebSocketMessage {
             op: Opcode::Heartbeat,
             d: WebSocketMessageData::Heartbeat(seq),
         })
@@ -391,7 +504,7 @@ impl WsClient {
             },
         };
 
-        self.send_json(&msg).await
+        self.send_payload(&msg).await
     }
 
     /// # Errors
@@ -408,7 +521,7 @@ impl WsClient {
 
         debug!("[{shard_info:?}] Sending presence update");
 
-        self.send_json(&WebSocketMessage {
+        self.send_payload(&WebSocketMessage {
             op: Opcode::PresenceUpdate,
             d: WebSocketMessageData::PresenceUpdate(PresenceUpdateMessage {
                 afk: false,
@@ -433,7 +546,7 @@ impl WsClient {
     ) -> Result<()> {
         debug!("[{:?}] Sending resume; seq: {}", shard_info, seq);
 
-        self.send_json(&WebSocketMessage {
+        self.send_payload(&WebSocketMessage {
             op: Opcode::Resume,
             d: WebSocketMessageData::Resume {
                 session_id,
@@ -443,4 +556,64 @@ impl WsClient {
         })
         .await
     }
+
+    /// # Errors
+    ///
+    /// Errors if there is a problem with the WS connection.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(self)))]
+    pub async fn send_voice_state_update(
+        &mut self,
+        shard_info: &ShardInfo,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        self_mute: bool,
+        self_deaf: bool,
+    ) -> Result<()> {
+        debug!("[{shard_info:?}] Sending voice state update for guild {guild_id}");
+
+        self.send_payload(&WebSocketMessage {
+            op: Opcode::VoiceStateUpdate,
+            d: WebSocketMessageData::VoiceStateUpdate {
+                guild_id,
+                channel_id,
+                self_mute,
+                self_deaf,
+            },
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_decode_etf_dispatch() {
+        let payload = json!({"op": 0, "s": 5, "t": "RESUMED", "d": {"a": [1, "b"]}});
+        let bytes = etf::to_vec(&payload);
+
+        let GatewayEvent::Dispatch {
+            seq,
+            data,
+            original_str,
+        } = decode_etf(&bytes, None).unwrap()
+        else {
+            panic!("expected a dispatch");
+        };
+        assert_eq!(seq, 5);
+        assert_eq!(Value::Object(data), json!({"t": "RESUMED", "d": {"a": [1, "b"]}}));
+        assert!(original_str.is_empty());
+
+        let wanted = WantedEvents::from([Box::from("READY")]);
+        assert!(matches!(
+            decode_etf(&bytes, Some(&wanted)).unwrap(),
+            GatewayEvent::SkippedDispatch { seq: 5, kind } if kind == "RESUMED"
+        ));
+
+        let bytes = etf::to_vec(&json!({"op": 11}));
+        assert!(matches!(decode_etf(&bytes, Some(&wanted)).unwrap(), GatewayEvent::HeartbeatAck));
+    }
 }
@@ -2,9 +2,12 @@
 
 use std::borrow::Cow;
 use std::cell::Cell;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 use arrayvec::ArrayVec;
+use bytes::Bytes;
 use nonmax::{NonMaxU16, NonMaxU8};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::header::{HeaderMap as Headers, HeaderValue};
@@ -19,8 +22,8 @@ use tracing::{debug, warn};
 
 use super::multipart::{Multipart, MultipartUpload};
 use super::ratelimiting::Ratelimiter;
-use super::request::Request;
-use super::routing::Route;
+use super::request::{Request, RequestTimeout};
+use super::routing::{Route, RouteKind};
 use super::{
     ErrorResponse,
     GuildPagination,
@@ -30,6 +33,7 @@ use super::{
     UserPagination,
 };
 use crate::builder::{CreateAllowedMentions, CreateAttachment};
+use crate::cdn::{CdnUrl, CdnUrlError};
 use crate::constants;
 use crate::internal::prelude::*;
 use crate::model::prelude::*;
@@ -59,8 +63,8 @@ where
 
 /// A builder for the underlying [`Http`] client.
 ///
-/// If you do not need to use a proxy or do not need to disable the rate limiter, you can use
-/// [`Http::new`] instead.
+/// If you do not need to use a proxy, point requests at a different URL, or disable the rate
+/// limiter, you can use [`Http::new`] instead.
 ///
 /// ## Example
 ///
@@ -82,8 +86,14 @@ pub struct HttpBuilder {
     ratelimiter_disabled: bool,
     token: Option<Token>,
     proxy: Option<FixedString<u16>>,
+    base_url: Option<FixedString<u16>>,
+    api_version: Option<u8>,
+    cdn_url: Option<FixedString<u16>>,
     application_id: Option<ApplicationId>,
     default_allowed_mentions: Option<CreateAllowedMentions<'static>>,
+    default_timeout: Option<Duration>,
+    route_timeouts: HashMap<RouteKind, Duration>,
+    timeout_includes_ratelimits: bool,
 }
 
 impl HttpBuilder {
@@ -95,8 +105,14 @@ impl HttpBuilder {
             ratelimiter_disabled: false,
             token: Some(token),
             proxy: None,
+            base_url: None,
+            api_version: None,
+            cdn_url: None,
             application_id: None,
             default_allowed_mentions: None,
+            default_timeout: None,
+            route_timeouts: HashMap::new(),
+            timeout_includes_ratelimits: false,
         }
     }
 
@@ -111,8 +127,14 @@ impl HttpBuilder {
             ratelimiter_disabled: false,
             token: None,
             proxy: None,
+            base_url: None,
+            api_version: None,
+            cdn_url: None,
             application_id: None,
             default_allowed_mentions: None,
+            default_timeout: None,
+            route_timeouts: HashMap::new(),
+            timeout_includes_ratelimits: false,
         }
     }
 
@@ -164,15 +186,43 @@ impl HttpBuilder {
     /// [`twilight-http-proxy`]: https://github.com/twilight-rs/http-proxy
     /// [`HTTP CONNECT`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/CONNECT
     pub fn proxy<'a>(mut self, proxy: impl Into<Cow<'a, str>>) -> Self {
-        let proxy = proxy.into();
-        u16::try_from(proxy.len()).expect("Proxy URL should be less than u16::MAX characters");
+        self.proxy = Some(url_into_fixed(proxy.into(), "Proxy"));
+        self
+    }
 
-        let proxy = match proxy {
-            Cow::Owned(proxy) => FixedString::from_string_trunc(proxy),
-            Cow::Borrowed(proxy) => FixedString::from_str_trunc(proxy),
-        };
+    /// Sets the base URL that Discord HTTP API requests are sent to instead of
+    /// `https://discord.com`, such as a staging environment or a local mock server in tests.
+    ///
+    /// The URL should be in the form of the protocol and hostname, e.g. `http://127.0.0.1:3000`,
+    /// and must serve the API under `/api/v{version}`. This takes priority over [`Self::proxy`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the URL is larger than u16::MAX characters.
+    pub fn base_url<'a>(mut self, base_url: impl Into<Cow<'a, str>>) -> Self {
+        self.base_url = Some(url_into_fixed(base_url.into(), "Base"));
+        self
+    }
 
-        self.proxy = Some(proxy);
+    /// Sets the version of Discord's HTTP API to send requests to. Defaults to `10`.
+    ///
+    /// **Note**: The models in this library are written against the default version, so other
+    /// versions may fail to deserialize.
+    pub fn api_version(mut self, api_version: u8) -> Self {
+        self.api_version = Some(api_version);
+        self
+    }
+
+    /// Sets the base URL of the CDN used for assets instead of `https://cdn.discordapp.com`.
+    ///
+    /// It only applies to this client: use [`Http::build_cdn_url`] to build URLs pointing to it,
+    /// and [`Http::cdn_url`] to rewrite URLs returned by models, such as [`User::avatar_url`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the URL is larger than u16::MAX characters.
+    pub fn cdn_url<'a>(mut self, cdn_url: impl Into<Cow<'a, str>>) -> Self {
+        self.cdn_url = Some(url_into_fixed(cdn_url.into(), "CDN"));
         self
     }
 
@@ -188,6 +238,39 @@ impl HttpBuilder {
         self
     }
 
+    /// Sets the timeout used for requests to routes which have no timeout set through
+    /// [`Self::route_timeout`]. By default, requests only time out if the [`reqwest::Client`] was
+    /// configured to do so.
+    ///
+    /// When a request times out, [`HttpError::Timeout`] is returned.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout used for all requests to the given kind of route, taking priority over
+    /// [`Self::default_timeout`].
+    ///
+    /// This is useful for endpoints which legitimately take long to respond, such as bulk bans,
+    /// guild prunes or large uploads. A timeout set on an individual request through
+    /// [`Request::timeout`] takes priority over both.
+    pub fn route_timeout(mut self, route: RouteKind, timeout: Duration) -> Self {
+        self.route_timeouts.insert(route, timeout);
+        self
+    }
+
+    /// Sets whether time spent waiting for ratelimits counts towards a request's timeout. By
+    /// default, it does not, and only the time spent on the HTTP exchange itself is limited. In
+    /// that case, the timeout applies to each attempt separately, and starts over when a request
+    /// is retried after a 429 response, so the total time spent on a request is not bounded.
+    ///
+    /// **Note**: When enabled, a request that is delayed by a long ratelimit may time out without
+    /// ever being sent.
+    pub fn timeout_includes_ratelimits(mut self, timeout_includes_ratelimits: bool) -> Self {
+        self.timeout_includes_ratelimits = timeout_includes_ratelimits;
+        self
+    }
+
     /// Use the given configuration to build the `Http` client.
     #[must_use]
     pub fn build(self) -> Http {
@@ -199,21 +282,49 @@ impl HttpBuilder {
             builder.build().expect("Cannot build reqwest::Client")
         });
 
+        let base_url = self.base_url.as_deref().or(self.proxy.as_deref());
+        let api_url = (base_url.is_some() || self.api_version.is_some()).then(|| {
+            let base_url = base_url.unwrap_or("https://discord.com").trim_end_matches('/');
+            let api_version = self.api_version.unwrap_or(constants::API_VERSION);
+            FixedString::from_string_trunc(format!("{base_url}/api/v{api_version}"))
+        });
+
         let ratelimiter = (!self.ratelimiter_disabled).then(|| {
-            self.ratelimiter.unwrap_or_else(|| Ratelimiter::new(client.clone(), self.token.clone()))
+            let mut ratelimiter = self
+                .ratelimiter
+                .unwrap_or_else(|| Ratelimiter::new(client.clone(), self.token.clone()));
+            ratelimiter.api_url.clone_from(&api_url);
+            ratelimiter
         });
 
+        let cdn_url =
+            self.cdn_url.map(|url| FixedString::from_str_trunc(url.trim_end_matches('/')));
+
         Http {
             client,
             ratelimiter,
             proxy: self.proxy,
+            api_url,
+            cdn_url,
             token: self.token,
             application_id,
             default_allowed_mentions: self.default_allowed_mentions,
+            default_timeout: self.default_timeout,
+            route_timeouts: self.route_timeouts,
+            timeout_includes_ratelimits: self.timeout_includes_ratelimits,
         }
     }
 }
 
+fn url_into_fixed(url: Cow<'_, str>, kind: &str) -> FixedString<u16> {
+    assert!(u16::try_from(url.len()).is_ok(), "{kind} URL should be less than u16::MAX characters");
+
+    match url {
+        Cow::Owned(url) => FixedString::from_string_trunc(url),
+        Cow::Borrowed(url) => FixedString::from_str_trunc(url),
+    }
+}
+
 fn reason_into_header(reason: &str) -> Headers {
     let mut headers = Headers::new();
 
@@ -238,9 +349,14 @@ pub struct Http {
     pub(crate) client: Client,
     pub ratelimiter: Option<Ratelimiter>,
     pub proxy: Option<FixedString<u16>>,
+    api_url: Option<FixedString<u16>>,
+    cdn_url: Option<FixedString<u16>>,
     token: Option<Token>,
     application_id: AtomicU64,
     pub default_allowed_mentions: Option<CreateAllowedMentions<'static>>,
+    default_timeout: Option<Duration>,
+    route_timeouts: HashMap<RouteKind, Duration>,
+    timeout_includes_ratelimits: bool,
 }
 
 impl Http {
@@ -276,6 +392,52 @@ impl Http {
         self.application_id.store(application_id.get(), Ordering::Relaxed);
     }
 
+    /// Returns the URL that HTTP API requests are sent to, including the API version.
+    ///
+    /// This is [`constants::API_URL`] unless configured otherwise through
+    /// [`HttpBuilder::base_url`], [`HttpBuilder::proxy`] or [`HttpBuilder::api_version`].
+    #[must_use]
+    pub fn api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(constants::API_URL)
+    }
+
+    /// Rewrites a URL pointing to Discord's CDN to use the CDN URL set through
+    /// [`HttpBuilder::cdn_url`]. URLs pointing elsewhere are returned unchanged.
+    #[must_use]
+    pub fn cdn_url<'a>(&self, url: &'a str) -> Cow<'a, str> {
+        match (&self.cdn_url, url.strip_prefix(constants::CDN_URL)) {
+            (Some(cdn_url), Some(path)) => Cow::Owned(format!("{cdn_url}{path}")),
+            _ => Cow::Borrowed(url),
+        }
+    }
+
+    /// Builds the URL of an asset, pointing to the CDN URL set through [`HttpBuilder::cdn_url`] if
+    /// any, or to [`constants::CDN_URL`] otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`CdnUrlError`] if the asset is not available in the URL's format, or its size is
+    /// invalid.
+    pub fn build_cdn_url(&self, url: &CdnUrl) -> StdResult<String, CdnUrlError> {
+        url.build_with_base_url(self.cdn_url.as_deref().unwrap_or(constants::CDN_URL))
+    }
+
+    /// Returns the timeout that applies to the given request, if any.
+    ///
+    /// A timeout set through [`Request::timeout`] takes priority over one set for the request's
+    /// route through [`HttpBuilder::route_timeout`], which in turn takes priority over
+    /// [`HttpBuilder::default_timeout`].
+    #[must_use]
+    pub fn timeout_for(&self, req: &Request<'_>) -> Option<Duration> {
+        match req.timeout {
+            RequestTimeout::Inherit => {
+                self.route_timeouts.get(&req.route.kind()).copied().or(self.default_timeout)
+            },
+            RequestTimeout::Disabled => None,
+            RequestTimeout::After(timeout) => Some(timeout),
+        }
+    }
+
     /// Adds a [`User`] to a [`Guild`] with a valid OAuth2 access token.
     ///
     /// Returns the created [`Member`] object, or nothing if the user is already a guild member.
@@ -298,6 +460,7 @@ impl Http {
                     user_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -327,6 +490,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -355,6 +519,7 @@ impl Http {
                 user_id,
             },
             params: Some(&[("delete_message_seconds", &delete_message_seconds.to_arraystring())]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -378,6 +543,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -393,6 +559,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -415,6 +582,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -432,6 +600,7 @@ impl Http {
             method: LightMethod::Post,
             route: Route::StageInstances,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -456,6 +625,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -478,6 +648,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -503,6 +674,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -523,6 +695,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -542,6 +715,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -565,6 +739,7 @@ impl Http {
                 token: interaction_token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if files.is_empty() {
@@ -591,6 +766,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -609,6 +785,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -629,6 +806,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -647,6 +825,7 @@ impl Http {
             method: LightMethod::Post,
             route: Route::Guilds,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -669,6 +848,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -691,6 +871,7 @@ impl Http {
                 integration_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -713,6 +894,7 @@ impl Http {
                 token: interaction_token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if files.is_empty() {
@@ -746,6 +928,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -770,6 +953,7 @@ impl Http {
                 target_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -788,6 +972,7 @@ impl Http {
             method: LightMethod::Post,
             route: Route::UserMeDmChannels,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -810,6 +995,7 @@ impl Http {
                 reaction: &reaction_type.as_data(),
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -830,6 +1016,7 @@ impl Http {
                     guild_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -857,6 +1044,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -882,6 +1070,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -921,6 +1110,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -943,6 +1133,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -962,6 +1153,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -981,6 +1173,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1002,6 +1195,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1018,6 +1212,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1039,6 +1234,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1055,6 +1251,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1070,6 +1267,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1091,6 +1289,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1112,6 +1311,7 @@ impl Http {
                 integration_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1131,6 +1331,7 @@ impl Http {
                 code,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1152,6 +1353,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1172,6 +1374,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1192,6 +1395,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1214,6 +1418,7 @@ impl Http {
                 reaction: &reaction_type.as_data(),
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1233,6 +1438,7 @@ impl Http {
                 token: interaction_token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1254,6 +1460,7 @@ impl Http {
                 target_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1278,6 +1485,7 @@ impl Http {
                 reaction: &reaction_type.as_data(),
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1300,6 +1508,7 @@ impl Http {
                 reaction: &reaction_type.as_data(),
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1321,6 +1530,7 @@ impl Http {
                 role_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1341,6 +1551,7 @@ impl Http {
                 event_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1362,6 +1573,7 @@ impl Http {
                 sticker_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1379,6 +1591,7 @@ impl Http {
                 entitlement_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1398,6 +1611,7 @@ impl Http {
                 webhook_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1421,6 +1635,7 @@ impl Http {
                 token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1443,6 +1658,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1463,6 +1679,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1487,6 +1704,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1511,6 +1729,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1534,6 +1753,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if new_attachments.is_empty() {
@@ -1566,6 +1786,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1586,6 +1807,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1608,6 +1830,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1630,6 +1853,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1652,6 +1876,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1673,6 +1898,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1700,6 +1926,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
         .map(|mfa: GuildMfaLevel| mfa.level)
@@ -1723,6 +1950,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1745,6 +1973,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1770,6 +1999,7 @@ impl Http {
                     user_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -1800,6 +2030,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if new_attachments.is_empty() {
@@ -1833,6 +2064,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1855,6 +2087,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1875,6 +2108,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1894,6 +2128,7 @@ impl Http {
                 channel_id: news_channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1913,6 +2148,7 @@ impl Http {
                 token: interaction_token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1934,6 +2170,7 @@ impl Http {
                 token: interaction_token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if new_attachments.is_empty() {
@@ -1960,6 +2197,7 @@ impl Http {
             method: LightMethod::Patch,
             route: Route::UserMe,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -1983,6 +2221,7 @@ impl Http {
                     role_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -2012,6 +2251,7 @@ impl Http {
                     guild_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -2049,6 +2289,7 @@ impl Http {
                 event_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2076,6 +2317,7 @@ impl Http {
                     sticker_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -2102,6 +2344,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2123,6 +2366,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2142,6 +2386,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2164,6 +2409,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2184,6 +2430,7 @@ impl Http {
                 webhook_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2208,6 +2455,7 @@ impl Http {
                 token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2242,6 +2490,7 @@ impl Http {
                 token,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         };
 
         if files.is_empty() {
@@ -2286,6 +2535,7 @@ impl Http {
                 message_id,
             },
             params: params.as_ref().map(<[_; 1]>::as_slice),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2319,6 +2569,7 @@ impl Http {
                 message_id,
             },
             params: params.as_ref().map(<[_; 1]>::as_slice),
+            timeout: RequestTimeout::Inherit,
         };
 
         if new_attachments.is_empty() {
@@ -2361,6 +2612,7 @@ impl Http {
                 message_id,
             },
             params: params.as_ref().map(<[_; 1]>::as_slice),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2383,6 +2635,7 @@ impl Http {
                 method: LightMethod::Get,
                 route: Route::StatusMaintenancesActive,
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -2433,6 +2686,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2474,6 +2728,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2489,6 +2744,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2509,6 +2765,7 @@ impl Http {
                 rule_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2531,6 +2788,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2555,6 +2813,7 @@ impl Http {
                 rule_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2576,6 +2835,7 @@ impl Http {
                 rule_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2589,6 +2849,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::GatewayBot,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2604,6 +2865,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2622,6 +2884,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2637,6 +2900,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2668,6 +2932,7 @@ impl Http {
                 channel_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2699,6 +2964,7 @@ impl Http {
                 channel_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2730,6 +2996,7 @@ impl Http {
                 channel_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2745,6 +3012,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2760,6 +3028,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2780,6 +3049,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2800,6 +3070,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2820,6 +3091,7 @@ impl Http {
                 user_id,
             },
             params: Some(&[("with_member", &with_member.to_arraystring())]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2835,6 +3107,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2850,6 +3123,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2868,6 +3142,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2883,6 +3158,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2925,6 +3201,7 @@ impl Http {
                     answer_id,
                 },
                 params: Some(&params),
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -2946,6 +3223,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2961,6 +3239,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::OAuth2ApplicationCurrent,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2981,6 +3260,7 @@ impl Http {
             method: LightMethod::Patch,
             route: Route::OAuth2ApplicationCurrent,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -2994,6 +3274,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::UserMe,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3009,6 +3290,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3025,6 +3307,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3047,6 +3330,7 @@ impl Http {
                     application_id: self.try_application_id()?,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3065,6 +3349,7 @@ impl Http {
                 emoji_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3121,6 +3406,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3134,6 +3420,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::Gateway,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3149,6 +3436,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3164,6 +3452,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: Some(&[("with_localizations", "true")]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3180,6 +3469,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3195,6 +3485,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3210,6 +3501,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&[("with_counts", "true")]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3226,6 +3518,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3246,6 +3539,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&[("with_localizations", "true")]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3267,6 +3561,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3286,6 +3581,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3307,6 +3603,7 @@ impl Http {
                 command_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3322,6 +3619,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3337,6 +3635,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3352,6 +3651,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3367,6 +3667,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3382,6 +3683,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3402,6 +3704,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
         .map(|x: GuildVanityUrl| x.code)
@@ -3436,6 +3739,7 @@ impl Http {
                     guild_id,
                 },
                 params: Some(&params),
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3462,6 +3766,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&[("days", &days_str)]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3478,6 +3783,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3495,6 +3801,7 @@ impl Http {
                     role_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3517,6 +3824,7 @@ impl Http {
                     guild_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3549,6 +3857,7 @@ impl Http {
                 event_id,
             },
             params: Some(&[("with_user_count", &with_user_count_str)]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3569,6 +3878,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&[("with_user_count", &with_user_count_str)]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3613,6 +3923,7 @@ impl Http {
                 event_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3629,6 +3940,7 @@ impl Http {
                     guild_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3660,6 +3972,7 @@ impl Http {
                     sticker_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3681,6 +3994,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3714,6 +4028,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::UserMeGuilds,
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3734,6 +4049,7 @@ impl Http {
                     guild_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3778,6 +4094,7 @@ impl Http {
                 code,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3795,6 +4112,7 @@ impl Http {
                     user_id,
                 },
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -3821,6 +4139,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3860,6 +4179,7 @@ impl Http {
                 channel_id,
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3875,6 +4195,7 @@ impl Http {
                 sticker_pack_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3893,6 +4214,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::StickerPacks,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
         .map(|s: StickerPacks| s.sticker_packs)
@@ -3909,6 +4231,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3944,6 +4267,7 @@ impl Http {
                 reaction: &reaction_type.as_data(),
             },
             params: Some(&params),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3959,6 +4283,7 @@ impl Http {
                 application_id: self.try_application_id()?,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3974,6 +4299,7 @@ impl Http {
                 sticker_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -3996,6 +4322,7 @@ impl Http {
                 method: LightMethod::Get,
                 route: Route::StatusIncidentsUnresolved,
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -4020,6 +4347,7 @@ impl Http {
                 method: LightMethod::Get,
                 route: Route::StatusMaintenancesUpcoming,
                 params: None,
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -4037,6 +4365,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4054,6 +4383,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::UserMeConnections,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4067,6 +4397,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::UserMeDmChannels,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4080,6 +4411,7 @@ impl Http {
             method: LightMethod::Get,
             route: Route::VoiceRegions,
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4098,6 +4430,7 @@ impl Http {
                 webhook_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4120,6 +4453,7 @@ impl Http {
                 token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4142,6 +4476,7 @@ impl Http {
                 token,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4163,6 +4498,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4178,6 +4514,7 @@ impl Http {
                 guild_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4198,6 +4535,7 @@ impl Http {
                 channel_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         };
 
         if files.is_empty() {
@@ -4230,6 +4568,7 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4251,6 +4590,7 @@ impl Http {
                 user_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4274,6 +4614,7 @@ impl Http {
                 role_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4297,6 +4638,7 @@ impl Http {
                     guild_id,
                 },
                 params: Some(&[("query", query), ("limit", &limit_str)]),
+                timeout: RequestTimeout::Inherit,
             })
             .await?;
 
@@ -4328,6 +4670,7 @@ impl Http {
                 guild_id,
             },
             params: Some(&[("days", &days_str)]),
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4348,6 +4691,7 @@ impl Http {
                 integration_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
@@ -4369,10 +4713,32 @@ impl Http {
                 message_id,
             },
             params: None,
+            timeout: RequestTimeout::Inherit,
         })
         .await
     }
 
+    /// Downloads an asset from Discord's CDN, such as a URL returned by [`User::avatar_url`] or
+    /// built through [`CdnUrl`].
+    ///
+    /// The URL is rewritten to use the CDN URL set through [`HttpBuilder::cdn_url`], if any. The
+    /// request is not authorized and does not go through the ratelimiter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Http`] if the request fails or the CDN responds with an unsuccessful
+    /// status code.
+    pub async fn download_asset(&self, url: &str) -> Result<Bytes> {
+        let response = self.client.get(self.cdn_url(url).as_ref()).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::Http(HttpError::UnsuccessfulRequest(
+                ErrorResponse::from_response(response, reqwest::Method::GET).await,
+            )));
+        }
+
+        Ok(response.bytes().await?)
+    }
+
     /// Fires off a request, deserializing the response reader via the given type bound.
     ///
     /// If you don't need to deserialize the response and want the response instance itself, use
@@ -4392,19 +4758,23 @@ impl Http {
     /// Returns the raw reqwest Response. Use [`Self::fire`] to deserialize the response into some
     /// type.
     #[cfg_attr(feature = "tracing_instrument", instrument)]
-    pub async fn request(&self, req: Request<'_>) -> Result<ReqwestResponse> {
+    pub async fn request(&self, mut req: Request<'_>) -> Result<ReqwestResponse> {
         let method = req.method.reqwest_method();
-        let response = if let Some(ratelimiter) = &self.ratelimiter {
-            ratelimiter.perform(req).await?
-        } else {
-            let request = req
-                .build(
-                    &self.client,
-                    self.token.as_ref().map(Token::expose_secret),
-                    self.proxy.as_deref(),
-                )?
-                .build()?;
-            self.client.execute(request).await?
+        let timeout = self.timeout_for(&req);
+        req.timeout = timeout.map_or(RequestTimeout::Disabled, RequestTimeout::After);
+
+        let response = match (timeout, self.timeout_includes_ratelimits) {
+            (Some(timeout), true) => tokio::time::timeout(timeout, self.perform(req))
+                .await
+                .map_err(|_| HttpError::Timeout(timeout))?,
+            _ => self.perform(req).await,
+        };
+
+        let response = match (response, timeout) {
+            (Err(Error::Http(HttpError::Request(e))), Some(timeout)) if e.is_timeout() => {
+                return Err(Error::Http(HttpError::Timeout(timeout)));
+            },
+            (response, _) => response?,
         };
 
         if response.status().is_success() {
@@ -4416,6 +4786,21 @@ impl Http {
         }
     }
 
+    async fn perform(&self, req: Request<'_>) -> Result<ReqwestResponse> {
+        if let Some(ratelimiter) = &self.ratelimiter {
+            ratelimiter.perform(req).await
+        } else {
+            let request = req
+                .build_with_api_url(
+                    &self.client,
+                    self.token.as_ref().map(Token::expose_secret),
+                    self.api_url.as_deref(),
+                )?
+                .build()?;
+            Ok(self.client.execute(request).await?)
+        }
+    }
+
     /// Performs a request and verifies that Discord responds with [`StatusCode::NO_CONTENT`].
     ///
     /// This is a function that performs a light amount of work and returns the unit type, so it's
@@ -4451,3 +4836,143 @@ fn configure_client_backend(builder: ClientBuilder) -> ClientBuilder {
 fn configure_client_backend(builder: ClientBuilder) -> ClientBuilder {
     builder.use_native_tls()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::cdn::Asset;
+
+    const TOKEN: &str = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GHIJKL.MNOPQRSTUVWXYZabcdefghijklmnopqrstuv";
+
+    #[test]
+    fn test_timeout_precedence() {
+        let http = HttpBuilder::new(TOKEN.parse().unwrap())
+            .default_timeout(Duration::from_secs(30))
+            .route_timeout(RouteKind::GatewayBot, Duration::from_secs(5))
+            .build();
+
+        let request = Request::new(Route::Gateway, LightMethod::Get);
+        assert_eq!(http.timeout_for(&request), Some(Duration::from_secs(30)));
+
+        let request = Request::new(Route::GatewayBot, LightMethod::Get);
+        assert_eq!(http.timeout_for(&request), Some(Duration::from_secs(5)));
+
+        let request = request.timeout(Duration::from_secs(1));
+        assert_eq!(http.timeout_for(&request), Some(Duration::from_secs(1)));
+
+        let request = request.timeout(RequestTimeout::Disabled);
+        assert_eq!(http.timeout_for(&request), None);
+
+        let request = request.timeout(RequestTimeout::Inherit);
+        assert_eq!(http.timeout_for(&request), Some(Duration::from_secs(5)));
+
+        let http = HttpBuilder::new(TOKEN.parse().unwrap()).build();
+        let request = Request::new(Route::Gateway, LightMethod::Get);
+        assert_eq!(http.timeout_for(&request), None);
+    }
+
+    #[test]
+    fn test_api_url() {
+        assert_eq!(
+            constants::API_URL,
+            format!("https://discord.com/api/v{}", constants::API_VERSION)
+        );
+        assert_eq!(api!("/gateway"), "https://discord.com/api/v10/gateway");
+
+        let http = HttpBuilder::new(TOKEN.parse().unwrap()).build();
+        assert_eq!(http.api_url(), constants::API_URL);
+
+        let http = HttpBuilder::new(TOKEN.parse().unwrap()).api_version(9).build();
+        assert_eq!(http.api_url(), "https://discord.com/api/v9");
+
+        let http = HttpBuilder::new(TOKEN.parse().unwrap()).proxy("http://proxy:8080/").build();
+        assert_eq!(http.api_url(), "http://proxy:8080/api/v10");
+
+        let http = HttpBuilder::new(TOKEN.parse().unwrap())
+            .proxy("http://proxy:8080")
+            .base_url("http://127.0.0.1:3000/")
+            .api_version(11)
+            .build();
+        assert_eq!(http.api_url(), "http://127.0.0.1:3000/api/v11");
+
+        let client = Client::new();
+        let url = |api_url| {
+            let request = Request::new(Route::GatewayBot, LightMethod::Get);
+            let request = request.build_with_api_url(&client, None, api_url).unwrap();
+            request.build().unwrap().url().to_string()
+        };
+        assert_eq!(url(None), "https://discord.com/api/v10/gateway/bot");
+        assert_eq!(url(Some(http.api_url())), "http://127.0.0.1:3000/api/v11/gateway/bot");
+
+        let request = Request::new(Route::GatewayBot, LightMethod::Get);
+        let request = request.build(&client, None, Some("http://proxy:8080/")).unwrap();
+        assert_eq!(
+            request.build().unwrap().url().as_str(),
+            "http://proxy:8080/api/v10/gateway/bot"
+        );
+    }
+
+    #[test]
+    fn test_cdn_url() {
+        let icon = "https://cdn.discordapp.com/icons/1/a_e3c0db7f38777778fb43081f8746ebc9.gif";
+        let http = HttpBuilder::new(TOKEN.parse().unwrap()).build();
+        assert_eq!(http.cdn_url(icon), icon);
+
+        let asset = Asset::GuildIcon {
+            guild_id: GuildId::new(1),
+            hash: "a_e3c0db7f38777778fb43081f8746ebc9".parse().unwrap(),
+        };
+        assert_eq!(http.build_cdn_url(&CdnUrl::new(asset)).unwrap(), icon);
+
+        let http =
+            HttpBuilder::new(TOKEN.parse().unwrap()).cdn_url("http://127.0.0.1:3000/").build();
+        assert_eq!(
+            http.build_cdn_url(&CdnUrl::new(asset)).unwrap(),
+            "http://127.0.0.1:3000/icons/1/a_e3c0db7f38777778fb43081f8746ebc9.gif"
+        );
+        // Other clients and model methods are not affected.
+        assert_eq!(CdnUrl::new(asset).build().unwrap(), icon);
+        assert_eq!(
+            http.cdn_url(icon),
+            "http://127.0.0.1:3000/icons/1/a_e3c0db7f38777778fb43081f8746ebc9.gif"
+        );
+        assert_eq!(http.cdn_url("https://example.com/image.png"), "https://example.com/image.png");
+    }
+
+    /// Returns the URL of a server that accepts connections but never responds. The server runs
+    /// on the test's runtime, so it stops along with the test.
+    async fn unresponsive_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let timeout = Duration::from_millis(100);
+        for includes_ratelimits in [false, true] {
+            let http = HttpBuilder::new(TOKEN.parse().unwrap())
+                .base_url(unresponsive_server().await)
+                .route_timeout(RouteKind::Gateway, timeout)
+                .timeout_includes_ratelimits(includes_ratelimits)
+                .build();
+
+            let request = Request::new(Route::Gateway, LightMethod::Get);
+            let result = http.request(request).await;
+            assert!(
+                matches!(result, Err(Error::Http(HttpError::Timeout(t))) if t == timeout),
+                "includes_ratelimits: {includes_ratelimits}, result: {result:?}",
+            );
+        }
+    }
+}
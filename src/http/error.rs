@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::error::Error as StdError;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use reqwest::header::InvalidHeaderValue;
 use reqwest::{Error as ReqwestError, Method, Response, StatusCode};
@@ -245,6 +246,22 @@ enum_number! {
     }
 }
 
+impl JsonErrorCode {
+    /// Returns true if the code signals that a requested resource does not exist, such as
+    /// [`Self::UnknownChannel`] or [`Self::UnknownMessage`].
+    #[must_use]
+    pub fn is_unknown_resource(&self) -> bool {
+        (10001..20000).contains(&self.0)
+    }
+
+    /// Returns true if the code signals that the current user lacks the permissions or access
+    /// required for an action.
+    #[must_use]
+    pub fn is_missing_permissions(&self) -> bool {
+        *self == Self::MissingAccess || *self == Self::LackPermissionsForAction
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 #[non_exhaustive]
 pub struct DiscordJsonError {
@@ -257,6 +274,30 @@ pub struct DiscordJsonError {
     pub errors: FixedArray<DiscordJsonSingleError>,
 }
 
+impl DiscordJsonError {
+    /// Returns the first error with exactly the given path, if any.
+    ///
+    /// The path may be given in either Discord's dot separated form (`embeds.0.fields.3.value`)
+    /// or with indices in brackets (`embeds[0].fields[3].value`).
+    #[must_use]
+    pub fn error_at(&self, path: &str) -> Option<&DiscordJsonSingleError> {
+        let path = ErrorPath::parse(path);
+        self.errors.iter().find(|e| e.error_path() == path)
+    }
+
+    /// Returns an iterator over all errors whose path starts with the given prefix.
+    ///
+    /// For example, a prefix of `embeds[0]` yields all errors in the first embed, including its
+    /// fields.
+    pub fn errors_under<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a DiscordJsonSingleError> + 'a {
+        let prefix = ErrorPath::parse(prefix);
+        self.errors.iter().filter(move |e| e.error_path().starts_with(&prefix))
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct RawDiscordJsonSingleError {
     code: FixedString<u8>,
@@ -274,6 +315,156 @@ pub struct DiscordJsonSingleError {
     pub path: Arc<str>,
 }
 
+impl DiscordJsonSingleError {
+    /// Returns the typed representation of [`Self::path`].
+    #[must_use]
+    pub fn error_path(&self) -> ErrorPath<'_> {
+        ErrorPath::parse(&self.path)
+    }
+}
+
+/// A single segment of an [`ErrorPath`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorPathSegment<'a> {
+    /// A named field of a JSON object, such as `embeds`.
+    Field(&'a str),
+    /// An index into a JSON array.
+    Index(usize),
+}
+
+/// The location of an error in a request body, as reported by Discord.
+///
+/// Displays with indices in brackets, e.g. `embeds[0].fields[3].value`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ErrorPath<'a> {
+    pub segments: Vec<ErrorPathSegment<'a>>,
+}
+
+impl<'a> ErrorPath<'a> {
+    /// Parses a path in either Discord's dot separated form or with indices in brackets.
+    #[must_use]
+    pub fn parse(path: &'a str) -> Self {
+        let segments = path
+            .split(['.', '[', ']'])
+            .filter(|s| !s.is_empty())
+            .map(|s| match s.parse() {
+                Ok(index) => ErrorPathSegment::Index(index),
+                Err(_) => ErrorPathSegment::Field(s),
+            })
+            .collect();
+
+        Self {
+            segments,
+        }
+    }
+
+    /// Returns true if this path is equal to or nested within the given path.
+    #[must_use]
+    pub fn starts_with(&self, prefix: &ErrorPath<'_>) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+
+    /// Returns the part of the request which the path points into, corresponding to the builder
+    /// that produced it.
+    ///
+    /// Paths inside interaction response `data` are treated the same as their top-level message
+    /// counterparts.
+    #[must_use]
+    pub fn origin(&self) -> ErrorOrigin {
+        use ErrorPathSegment::{Field, Index};
+
+        let segments = match self.segments.as_slice() {
+            [Field("data"), rest @ ..] => rest,
+            segments => segments,
+        };
+
+        match segments {
+            [Field("content"), ..] => ErrorOrigin::Content,
+            [Field("embeds"), Index(index), Field("fields"), Index(field), ..] => {
+                ErrorOrigin::Embed {
+                    index: *index,
+                    field: Some(*field),
+                }
+            },
+            [Field("embeds"), Index(index), ..] => ErrorOrigin::Embed {
+                index: *index,
+                field: None,
+            },
+            [Field("components"), Index(row), Field("components"), Index(component), ..] => {
+                ErrorOrigin::Component {
+                    row: *row,
+                    component: Some(*component),
+                }
+            },
+            [Field("components"), Index(row), ..] => ErrorOrigin::Component {
+                row: *row,
+                component: None,
+            },
+            [Field("attachments" | "files"), Index(index), ..] => ErrorOrigin::Attachment {
+                index: *index,
+            },
+            [Field("poll"), Field("answers"), Index(answer), ..] => ErrorOrigin::Poll {
+                answer: Some(*answer),
+            },
+            [Field("poll"), ..] => ErrorOrigin::Poll {
+                answer: None,
+            },
+            [Field("allowed_mentions"), ..] => ErrorOrigin::AllowedMentions,
+            _ => ErrorOrigin::Other,
+        }
+    }
+}
+
+impl fmt::Display for ErrorPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                ErrorPathSegment::Field(field) if i == 0 => f.write_str(field)?,
+                ErrorPathSegment::Field(field) => write!(f, ".{field}")?,
+                ErrorPathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The part of a request that an [`ErrorPath`] points into, named after the builder that
+/// produces it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorOrigin {
+    /// The message content, as set by e.g. [`CreateMessage::content`].
+    ///
+    /// [`CreateMessage::content`]: crate::builder::CreateMessage::content
+    Content,
+    /// An embed built by a [`CreateEmbed`], and the index of the field in it if the error is in
+    /// one of its fields.
+    ///
+    /// [`CreateEmbed`]: crate::builder::CreateEmbed
+    Embed { index: usize, field: Option<usize> },
+    /// An action row built by a [`CreateActionRow`], and the index of the component in it if the
+    /// error is in one of its components.
+    ///
+    /// [`CreateActionRow`]: crate::builder::CreateActionRow
+    Component { row: usize, component: Option<usize> },
+    /// An attachment built by a [`CreateAttachment`].
+    ///
+    /// [`CreateAttachment`]: crate::builder::CreateAttachment
+    Attachment { index: usize },
+    /// A poll built by a [`CreatePoll`], and the index of the answer if the error is in one of
+    /// its answers.
+    ///
+    /// [`CreatePoll`]: crate::builder::CreatePoll
+    Poll { answer: Option<usize> },
+    /// The allowed mentions built by a [`CreateAllowedMentions`].
+    ///
+    /// [`CreateAllowedMentions`]: crate::builder::CreateAllowedMentions
+    AllowedMentions,
+    /// Any other part of the request.
+    Other,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[non_exhaustive]
 pub struct ErrorResponse {
@@ -319,6 +510,13 @@ pub enum HttpError {
     Request(ReqwestError),
     /// When an application id was expected but missing.
     ApplicationIdMissing,
+    /// When a request did not complete within its configured timeout.
+    ///
+    /// See [`HttpBuilder::default_timeout`] and [`Request::timeout`] for configuring timeouts.
+    ///
+    /// [`HttpBuilder::default_timeout`]: super::HttpBuilder::default_timeout
+    /// [`Request::timeout`]: super::Request::timeout
+    Timeout(Duration),
 }
 
 impl HttpError {
@@ -340,6 +538,12 @@ impl HttpError {
         matches!(self, Self::InvalidHeader(_))
     }
 
+    /// Returns true when the error is caused by a request timing out
+    #[must_use]
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
+
     /// Returns the status code if the error is an unsuccessful request
     #[must_use]
     pub fn status_code(&self) -> Option<StatusCode> {
@@ -348,6 +552,29 @@ impl HttpError {
             _ => None,
         }
     }
+
+    /// Returns the error returned by Discord if the error is an unsuccessful request
+    #[must_use]
+    pub fn discord_error(&self) -> Option<&DiscordJsonError> {
+        match self {
+            Self::UnsuccessfulRequest(res) => Some(&res.error),
+            _ => None,
+        }
+    }
+
+    /// Returns true when Discord rejected the request because the current user lacks the
+    /// required permissions or access
+    #[must_use]
+    pub fn is_missing_permissions(&self) -> bool {
+        self.discord_error().is_some_and(|e| e.code.is_missing_permissions())
+    }
+
+    /// Returns true when Discord rejected the request because a resource it refers to does not
+    /// exist
+    #[must_use]
+    pub fn is_unknown_resource(&self) -> bool {
+        self.discord_error().is_some_and(|e| e.code.is_unknown_resource())
+    }
 }
 
 impl From<ErrorResponse> for HttpError {
@@ -405,6 +632,7 @@ impl fmt::Display for HttpError {
             Self::InvalidHeader(_) => f.write_str("Provided value is an invalid header value."),
             Self::Request(_) => f.write_str("Error while sending HTTP request."),
             Self::ApplicationIdMissing => f.write_str("Application id was expected but missing."),
+            Self::Timeout(timeout) => write!(f, "Request timed out after {timeout:?}."),
         }
     }
 }
@@ -510,4 +738,41 @@ mod test {
 
         assert_eq!(error_response, known);
     }
+
+    #[test]
+    fn test_error_path() {
+        let path = ErrorPath::parse("embeds.0.fields.3.value");
+        assert_eq!(path, ErrorPath::parse("embeds[0].fields[3].value"));
+        assert_eq!(path.to_string(), "embeds[0].fields[3].value");
+        assert!(path.starts_with(&ErrorPath::parse("embeds[0]")));
+        assert!(!path.starts_with(&ErrorPath::parse("embeds[1]")));
+        assert_eq!(path.origin(), ErrorOrigin::Embed {
+            index: 0,
+            field: Some(3),
+        });
+
+        let path = ErrorPath::parse("data.components.1.components.0.custom_id");
+        assert_eq!(path.origin(), ErrorOrigin::Component {
+            row: 1,
+            component: Some(0),
+        });
+        assert_eq!(ErrorPath::parse("nonce").origin(), ErrorOrigin::Other);
+    }
+
+    #[test]
+    fn test_error_lookup() {
+        let json = r#"{
+            "code": 50035,
+            "message": "Invalid Form Body",
+            "errors": {"embeds": {"0": {"fields": {"3": {"value": {"_errors": [
+                {"code": "BASE_TYPE_REQUIRED", "message": "This field is required"}
+            ]}}}}}}
+        }"#;
+        let error: DiscordJsonError = serde_json::from_str(json).unwrap();
+
+        assert!(error.error_at("embeds[0].fields[3].value").is_some());
+        assert!(error.error_at("embeds[0]").is_none());
+        assert_eq!(error.errors_under("embeds[0]").count(), 1);
+        assert_eq!(error.errors_under("embeds[1]").count(), 0);
+    }
 }
@@ -85,6 +85,7 @@ pub struct Ratelimiter {
     global: Mutex<()>,
     routes: DashMap<RatelimitingBucket, Ratelimit>,
     token: Option<Token>,
+    pub(super) api_url: Option<FixedString<u16>>,
     absolute_ratelimits: bool,
     ratelimit_callback: parking_lot::RwLock<Box<dyn Fn(RatelimitInfo) + Send + Sync>>,
 }
@@ -96,6 +97,7 @@ impl fmt::Debug for Ratelimiter {
             .field("global", &self.global)
             .field("routes", &self.routes)
             .field("token", &self.token)
+            .field("api_url", &self.api_url)
             .field("absolute_ratelimits", &self.absolute_ratelimits)
             .field("ratelimit_callback", &"Fn(RatelimitInfo)")
             .finish()
@@ -109,6 +111,7 @@ impl Ratelimiter {
         Self {
             client,
             token,
+            api_url: None,
             global: Mutex::default(),
             routes: DashMap::new(),
             absolute_ratelimits: false,
@@ -190,10 +193,10 @@ impl Ratelimiter {
                 sleep(delay_time).await;
             }
 
-            let request = req.clone().build(
+            let request = req.clone().build_with_api_url(
                 &self.client,
                 self.token.as_ref().map(Token::expose_secret),
-                None,
+                self.api_url.as_deref(),
             )?;
             let response = self.client.execute(request.build()?).await?;
 
@@ -1,4 +1,5 @@
 use std::fmt::Write;
+use std::time::Duration;
 
 use reqwest::header::{
     HeaderMap as Headers,
@@ -16,6 +17,31 @@ use super::{HttpError, LightMethod};
 use crate::constants;
 use crate::internal::prelude::*;
 
+/// The timeout of a single [`Request`], set through [`Request::timeout`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RequestTimeout {
+    /// Use the timeout configured on the [`Http`] client for the request's route, if any.
+    ///
+    /// [`Http`]: super::Http
+    #[default]
+    Inherit,
+    /// Never time out, even if the [`Http`] client configures a timeout for the request's route.
+    ///
+    /// **Note**: A timeout configured on the [`reqwest::Client`] itself still applies.
+    ///
+    /// [`Http`]: super::Http
+    Disabled,
+    /// Time out after the given duration.
+    After(Duration),
+}
+
+impl From<Duration> for RequestTimeout {
+    fn from(timeout: Duration) -> Self {
+        Self::After(timeout)
+    }
+}
+
 #[derive(Clone, Debug)]
 #[must_use]
 pub struct Request<'a> {
@@ -25,6 +51,7 @@ pub struct Request<'a> {
     pub(super) method: LightMethod,
     pub(super) route: Route<'a>,
     pub(super) params: Option<&'a [(&'a str, &'a str)]>,
+    pub(super) timeout: RequestTimeout,
 }
 
 impl<'a> Request<'a> {
@@ -36,6 +63,7 @@ impl<'a> Request<'a> {
             method,
             route,
             params: None,
+            timeout: RequestTimeout::Inherit,
         }
     }
 
@@ -63,6 +91,20 @@ impl<'a> Request<'a> {
         self
     }
 
+    /// Sets the timeout of this request. Unless this is [`RequestTimeout::Inherit`], which is the
+    /// default, it overrides any timeout configured on the [`Http`] client for the request's route.
+    ///
+    /// Unless [`HttpBuilder::timeout_includes_ratelimits`] is enabled, the timeout applies to each
+    /// attempt separately, including the retries made after being ratelimited, so the total time
+    /// spent on the request is not bounded by it.
+    ///
+    /// [`Http`]: super::Http
+    /// [`HttpBuilder::timeout_includes_ratelimits`]: super::HttpBuilder::timeout_includes_ratelimits
+    pub fn timeout(mut self, timeout: impl Into<RequestTimeout>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
     /// # Errors
     ///
     /// Errors if the given proxy URL is invalid, or the token cannot be parsed into a HTTP header.
@@ -72,12 +114,36 @@ impl<'a> Request<'a> {
         client: &Client,
         token: Option<&str>,
         proxy: Option<&str>,
+    ) -> Result<ReqwestRequestBuilder> {
+        let api_url = proxy.map(|proxy| {
+            let path = constants::API_URL.trim_start_matches("https://discord.com");
+            // trim_end_matches to prevent double slashes after the domain
+            format!("{}{path}", proxy.trim_end_matches('/'))
+        });
+
+        self.build_with_api_url(client, token, api_url.as_deref())
+    }
+
+    /// Builds the request, sending it to the given API URL instead of [`constants::API_URL`] if
+    /// one is set. The API URL must include the API version, e.g. `http://127.0.0.1:3000/api/v10`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the given API URL is invalid, or the token cannot be parsed into a HTTP header.
+    #[cfg_attr(feature = "tracing_instrument", instrument(skip(token)))]
+    pub fn build_with_api_url(
+        self,
+        client: &Client,
+        token: Option<&str>,
+        api_url: Option<&str>,
     ) -> Result<ReqwestRequestBuilder> {
         let mut path = self.route.path().into_owned();
 
-        if let Some(proxy) = proxy {
-            // trim_end_matches to prevent double slashes after the domain
-            path = path.replace("https://discord.com", proxy.trim_end_matches('/'));
+        if let Some(api_url) = api_url {
+            if let Some(rest) = path.strip_prefix(constants::API_URL) {
+                // trim_end_matches to prevent double slashes after the version
+                path = format!("{}{rest}", api_url.trim_end_matches('/'));
+            }
         }
 
         if let Some(params) = self.params {
@@ -88,6 +154,9 @@ impl<'a> Request<'a> {
         }
 
         let mut builder = client.request(self.method.reqwest_method(), path);
+        if let RequestTimeout::After(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
 
         let mut headers = self.headers.unwrap_or_default();
         headers.insert(USER_AGENT, HeaderValue::from_static(constants::USER_AGENT));
@@ -146,4 +215,9 @@ impl<'a> Request<'a> {
     pub fn params_ref(&self) -> Option<&'a [(&'a str, &'a str)]> {
         self.params
     }
+
+    #[must_use]
+    pub fn timeout_ref(&self) -> RequestTimeout {
+        self.timeout
+    }
 }
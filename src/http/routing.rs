@@ -39,13 +39,21 @@ macro_rules! routes {
             )+
         }
 
+        /// The kind of a [`Route`], without any of its parameters.
+        ///
+        /// Used to configure behaviour for all requests to an endpoint, such as
+        /// [`HttpBuilder::route_timeout`].
+        ///
+        /// [`HttpBuilder::route_timeout`]: super::HttpBuilder::route_timeout
         #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-        enum RouteKind {
+        #[non_exhaustive]
+        pub enum RouteKind {
             $($name,)+
         }
 
         impl<$lt> Route<$lt> {
-            fn kind(&self) -> RouteKind {
+            #[must_use]
+            pub fn kind(&self) -> RouteKind {
                 match self {
                     $(
                         Self::$name {..} => RouteKind::$name,
@@ -1,19 +1,23 @@
 //! A set of macros for easily working with internals.
 
-#[cfg(any(feature = "model", feature = "utils"))]
-macro_rules! cdn {
-    ($e:expr) => {
-        concat!("https://cdn.discordapp.com", $e)
+/// The version of Discord's HTTP API, shared by `constants::API_VERSION` and `api_url!`.
+macro_rules! api_version {
+    () => {
+        10
     };
-    ($e:expr, $($rest:tt)*) => {
-        format!(cdn!($e), $($rest)*)
+}
+
+/// The URL of Discord's HTTP API, shared by `constants::API_URL` and `api!`.
+macro_rules! api_url {
+    () => {
+        concat!("https://discord.com/api/v", api_version!())
     };
 }
 
 #[cfg(feature = "http")]
 macro_rules! api {
     ($e:expr) => {
-        concat!("https://discord.com/api/v10", $e)
+        concat!(api_url!(), $e)
     };
     ($e:expr, $($rest:tt)*) => {
         format!(api!($e), $($rest)*)
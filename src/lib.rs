@@ -85,6 +85,7 @@ extern crate serde;
 #[macro_use]
 mod internal;
 
+pub mod cdn;
 pub mod constants;
 pub mod model;
 pub mod prelude;
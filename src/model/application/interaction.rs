@@ -12,7 +12,7 @@ use super::{
 use crate::internal::prelude::*;
 #[cfg(not(feature = "unstable"))]
 use crate::model::guild::PartialMember;
-use crate::model::id::{ApplicationId, GuildId, InteractionId, MessageId, UserId};
+use crate::model::id::{ApplicationId, ChannelId, GuildId, InteractionId, MessageId, UserId};
 use crate::model::monetization::Entitlement;
 use crate::model::user::User;
 use crate::model::utils::{deserialize_val, remove_from_map, StrOrInt};
@@ -76,6 +76,17 @@ impl Interaction {
         }
     }
 
+    /// Channel ID the interaction was sent from, if any.
+    #[must_use]
+    pub fn channel_id(&self) -> Option<ChannelId> {
+        match self {
+            Self::Ping(_) => None,
+            Self::Command(i) | Self::Autocomplete(i) => Some(i.channel_id),
+            Self::Component(i) => Some(i.channel_id),
+            Self::Modal(i) => Some(i.channel_id),
+        }
+    }
+
     /// Gets the interaction application Id
     #[must_use]
     pub fn application_id(&self) -> ApplicationId {
@@ -194,6 +194,87 @@ impl ChannelId {
         }
     }
 
+    /// Deletes any number of messages in the channel, working around the limits of
+    /// [`Self::delete_messages`].
+    ///
+    /// Messages are deleted in bulk in batches of up to 100, newest first. Messages older than two
+    /// weeks, which cannot be deleted in bulk, are deleted one at a time instead, which is
+    /// considerably slower due to ratelimits. Duplicate messages are only deleted once. A failure
+    /// to delete a batch or message does not stop the purge.
+    ///
+    /// After each batch or single deletion, `on_progress` is called with the summary so far.
+    ///
+    /// Requires the [Manage Messages] permission.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::http::Http;
+    /// # use serenity::model::prelude::*;
+    /// # async fn run(http: &Http, channel_id: ChannelId, messages: Vec<Message>) {
+    /// let summary = channel_id
+    ///     .purge_messages(http, &messages, None, |progress| {
+    ///         println!("Deleted {} messages so far", progress.deleted_count());
+    ///     })
+    ///     .await;
+    ///
+    /// println!("Deleted {}, failed {}", summary.deleted_count(), summary.failed_count());
+    /// # }
+    /// ```
+    ///
+    /// [Manage Messages]: Permissions::MANAGE_MESSAGES
+    pub async fn purge_messages<M: Into<MessageId>>(
+        self,
+        http: &Http,
+        messages: impl IntoIterator<Item = M>,
+        reason: Option<&str>,
+        on_progress: impl FnMut(&PurgeSummary),
+    ) -> PurgeSummary {
+        let message_ids = purge_order(messages.into_iter().map(Into::into));
+        purge(
+            &message_ids,
+            || Timestamp::now().unix_timestamp(),
+            |batch| self.delete_messages(http, batch, reason),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Deletes all messages matching the filter among the latest `scan_limit` messages in the
+    /// channel, using [`Self::messages_iter`] to find them and [`Self::purge_messages`] to delete
+    /// them.
+    ///
+    /// Requires the [Manage Messages] and [Read Message History] permissions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Http`] if fetching the messages fails. Failures to delete messages are
+    /// reported in the returned [`PurgeSummary`] instead.
+    ///
+    /// [Manage Messages]: Permissions::MANAGE_MESSAGES
+    /// [Read Message History]: Permissions::READ_MESSAGE_HISTORY
+    pub async fn purge_messages_matching(
+        self,
+        cache_http: impl CacheHttp,
+        scan_limit: usize,
+        mut filter: impl FnMut(&Message) -> bool,
+        reason: Option<&str>,
+        on_progress: impl FnMut(&PurgeSummary),
+    ) -> Result<PurgeSummary> {
+        use futures::{StreamExt as _, TryStreamExt as _};
+
+        let matching: Vec<MessageId> = self
+            .messages_iter(&cache_http)
+            .take(scan_limit)
+            .try_filter_map(|message| {
+                futures::future::ready(Ok(filter(&message).then_some(message.id)))
+            })
+            .try_collect()
+            .await?;
+
+        Ok(self.purge_messages(cache_http.http(), matching, reason, on_progress).await)
+    }
+
     /// Deletes all permission overrides in the channel from a member or role.
     ///
     /// **Note**: Requires the [Manage Channel] permission.
@@ -1085,6 +1166,111 @@ impl From<&WebhookChannel> for ChannelId {
     }
 }
 
+/// The maximum age of messages that can be deleted in bulk, which is two weeks.
+#[cfg(feature = "model")]
+const BULK_DELETE_MAX_AGE_SECS: i64 = 14 * 24 * 60 * 60;
+
+/// Deduplicates the messages to purge and sorts them newest first, so that the messages which
+/// can be deleted in bulk come first.
+#[cfg(feature = "model")]
+fn purge_order(message_ids: impl Iterator<Item = MessageId>) -> Vec<MessageId> {
+    let mut message_ids: Vec<_> = message_ids.collect();
+    message_ids.sort_unstable_by(|a, b| b.cmp(a));
+    message_ids.dedup();
+    message_ids
+}
+
+/// Splits off the next batch to delete from messages sorted newest first, returning the batch,
+/// whether it can be deleted in bulk, and the remaining messages.
+///
+/// A bulk batch holds up to 100 messages younger than two weeks at `now`. Otherwise, the batch is
+/// a single message which has to be deleted on its own.
+#[cfg(feature = "model")]
+fn next_purge_batch(message_ids: &[MessageId], now: i64) -> (&[MessageId], bool, &[MessageId]) {
+    use crate::model::error::Maximum;
+
+    // Leave some leeway, as Discord rejects the whole batch if a single message is too old.
+    let cutoff = now - BULK_DELETE_MAX_AGE_SECS + 60;
+    let bulk_len = message_ids
+        .iter()
+        .take(Maximum::BulkDeleteAmount.value())
+        .take_while(|id| id.created_at().unix_timestamp() > cutoff)
+        .count();
+
+    let (batch, rest) = message_ids.split_at(bulk_len.max(1).min(message_ids.len()));
+    (batch, bulk_len > 0, rest)
+}
+
+/// Deletes the messages sorted newest first using `delete`, recomputing which messages can be
+/// deleted in bulk before each batch, as messages may become too old during a long purge.
+#[cfg(feature = "model")]
+async fn purge<'a, Fut>(
+    mut message_ids: &'a [MessageId],
+    mut now: impl FnMut() -> i64,
+    mut delete: impl FnMut(&'a [MessageId]) -> Fut,
+    mut on_progress: impl FnMut(&PurgeSummary),
+) -> PurgeSummary
+where
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let mut summary = PurgeSummary::default();
+    while !message_ids.is_empty() {
+        let (batch, bulk, rest) = next_purge_batch(message_ids, now());
+        message_ids = rest;
+
+        match delete(batch).await {
+            Ok(()) if bulk => summary.bulk_deleted.extend_from_slice(batch),
+            Ok(()) => summary.single_deleted.extend_from_slice(batch),
+            Err(error) => summary.failed.push(PurgeFailure {
+                message_ids: batch.to_vec(),
+                error,
+            }),
+        }
+
+        on_progress(&summary);
+    }
+
+    summary
+}
+
+/// The outcome of [`ChannelId::purge_messages`].
+#[derive(Debug, Default)]
+#[cfg(feature = "model")]
+#[non_exhaustive]
+pub struct PurgeSummary {
+    /// The messages that were deleted in bulk.
+    pub bulk_deleted: Vec<MessageId>,
+    /// The messages that were deleted one at a time, because they were too old to be deleted in
+    /// bulk.
+    pub single_deleted: Vec<MessageId>,
+    /// The batches and messages that failed to be deleted.
+    pub failed: Vec<PurgeFailure>,
+}
+
+#[cfg(feature = "model")]
+impl PurgeSummary {
+    /// Returns the number of messages that were deleted.
+    #[must_use]
+    pub fn deleted_count(&self) -> usize {
+        self.bulk_deleted.len() + self.single_deleted.len()
+    }
+
+    /// Returns the number of messages that failed to be deleted.
+    #[must_use]
+    pub fn failed_count(&self) -> usize {
+        self.failed.iter().map(|f| f.message_ids.len()).sum()
+    }
+}
+
+/// A batch of messages, or a single message, that [`ChannelId::purge_messages`] failed to delete.
+#[derive(Debug)]
+#[cfg(feature = "model")]
+#[non_exhaustive]
+pub struct PurgeFailure {
+    pub message_ids: Vec<MessageId>,
+    pub error: Error,
+}
+
 /// A helper class returned by [`ChannelId::messages_iter`]
 #[derive(Clone, Debug)]
 #[cfg(feature = "model")]
@@ -1205,3 +1391,90 @@ impl<'a> MessagesIter<'a> {
         })
     }
 }
+
+#[cfg(all(test, feature = "model"))]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const NOW: i64 = 1_700_000_000;
+
+    /// Creates a message Id of a message sent the given number of seconds before [`NOW`].
+    fn message_id(age: i64, increment: u64) -> MessageId {
+        let millis = u64::try_from((NOW - age) * 1000).unwrap() - 1_420_070_400_000;
+        MessageId::new((millis << 22) | increment)
+    }
+
+    #[test]
+    fn test_purge_order() {
+        let ids = [message_id(10, 0), message_id(5, 0), message_id(10, 0), message_id(20, 0)];
+        let ordered = purge_order(ids.into_iter());
+        assert_eq!(ordered, [message_id(5, 0), message_id(10, 0), message_id(20, 0)]);
+    }
+
+    #[test]
+    fn test_next_purge_batch() {
+        // 150 recent messages, followed by two older than two weeks.
+        let mut ids: Vec<_> = (0..150).map(|i| message_id(DAY, i)).collect();
+        ids.extend([message_id(15 * DAY, 0), message_id(20 * DAY, 0)]);
+        let ids = purge_order(ids.into_iter());
+
+        let (batch, bulk, rest) = next_purge_batch(&ids, NOW);
+        assert!(bulk);
+        assert_eq!((batch.len(), rest.len()), (100, 52));
+
+        let (batch, bulk, rest) = next_purge_batch(rest, NOW);
+        assert!(bulk);
+        assert_eq!((batch.len(), rest.len()), (50, 2));
+
+        let (batch, bulk, rest) = next_purge_batch(rest, NOW);
+        assert!(!bulk);
+        assert_eq!((batch, rest), (&[message_id(15 * DAY, 0)][..], &[message_id(20 * DAY, 0)][..]));
+
+        // Messages within a minute of the cutoff are deleted one at a time.
+        let ids = [message_id(14 * DAY - 30, 0), message_id(14 * DAY - 90, 0)];
+        let ids = purge_order(ids.into_iter());
+        let (batch, bulk, _) = next_purge_batch(&ids, NOW);
+        assert!(bulk);
+        assert_eq!(batch, [message_id(14 * DAY - 90, 0)]);
+    }
+
+    #[tokio::test]
+    async fn test_purge() {
+        let mut ids: Vec<_> = (0..120).map(|i| message_id(13 * DAY, i)).collect();
+        ids.extend((0..3).map(|i| message_id(20 * DAY, i)));
+        let ids = purge_order(ids.into_iter());
+
+        // A day passes between each batch, so the remaining recent messages become too old.
+        let mut now = NOW - DAY;
+        let mut batches = Vec::new();
+        let mut progress = Vec::new();
+        let summary = purge(
+            &ids,
+            || {
+                now += DAY;
+                now
+            },
+            |batch: &[MessageId]| {
+                batches.push(batch.len());
+                let failed = batch.contains(&message_id(20 * DAY, 1));
+                std::future::ready(if failed {
+                    Err(Error::Io(std::io::Error::other("failed")))
+                } else {
+                    Ok(())
+                })
+            },
+            |summary| progress.push((summary.deleted_count(), summary.failed_count())),
+        )
+        .await;
+
+        assert_eq!(batches, [&[100][..], &[1; 23]].concat());
+        assert_eq!(summary.bulk_deleted.len(), 100);
+        assert_eq!(summary.single_deleted.len(), 22);
+        assert_eq!(summary.deleted_count(), 122);
+        assert_eq!(summary.failed_count(), 1);
+        assert_eq!(summary.failed[0].message_ids, [message_id(20 * DAY, 1)]);
+        assert_eq!(progress.len(), 24);
+        assert_eq!(progress.last(), Some(&(122, 1)));
+    }
+}
@@ -506,6 +506,42 @@ impl Message {
         self.mentions_user_id(user.id)
     }
 
+    /// Returns the names of the fields that Discord sent empty because the message was received
+    /// with the given intents, which lack [`GatewayIntents::MESSAGE_CONTENT`].
+    ///
+    /// Without that intent, the content, embeds, attachments, components and poll are left out of
+    /// guild messages, unless the message was sent by or mentions the current user. An empty
+    /// slice is returned if the fields were sent in full.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use serenity::model::prelude::*;
+    /// # fn run(message: Message, current_user_id: UserId) {
+    /// let intents = GatewayIntents::GUILD_MESSAGES;
+    /// let blank = message.fields_blanked_by_intents(intents, current_user_id);
+    /// if !blank.is_empty() {
+    ///     println!("Enable the MESSAGE_CONTENT intent to receive {}", blank.join(", "));
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn fields_blanked_by_intents(
+        &self,
+        intents: GatewayIntents,
+        current_user_id: UserId,
+    ) -> &'static [&'static str] {
+        if intents.message_content()
+            || self.guild_id.is_none()
+            || self.author.id == current_user_id
+            || self.mentions_user_id(current_user_id)
+        {
+            return &[];
+        }
+
+        &["content", "embeds", "attachments", "components", "poll"]
+    }
+
     /// Checks whether the message mentions the current user.
     ///
     /// # Errors
@@ -1075,9 +1075,15 @@ pub enum GatewayEvent {
         seq: u64,
         // Avoid deserialising straight away to handle errors and get access to `seq`.
         data: JsonMap,
-        // Used for debugging, if the data cannot be deserialised.
+        // Used for debugging, if the data cannot be deserialised. Empty for ETF payloads.
         original_str: FixedString,
     },
+    /// A dispatch that was not decoded any further than its name and sequence number, as nothing
+    /// needed it.
+    SkippedDispatch {
+        seq: u64,
+        kind: FixedString,
+    },
     Heartbeat(u64),
     Reconnect,
     /// Whether the session can be resumed.
@@ -1323,12 +1329,230 @@ pub enum Event {
     MessagePollVoteAdd(MessagePollVoteAddEvent),
     /// A user has removed a previous vote on a Message Poll.
     MessagePollVoteRemove(MessagePollVoteRemoveEvent),
+    /// A dispatch event that is not known to the library, such as one recently added by Discord.
+    ///
+    /// This is never deserialized directly, but created by the shard when the event name is not
+    /// recognised. It cannot be serialized.
+    ///
+    /// Fires the [`EventHandler::unknown_event`] event.
+    ///
+    /// [`EventHandler::unknown_event`]: crate::gateway::client::EventHandler::unknown_event
+    #[serde(skip)]
+    Unknown {
+        /// The name of the event, as sent by Discord.
+        kind: FixedString,
+        /// The raw data of the event.
+        data: Value,
+    },
 }
 
+/// The names Discord sends for the events whose variant is renamed with `#[serde(rename)]`, along
+/// with the name returned by [`Event::name`] for them.
+const RENAMED_EVENTS: &[(&str, &str)] = &[
+    ("APPLICATION_COMMAND_PERMISSIONS_UPDATE", "COMMAND_PERMISSIONS_UPDATE"),
+    ("AUTO_MODERATION_RULE_CREATE", "AUTO_MOD_RULE_CREATE"),
+    ("AUTO_MODERATION_RULE_UPDATE", "AUTO_MOD_RULE_UPDATE"),
+    ("AUTO_MODERATION_RULE_DELETE", "AUTO_MOD_RULE_DELETE"),
+    ("AUTO_MODERATION_ACTION_EXECUTION", "AUTO_MOD_ACTION_EXECUTION"),
+    ("MESSAGE_REACTION_ADD", "REACTION_ADD"),
+    ("MESSAGE_REACTION_REMOVE", "REACTION_REMOVE"),
+    ("MESSAGE_REACTION_REMOVE_ALL", "REACTION_REMOVE_ALL"),
+    ("MESSAGE_REACTION_REMOVE_EMOJI", "REACTION_REMOVE_EMOJI"),
+    ("WEBHOOKS_UPDATE", "WEBHOOK_UPDATE"),
+];
+
 impl Event {
     /// Returns the event name of this event.
+    ///
+    /// **Note**: This is the name of the variant, which differs from the name sent by Discord for
+    /// some events, e.g. `WEBHOOK_UPDATE` for [`Self::WebhookUpdate`], which Discord sends as
+    /// `WEBHOOKS_UPDATE`.
     #[must_use]
     pub fn name(&self) -> &'static str {
         self.into()
     }
+
+    /// Returns the name sent by Discord for the event with the given name, as returned by
+    /// [`Self::name`]. Names sent by Discord are returned as they are.
+    pub(crate) fn wire_name(name: &str) -> &str {
+        RENAMED_EVENTS
+            .iter()
+            .find(|(_, variant_name)| *variant_name == name)
+            .map_or(name, |(wire_name, _)| wire_name)
+    }
+
+    /// Returns whether a dispatch event with the given name, as sent by Discord, is known to the
+    /// library.
+    pub(crate) fn is_known_wire_name(name: &str) -> bool {
+        if RENAMED_EVENTS.iter().any(|(wire_name, _)| *wire_name == name) {
+            return true;
+        }
+
+        name != "UNKNOWN"
+            && !RENAMED_EVENTS.iter().any(|(_, variant_name)| *variant_name == name)
+            && Self::VARIANTS.contains(&name)
+    }
+
+    /// Returns the Id of the guild this event happened in, if any.
+    ///
+    /// This is [`None`] for events that happened outside of a guild, such as in direct messages,
+    /// and for events that aren't tied to a guild, such as [`Self::Ready`].
+    #[must_use]
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            Self::CommandPermissionsUpdate(e) => Some(e.permission.guild_id),
+            Self::AutoModRuleCreate(e) => Some(e.rule.guild_id),
+            Self::AutoModRuleUpdate(e) => Some(e.rule.guild_id),
+            Self::AutoModRuleDelete(e) => Some(e.rule.guild_id),
+            Self::AutoModActionExecution(e) => Some(e.execution.guild_id),
+            Self::ChannelCreate(e) => Some(e.channel.guild_id),
+            Self::ChannelDelete(e) => Some(e.channel.guild_id),
+            Self::ChannelUpdate(e) => Some(e.channel.guild_id),
+            Self::ChannelPinsUpdate(e) => e.guild_id,
+            Self::GuildAuditLogEntryCreate(e) => Some(e.guild_id),
+            Self::GuildBanAdd(e) => Some(e.guild_id),
+            Self::GuildBanRemove(e) => Some(e.guild_id),
+            Self::GuildCreate(e) => Some(e.guild.id),
+            Self::GuildDelete(e) => Some(e.guild.id),
+            Self::GuildEmojisUpdate(e) => Some(e.guild_id),
+            Self::GuildIntegrationsUpdate(e) => Some(e.guild_id),
+            Self::GuildMemberAdd(e) => Some(e.member.guild_id),
+            Self::GuildMemberRemove(e) => Some(e.guild_id),
+            Self::GuildMemberUpdate(e) => Some(e.guild_id),
+            Self::GuildMembersChunk(e) => Some(e.guild_id),
+            Self::GuildRoleCreate(e) => Some(e.role.guild_id),
+            Self::GuildRoleUpdate(e) => Some(e.role.guild_id),
+            Self::GuildRoleDelete(e) => Some(e.guild_id),
+            Self::GuildStickersUpdate(e) => Some(e.guild_id),
+            Self::GuildUpdate(e) => Some(e.guild.id),
+            Self::InviteCreate(e) => e.guild_id,
+            Self::InviteDelete(e) => e.guild_id,
+            Self::MessageCreate(e) => e.message.guild_id,
+            Self::MessageDelete(e) => e.guild_id,
+            Self::MessageDeleteBulk(e) => e.guild_id,
+            Self::MessageUpdate(e) => e.guild_id,
+            Self::PresenceUpdate(e) => e.presence.guild_id,
+            Self::ReactionAdd(e) => e.reaction.guild_id,
+            Self::ReactionRemove(e) => e.reaction.guild_id,
+            Self::ReactionRemoveEmoji(e) => e.reaction.guild_id,
+            Self::ReactionRemoveAll(e) => e.guild_id,
+            Self::TypingStart(e) => e.guild_id,
+            Self::VoiceStateUpdate(e) => e.voice_state.guild_id,
+            Self::VoiceServerUpdate(e) => Some(e.guild_id),
+            Self::VoiceChannelStatusUpdate(e) => Some(e.guild_id),
+            Self::WebhookUpdate(e) => Some(e.guild_id),
+            Self::InteractionCreate(e) => e.interaction.guild_id(),
+            Self::IntegrationCreate(e) => e.integration.guild_id,
+            Self::IntegrationUpdate(e) => e.integration.guild_id,
+            Self::IntegrationDelete(e) => Some(e.guild_id),
+            Self::StageInstanceCreate(e) => Some(e.stage_instance.guild_id),
+            Self::StageInstanceUpdate(e) => Some(e.stage_instance.guild_id),
+            Self::StageInstanceDelete(e) => Some(e.stage_instance.guild_id),
+            Self::ThreadCreate(e) => Some(e.thread.guild_id),
+            Self::ThreadUpdate(e) => Some(e.thread.guild_id),
+            Self::ThreadDelete(e) => Some(e.thread.guild_id),
+            Self::ThreadListSync(e) => Some(e.guild_id),
+            Self::ThreadMemberUpdate(e) => e.member.guild_id,
+            Self::ThreadMembersUpdate(e) => Some(e.guild_id),
+            Self::GuildScheduledEventCreate(e) => Some(e.event.guild_id),
+            Self::GuildScheduledEventUpdate(e) => Some(e.event.guild_id),
+            Self::GuildScheduledEventDelete(e) => Some(e.event.guild_id),
+            Self::GuildScheduledEventUserAdd(e) => Some(e.guild_id),
+            Self::GuildScheduledEventUserRemove(e) => Some(e.guild_id),
+            Self::EntitlementCreate(e) => e.entitlement.guild_id,
+            Self::EntitlementUpdate(e) => e.entitlement.guild_id,
+            Self::EntitlementDelete(e) => e.entitlement.guild_id,
+            Self::MessagePollVoteAdd(e) => e.guild_id,
+            Self::MessagePollVoteRemove(e) => e.guild_id,
+            Self::Ready(_)
+            | Self::Resumed(_)
+            | Self::UserUpdate(_)
+            | Self::Unknown {
+                ..
+            } => None,
+        }
+    }
+
+    /// Returns the Id of the channel or thread this event happened in, if any.
+    ///
+    /// For events about a channel or thread itself, such as [`Self::ChannelUpdate`], this is the
+    /// Id of that channel.
+    #[must_use]
+    pub fn channel_id(&self) -> Option<ChannelId> {
+        match self {
+            Self::AutoModActionExecution(e) => e.execution.channel_id,
+            Self::ChannelCreate(e) => Some(e.channel.id),
+            Self::ChannelDelete(e) => Some(e.channel.id),
+            Self::ChannelUpdate(e) => Some(e.channel.id),
+            Self::ChannelPinsUpdate(e) => Some(e.channel_id),
+            Self::InviteCreate(e) => Some(e.channel_id),
+            Self::InviteDelete(e) => Some(e.channel_id),
+            Self::MessageCreate(e) => Some(e.message.channel_id),
+            Self::MessageDelete(e) => Some(e.channel_id),
+            Self::MessageDeleteBulk(e) => Some(e.channel_id),
+            Self::MessageUpdate(e) => Some(e.channel_id),
+            Self::ReactionAdd(e) => Some(e.reaction.channel_id),
+            Self::ReactionRemove(e) => Some(e.reaction.channel_id),
+            Self::ReactionRemoveEmoji(e) => Some(e.reaction.channel_id),
+            Self::ReactionRemoveAll(e) => Some(e.channel_id),
+            Self::TypingStart(e) => Some(e.channel_id),
+            Self::VoiceStateUpdate(e) => e.voice_state.channel_id,
+            Self::VoiceChannelStatusUpdate(e) => Some(e.id),
+            Self::WebhookUpdate(e) => Some(e.channel_id),
+            Self::InteractionCreate(e) => e.interaction.channel_id(),
+            Self::StageInstanceCreate(e) => Some(e.stage_instance.channel_id),
+            Self::StageInstanceUpdate(e) => Some(e.stage_instance.channel_id),
+            Self::StageInstanceDelete(e) => Some(e.stage_instance.channel_id),
+            Self::ThreadCreate(e) => Some(e.thread.id),
+            Self::ThreadUpdate(e) => Some(e.thread.id),
+            Self::ThreadDelete(e) => Some(e.thread.id),
+            Self::ThreadMemberUpdate(e) => Some(e.member.id),
+            Self::ThreadMembersUpdate(e) => Some(e.id),
+            Self::GuildScheduledEventCreate(e) => e.event.channel_id,
+            Self::GuildScheduledEventUpdate(e) => e.event.channel_id,
+            Self::GuildScheduledEventDelete(e) => e.event.channel_id,
+            Self::MessagePollVoteAdd(e) => Some(e.channel_id),
+            Self::MessagePollVoteRemove(e) => Some(e.channel_id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renamed_event_names() {
+        for &(wire_name, name) in RENAMED_EVENTS {
+            assert!(Event::VARIANTS.contains(&name), "{name}");
+            assert!(Event::is_known_wire_name(wire_name), "{wire_name}");
+            assert!(!Event::is_known_wire_name(name), "{name}");
+            assert_eq!(Event::wire_name(name), wire_name);
+            assert_eq!(Event::wire_name(wire_name), wire_name);
+        }
+
+        let event = Event::ReactionRemoveAll(ReactionRemoveAllEvent {
+            channel_id: ChannelId::new(1),
+            message_id: MessageId::new(2),
+            guild_id: None,
+        });
+        assert_eq!(event.name(), "REACTION_REMOVE_ALL");
+        assert!(Event::is_known_wire_name("MESSAGE_CREATE"));
+        assert!(!Event::is_known_wire_name("UNKNOWN"));
+    }
+
+    #[test]
+    fn test_known_wire_names() {
+        // Serde lists the tags of all variants it deserializes when the tag is unknown.
+        let value = serde_json::json!({"t": "NOT_AN_EVENT", "d": {}});
+        let err = Event::deserialize(value).unwrap_err().to_string();
+        let (_, tags) = err.split_once("expected one of ").unwrap();
+        let tags: Vec<_> = tags.split(", ").map(|tag| tag.trim_matches('`')).collect();
+        assert_eq!(tags.len(), Event::VARIANTS.len() - 1);
+
+        for tag in tags {
+            assert!(Event::is_known_wire_name(tag), "{tag}");
+        }
+    }
 }
@@ -598,6 +598,79 @@ impl GatewayIntents {
         // See: https://github.com/bitflags/bitflags/issues/180
         Self::GUILD_MEMBERS.union(Self::GUILD_PRESENCES).union(Self::MESSAGE_CONTENT)
     }
+
+    /// Returns the intents that enable the gateway event with the given name, such as
+    /// `"MESSAGE_CREATE"`, any one of which is enough to receive it. Names are accepted both as
+    /// returned by [`Event::name`] and as sent by Discord.
+    ///
+    /// For events that are split between guilds and direct messages, this includes the intents
+    /// for both. Returns [`None`] for events that are sent regardless of intents, such as `READY`
+    /// and `INTERACTION_CREATE`, as well as for unknown events.
+    #[must_use]
+    pub fn for_event(name: &str) -> Option<GatewayIntents> {
+        let intents = match Event::wire_name(name) {
+            "GUILD_CREATE"
+            | "GUILD_UPDATE"
+            | "GUILD_DELETE"
+            | "GUILD_ROLE_CREATE"
+            | "GUILD_ROLE_UPDATE"
+            | "GUILD_ROLE_DELETE"
+            | "CHANNEL_CREATE"
+            | "CHANNEL_UPDATE"
+            | "CHANNEL_DELETE"
+            | "THREAD_CREATE"
+            | "THREAD_UPDATE"
+            | "THREAD_DELETE"
+            | "THREAD_LIST_SYNC"
+            | "THREAD_MEMBER_UPDATE"
+            | "STAGE_INSTANCE_CREATE"
+            | "STAGE_INSTANCE_UPDATE"
+            | "STAGE_INSTANCE_DELETE" => Self::GUILDS,
+            "CHANNEL_PINS_UPDATE" => Self::GUILDS | Self::DIRECT_MESSAGES,
+            "THREAD_MEMBERS_UPDATE" => Self::GUILDS | Self::GUILD_MEMBERS,
+            "GUILD_MEMBER_ADD" | "GUILD_MEMBER_UPDATE" | "GUILD_MEMBER_REMOVE" => {
+                Self::GUILD_MEMBERS
+            },
+            "GUILD_AUDIT_LOG_ENTRY_CREATE" | "GUILD_BAN_ADD" | "GUILD_BAN_REMOVE" => {
+                Self::GUILD_MODERATION
+            },
+            "GUILD_EMOJIS_UPDATE" | "GUILD_STICKERS_UPDATE" => Self::GUILD_EMOJIS_AND_STICKERS,
+            "GUILD_INTEGRATIONS_UPDATE"
+            | "INTEGRATION_CREATE"
+            | "INTEGRATION_UPDATE"
+            | "INTEGRATION_DELETE" => Self::GUILD_INTEGRATIONS,
+            "WEBHOOKS_UPDATE" => Self::GUILD_WEBHOOKS,
+            "INVITE_CREATE" | "INVITE_DELETE" => Self::GUILD_INVITES,
+            "VOICE_STATE_UPDATE" => Self::GUILD_VOICE_STATES,
+            "PRESENCE_UPDATE" => Self::GUILD_PRESENCES,
+            "MESSAGE_CREATE" | "MESSAGE_UPDATE" | "MESSAGE_DELETE" => {
+                Self::GUILD_MESSAGES | Self::DIRECT_MESSAGES
+            },
+            "MESSAGE_DELETE_BULK" => Self::GUILD_MESSAGES,
+            "MESSAGE_REACTION_ADD"
+            | "MESSAGE_REACTION_REMOVE"
+            | "MESSAGE_REACTION_REMOVE_ALL"
+            | "MESSAGE_REACTION_REMOVE_EMOJI" => {
+                Self::GUILD_MESSAGE_REACTIONS | Self::DIRECT_MESSAGE_REACTIONS
+            },
+            "TYPING_START" => Self::GUILD_MESSAGE_TYPING | Self::DIRECT_MESSAGE_TYPING,
+            "GUILD_SCHEDULED_EVENT_CREATE"
+            | "GUILD_SCHEDULED_EVENT_UPDATE"
+            | "GUILD_SCHEDULED_EVENT_DELETE"
+            | "GUILD_SCHEDULED_EVENT_USER_ADD"
+            | "GUILD_SCHEDULED_EVENT_USER_REMOVE" => Self::GUILD_SCHEDULED_EVENTS,
+            "AUTO_MODERATION_RULE_CREATE"
+            | "AUTO_MODERATION_RULE_UPDATE"
+            | "AUTO_MODERATION_RULE_DELETE" => Self::AUTO_MODERATION_CONFIGURATION,
+            "AUTO_MODERATION_ACTION_EXECUTION" => Self::AUTO_MODERATION_EXECUTION,
+            "MESSAGE_POLL_VOTE_ADD" | "MESSAGE_POLL_VOTE_REMOVE" => {
+                Self::GUILD_MESSAGE_POLLS | Self::DIRECT_MESSAGE_POLLS
+            },
+            _ => return None,
+        };
+
+        Some(intents)
+    }
 }
 
 #[cfg(feature = "model")]
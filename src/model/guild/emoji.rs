@@ -1,6 +1,10 @@
 use std::fmt;
 
+#[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl};
 use crate::model::prelude::*;
+#[cfg(feature = "model")]
+use crate::model::utils::cdn_url;
 use crate::model::utils::default_true;
 
 /// Represents a custom guild emoji, which can either be created using the API, or via an
@@ -60,8 +64,10 @@ impl Emoji {
     /// ```
     #[must_use]
     pub fn url(&self) -> String {
-        let extension = if self.animated() { "gif" } else { "png" };
-        cdn!("/emojis/{}.{}", self.id, extension)
+        cdn_url(CdnUrl::new(Asset::CustomEmoji {
+            emoji_id: self.id,
+            animated: self.animated(),
+        }))
     }
 }
 
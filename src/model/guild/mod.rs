@@ -34,6 +34,8 @@ pub use self::system_channel::*;
 pub use self::welcome_screen::*;
 #[cfg(feature = "model")]
 use crate::builder::EditGuild;
+#[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl, ImageFormat};
 #[cfg(doc)]
 use crate::constants::LARGE_THRESHOLD;
 #[cfg(feature = "model")]
@@ -291,9 +293,12 @@ impl Guild {
     }
 
     /// Returns the formatted URL of the guild's banner image, if one exists.
+    ///
+    /// The URL points to Discord's CDN. Use [`Http::cdn_url`] to point it to the CDN URL set on a
+    /// client instead.
     #[must_use]
     pub fn banner_url(&self) -> Option<String> {
-        self.banner.as_ref().map(|banner| cdn!("/banners/{}/{}.webp?size=1024", self.id, banner))
+        self.banner.as_deref().map(|banner| banner_url(self.id, banner, Some(1024)))
     }
 
     /// Creates a guild with the data provided.
@@ -912,7 +917,13 @@ impl Guild {
     /// Returns the formatted URL of the guild's splash image, if one exists.
     #[must_use]
     pub fn splash_url(&self) -> Option<String> {
-        self.splash.as_ref().map(|splash| cdn!("/splashes/{}/{}.webp?size=4096", self.id, splash))
+        self.splash.map(|hash| {
+            let asset = Asset::GuildSplash {
+                guild_id: self.id,
+                hash,
+            };
+            cdn_url(CdnUrl::new(asset).format(ImageFormat::WebP).size(4096))
+        })
     }
 
     /// Obtain a reference to a role by its name.
@@ -1129,7 +1140,13 @@ impl InviteGuild {
     /// Returns the formatted URL of the guild's splash image, if one exists.
     #[must_use]
     pub fn splash_url(&self) -> Option<String> {
-        self.splash.as_ref().map(|splash| cdn!("/splashes/{}/{}.webp?size=4096", self.id, splash))
+        self.splash.map(|hash| {
+            let asset = Asset::GuildSplash {
+                guild_id: self.id,
+                hash,
+            };
+            cdn_url(CdnUrl::new(asset).format(ImageFormat::WebP).size(4096))
+        })
     }
 }
 
@@ -1301,5 +1318,24 @@ mod test {
 
             assert_eq!(lhs, gen_member().display_name());
         }
+
+        #[test]
+        fn banner_url() {
+            let mut guild = Guild {
+                id: GuildId::new(1),
+                banner: Some(FixedString::from_static_trunc("a_e3c0db7f38777778fb43081f8746ebc9")),
+                ..Default::default()
+            };
+            assert_eq!(
+                guild.banner_url().unwrap(),
+                "https://cdn.discordapp.com/banners/1/a_e3c0db7f38777778fb43081f8746ebc9.webp?size=1024"
+            );
+
+            guild.banner = Some(FixedString::from_static_trunc("not_a_hash"));
+            assert_eq!(
+                guild.banner_url().unwrap(),
+                "https://cdn.discordapp.com/banners/1/not_a_hash.webp?size=1024"
+            );
+        }
     }
 }
@@ -4,11 +4,13 @@ use serde::Serialize;
 #[cfg(feature = "model")]
 use crate::builder::EditGuild;
 #[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl, ImageFormat};
+#[cfg(feature = "model")]
 use crate::http::{CacheHttp, Http};
 use crate::internal::utils::lending_for_each;
 use crate::model::prelude::*;
 #[cfg(feature = "model")]
-use crate::model::utils::icon_url;
+use crate::model::utils::{banner_url, cdn_url, icon_url};
 
 /// Partial information about a [`Guild`]. This does not include information like member data.
 ///
@@ -242,9 +244,12 @@ impl PartialGuild {
     }
 
     /// Returns a formatted URL of the guild's banner, if the guild has a banner.
+    ///
+    /// The URL points to Discord's CDN. Use [`Http::cdn_url`] to point it to the CDN URL set on a
+    /// client instead.
     #[must_use]
     pub fn banner_url(&self) -> Option<String> {
-        self.banner.as_ref().map(|banner| cdn!("/banners/{}/{}.webp", self.id, banner))
+        self.banner.as_deref().map(|banner| banner_url(self.id, banner, None))
     }
 
     /// Calculate a [`Member`]'s permissions in a given channel in the guild.
@@ -263,7 +268,13 @@ impl PartialGuild {
     /// Returns the formatted URL of the guild's splash image, if one exists.
     #[must_use]
     pub fn splash_url(&self) -> Option<String> {
-        self.splash.as_ref().map(|splash| cdn!("/splashes/{}/{}.webp?size=4096", self.id, splash))
+        self.splash.map(|hash| {
+            let asset = Asset::GuildSplash {
+                guild_id: self.id,
+                hash,
+            };
+            cdn_url(CdnUrl::new(asset).format(ImageFormat::WebP).size(4096))
+        })
     }
 
     /// Obtain a reference to a role by its name.
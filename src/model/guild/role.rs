@@ -4,8 +4,12 @@ use std::fmt;
 #[cfg(feature = "model")]
 use crate::builder::EditRole;
 #[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl};
+#[cfg(feature = "model")]
 use crate::http::Http;
 use crate::model::prelude::*;
+#[cfg(feature = "model")]
+use crate::model::utils::cdn_url;
 use crate::model::utils::is_false;
 
 /// Information about a role within a guild.
@@ -124,10 +128,11 @@ impl Role {
     #[must_use]
     /// Generates a URL to the Role icon's image.
     pub fn icon_url(&self) -> Option<String> {
-        self.icon.map(|icon| {
-            let ext = if icon.is_animated() { "gif" } else { "webp" };
-
-            cdn!("/role-icons/{}/{}.{}", self.id, icon, ext)
+        self.icon.map(|hash| {
+            cdn_url(CdnUrl::new(Asset::RoleIcon {
+                role_id: self.id,
+                hash,
+            }))
         })
     }
 }
@@ -11,6 +11,10 @@ use std::str::FromStr;
 use aformat::ArrayString;
 
 use super::prelude::*;
+#[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl};
+#[cfg(feature = "model")]
+use crate::model::utils::cdn_url;
 #[cfg(all(feature = "model", any(feature = "cache", feature = "utils")))]
 use crate::utils;
 
@@ -173,14 +177,15 @@ pub struct EmojiIdentifier {
     pub name: FixedString,
 }
 
-#[cfg(all(feature = "model", feature = "utils"))]
+#[cfg(feature = "model")]
 impl EmojiIdentifier {
     /// Generates a URL to the emoji's image.
     #[must_use]
     pub fn url(&self) -> String {
-        let ext = if self.animated { "gif" } else { "png" };
-
-        cdn!("/emojis/{}.{}", self.id, ext)
+        cdn_url(CdnUrl::new(Asset::CustomEmoji {
+            emoji_id: self.id,
+            animated: self.animated,
+        }))
     }
 }
 
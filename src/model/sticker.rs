@@ -1,8 +1,12 @@
 #[cfg(feature = "model")]
 use crate::builder::EditSticker;
 #[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl, ImageFormat};
+#[cfg(feature = "model")]
 use crate::http::Http;
 use crate::model::prelude::*;
+#[cfg(feature = "model")]
+use crate::model::utils::cdn_url;
 use crate::model::utils::comma_separated_string;
 
 #[cfg(feature = "model")]
@@ -97,7 +101,10 @@ impl StickerPack {
 
 #[cfg(feature = "model")]
 fn banner_url(banner_asset_id: StickerPackBannerId) -> String {
-    cdn!("/app-assets/710982414301790216/store/{}.webp?size=1024", banner_asset_id)
+    let asset = Asset::StickerPackBanner {
+        banner_asset_id,
+    };
+    cdn_url(CdnUrl::new(asset).format(ImageFormat::WebP).size(1024))
 }
 
 /// A sticker sent with a message.
@@ -253,13 +260,11 @@ enum_number! {
 }
 
 #[cfg(feature = "model")]
-fn sticker_url(sticker_id: StickerId, sticker_format_type: StickerFormatType) -> Option<String> {
-    let ext = match sticker_format_type {
-        StickerFormatType::Png | StickerFormatType::Apng => "png",
-        StickerFormatType::Lottie => "json",
-        StickerFormatType::Gif => "gif",
-        StickerFormatType(_) => return None,
-    };
-
-    Some(cdn!("/stickers/{}.{}", sticker_id, ext))
+fn sticker_url(sticker_id: StickerId, format: StickerFormatType) -> Option<String> {
+    CdnUrl::new(Asset::Sticker {
+        sticker_id,
+        format,
+    })
+    .build()
+    .ok()
 }
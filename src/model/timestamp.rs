@@ -107,6 +107,16 @@ impl Timestamp {
         x
     }
 
+    /// Returns the number of non-leap milliseconds since January 1, 1970 0:00:00 UTC
+    #[must_use]
+    pub fn unix_timestamp_millis(&self) -> i64 {
+        #[cfg(feature = "chrono")]
+        let x = self.0.timestamp_millis();
+        #[cfg(not(feature = "chrono"))]
+        let x = (self.0.unix_timestamp_nanos() / 1_000_000) as i64;
+        x
+    }
+
     /// Parse a timestamp from an RFC 3339 date and time string.
     ///
     /// # Examples
@@ -12,9 +12,11 @@ use super::prelude::*;
 #[cfg(feature = "model")]
 use crate::builder::{CreateMessage, EditProfile};
 #[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl, ImageFormat};
+#[cfg(feature = "model")]
 use crate::http::{CacheHttp, Http};
 #[cfg(feature = "model")]
-use crate::model::utils::avatar_url;
+use crate::model::utils::{avatar_url, cdn_url};
 
 /// Used with `#[serde(with|deserialize_with|serialize_with)]`
 ///
@@ -697,19 +699,30 @@ fn default_avatar_url(user: &User) -> String {
         ((user.id.get() >> 22) % 6) as u16 // New username system
     };
 
-    cdn!("/embed/avatars/{}.png", avatar_id)
+    cdn_url(CdnUrl::new(Asset::DefaultUserAvatar {
+        index: avatar_id,
+    }))
 }
 
 #[cfg(feature = "model")]
 fn static_avatar_url(user_id: UserId, hash: Option<&ImageHash>) -> Option<String> {
-    hash.map(|hash| cdn!("/avatars/{}/{}.webp?size=1024", user_id, hash))
+    hash.map(|&hash| {
+        let asset = Asset::UserAvatar {
+            user_id,
+            hash,
+        };
+        cdn_url(CdnUrl::new(asset).format(ImageFormat::WebP).size(1024))
+    })
 }
 
 #[cfg(feature = "model")]
 fn banner_url(user_id: UserId, hash: Option<&ImageHash>) -> Option<String> {
-    hash.map(|hash| {
-        let ext = if hash.is_animated() { "gif" } else { "webp" };
-        cdn!("/banners/{}/{}.{}?size=1024", user_id, hash, ext)
+    hash.map(|&hash| {
+        let asset = Asset::UserBanner {
+            user_id,
+            hash,
+        };
+        cdn_url(CdnUrl::new(asset).size(1024))
     })
 }
 
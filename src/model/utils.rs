@@ -6,6 +6,8 @@ use serde_cow::CowStr;
 use small_fixed_array::FixedString;
 
 use super::prelude::*;
+#[cfg(feature = "model")]
+use crate::cdn::{Asset, CdnUrl, ImageFormat};
 
 pub fn default_true() -> bool {
     true
@@ -17,29 +19,64 @@ pub fn is_false(v: &bool) -> bool {
     !v
 }
 
+/// Builds the URL of an asset, for model methods which only use supported formats and sizes.
+#[cfg(feature = "model")]
+pub(super) fn cdn_url(url: CdnUrl) -> String {
+    url.build().expect("model asset URLs should use supported formats and sizes")
+}
+
 #[cfg(feature = "model")]
 pub(super) fn avatar_url(
     guild_id: Option<GuildId>,
     user_id: UserId,
     hash: Option<&ImageHash>,
 ) -> Option<String> {
-    hash.map(|hash| {
-        let ext = if hash.is_animated() { "gif" } else { "webp" };
-
-        if let Some(guild_id) = guild_id {
-            cdn!("/guilds/{}/users/{}/avatars/{}.{}?size=1024", guild_id, user_id, hash, ext)
+    hash.map(|&hash| {
+        let asset = if let Some(guild_id) = guild_id {
+            Asset::GuildMemberAvatar {
+                guild_id,
+                user_id,
+                hash,
+            }
         } else {
-            cdn!("/avatars/{}/{}.{}?size=1024", user_id, hash, ext)
-        }
+            Asset::UserAvatar {
+                user_id,
+                hash,
+            }
+        };
+
+        cdn_url(CdnUrl::new(asset).size(1024))
     })
 }
 
+/// Builds the URL of a guild banner in WebP format. Banners are stored as strings rather than
+/// [`ImageHash`]es, so one which can't be parsed is put in the URL as-is.
 #[cfg(feature = "model")]
-pub(super) fn icon_url(id: GuildId, icon: Option<&ImageHash>) -> Option<String> {
-    icon.map(|icon| {
-        let ext = if icon.is_animated() { "gif" } else { "webp" };
+pub(super) fn banner_url(guild_id: GuildId, banner: &str, size: Option<u16>) -> String {
+    let Ok(hash) = banner.parse() else {
+        let size = size.map(|size| format!("?size={size}")).unwrap_or_default();
+        return format!("{}/banners/{guild_id}/{banner}.webp{size}", crate::constants::CDN_URL);
+    };
+
+    let mut url = CdnUrl::new(Asset::GuildBanner {
+        guild_id,
+        hash,
+    })
+    .format(ImageFormat::WebP);
+    if let Some(size) = size {
+        url = url.size(size);
+    }
 
-        cdn!("/icons/{}/{}.{}", id, icon, ext)
+    cdn_url(url)
+}
+
+#[cfg(feature = "model")]
+pub(super) fn icon_url(guild_id: GuildId, icon: Option<&ImageHash>) -> Option<String> {
+    icon.map(|&hash| {
+        cdn_url(CdnUrl::new(Asset::GuildIcon {
+            guild_id,
+            hash,
+        }))
     })
 }
 
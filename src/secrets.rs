@@ -52,6 +52,12 @@ impl typesize::TypeSize for SecretString {
 pub struct Token(SecretString);
 
 impl Token {
+    /// An empty token for shards that never connect, such as those replaying recorded events.
+    #[cfg(feature = "gateway")]
+    pub(crate) fn placeholder() -> Self {
+        Self(SecretString::new(Arc::from("")))
+    }
+
     /// Fetch and parses the token out of the given environment variable.
     ///
     /// # Errors